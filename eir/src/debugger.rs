@@ -0,0 +1,463 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, Write},
+};
+
+use x86asm::instruction::{mnemonic::Mnemonic, operand::register::Register, Instruction};
+
+use crate::emulator::{cpu::REGISTERS, decode, exit::ExitReason, Emulator};
+
+// instructions shown by `disassemble` by default
+const DISASSEMBLE_COUNT: usize = 8;
+
+const HELP: &str = "\
+break <loc>          set a breakpoint at an address or symbol (b)
+delete [n]           delete breakpoint n, or all of them (d)
+info breakpoints     list breakpoints (i b)
+info registers       print the registers (i r)
+step [n]             execute n instructions (s, si)
+next [n]             like step, but run called functions to completion (n, ni)
+finish               run until the current function returns
+continue             run until a breakpoint or the end of the program (c)
+x/<n><f><u> <addr>   examine memory; f is x, d or u, and u is b, h, w or g
+print <expr>         print the value of an expression (p)
+set $<reg> = <expr>  change a register
+disassemble [loc]    disassemble around rip or at loc (disas)
+quit                 leave the debugger (q)
+expressions are numbers, symbols and $registers joined with + and -";
+
+// gdb-like REPL on top of the emulator
+pub struct Debugger<'a> {
+    emu: &'a mut Emulator,
+    // by breakpoint number
+    breakpoints: BTreeMap<usize, u64>,
+    next_breakpoint: usize,
+    // set once the program has ended
+    exit: Option<ExitReason>,
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(emu: &'a mut Emulator) -> Self {
+        Self {
+            emu,
+            breakpoints: BTreeMap::new(),
+            next_breakpoint: 1,
+            exit: None,
+        }
+    }
+
+    // reads commands from stdin until `quit` or EOF, and returns how the program
+    // ended if it did
+    pub fn run(&mut self) -> Option<ExitReason> {
+        self.print_location();
+
+        let stdin = io::stdin();
+        let mut last_line = String::new();
+        loop {
+            print!("(eir) ");
+            io::stdout().flush().unwrap();
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+                break;
+            }
+
+            // an empty line repeats the last command
+            let line = match line.trim() {
+                "" => last_line.clone(),
+                line => line.to_string(),
+            };
+            match self.execute(&line) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(message) => println!("{}", message),
+            }
+            last_line = line;
+        }
+
+        self.exit.clone()
+    }
+
+    // returns false on `quit`
+    pub fn execute(&mut self, line: &str) -> Result<bool, String> {
+        let (command, args) = match line.find(char::is_whitespace) {
+            Some(pos) => (&line[..pos], line[pos..].trim()),
+            None => (line, ""),
+        };
+
+        if let Some(format) = command.strip_prefix("x/") {
+            self.examine(format, args)?;
+            return Ok(true);
+        }
+        match command {
+            "" => {}
+            "b" | "break" => self.add_breakpoint(args)?,
+            "d" | "delete" => self.delete_breakpoint(args)?,
+            "i" | "info" => self.info(args)?,
+            "s" | "si" | "step" | "stepi" => {
+                let mut count = parse_count(args)?;
+                self.resume(|_, _| {
+                    count -= 1;
+                    count == 0
+                })?;
+            }
+            "n" | "ni" | "next" | "nexti" => {
+                for _ in 0..parse_count(args)? {
+                    self.next()?;
+                }
+            }
+            "finish" => {
+                let mut depth = 0;
+                self.resume(|_, inst| match inst.mnenomic {
+                    Mnemonic::Call => {
+                        depth += 1;
+                        false
+                    }
+                    Mnemonic::Ret if depth == 0 => true,
+                    Mnemonic::Ret => {
+                        depth -= 1;
+                        false
+                    }
+                    _ => false,
+                })?;
+            }
+            "c" | "continue" => self.resume(|_, _| false)?,
+            "x" => self.examine("", args)?,
+            "p" | "print" => {
+                let value = self.eval(args)?;
+                println!("{:#x} ({})", value, value as i64);
+            }
+            "set" => self.set(args)?,
+            "disas" | "disassemble" => self.disassemble(args)?,
+            "h" | "help" => println!("{}", HELP),
+            "q" | "quit" => return Ok(false),
+            _ => return Err(format!("Undefined command: \"{}\". Try \"help\".", command)),
+        }
+        Ok(true)
+    }
+
+    fn add_breakpoint(&mut self, args: &str) -> Result<(), String> {
+        let addr = match args {
+            "" => self.emu.cpu.get_rip(),
+            _ => self.eval(args)?,
+        };
+        let number = self.next_breakpoint;
+        self.next_breakpoint += 1;
+        self.breakpoints.insert(number, addr);
+        println!("Breakpoint {} at {}", number, self.describe(addr));
+        Ok(())
+    }
+
+    fn delete_breakpoint(&mut self, args: &str) -> Result<(), String> {
+        if args.is_empty() {
+            self.breakpoints.clear();
+            return Ok(());
+        }
+        let number = args
+            .parse()
+            .map_err(|_| format!("Invalid breakpoint number: {}", args))?;
+        match self.breakpoints.remove(&number) {
+            Some(_) => Ok(()),
+            None => Err(format!("No breakpoint number {}.", number)),
+        }
+    }
+
+    fn info(&self, args: &str) -> Result<(), String> {
+        match args {
+            "b" | "break" | "breakpoints" => {
+                if self.breakpoints.is_empty() {
+                    println!("No breakpoints.");
+                }
+                for (number, addr) in &self.breakpoints {
+                    println!("{:<4} {}", number, self.describe(*addr));
+                }
+            }
+            "r" | "registers" => {
+                for reg in REGISTERS.iter() {
+                    let value = self.emu.cpu.get_register64(reg);
+                    println!("{:<8} {:#018x}  {}", reg, value, value as i64);
+                }
+                let rip = self.emu.cpu.get_rip();
+                println!("{:<8} {:#018x}  {}", "rip", rip, self.describe(rip));
+                self.emu.dump_flags();
+            }
+            _ => return Err(format!("Undefined info command: \"{}\".", args)),
+        }
+        Ok(())
+    }
+
+    // steps over calls
+    fn next(&mut self) -> Result<(), String> {
+        let rip = self.emu.cpu.get_rip();
+        let (inst, inst_len) = self.emu.decode_at(rip).map_err(|fault| fault.to_string())?;
+        if inst.mnenomic != Mnemonic::Call {
+            return self.resume(|_, _| true);
+        }
+
+        // the call has returned when the stack is back to its current depth, which
+        // tells recursive calls apart
+        let return_addr = rip + inst_len as u64;
+        let rsp = self.emu.cpu.get_register64(&Register::Rsp);
+        self.resume(|emu, _| {
+            emu.cpu.get_rip() == return_addr && emu.cpu.get_register64(&Register::Rsp) >= rsp
+        })
+    }
+
+    // executes instructions until `done` returns true, a breakpoint is hit or the
+    // program ends. `done` is called after each instruction with the instruction
+    fn resume(
+        &mut self,
+        mut done: impl FnMut(&Emulator, &Instruction) -> bool,
+    ) -> Result<(), String> {
+        if self.exit.is_some() {
+            return Err("The program is not being run.".to_string());
+        }
+
+        loop {
+            let rip = self.emu.cpu.get_rip();
+            let inst = self.emu.decode_at(rip).ok().map(|(inst, _)| inst);
+            if let Some(reason) = self.emu.step() {
+                if let ExitReason::Fault(_) = reason {
+                    self.print_location();
+                }
+                println!("{}", reason);
+                self.exit = Some(reason);
+                return Ok(());
+            }
+
+            if inst.is_some_and(|inst| done(&*self.emu, &inst)) {
+                break;
+            }
+            let rip = self.emu.cpu.get_rip();
+            if let Some((number, _)) = self.breakpoints.iter().find(|(_, addr)| **addr == rip) {
+                println!("Breakpoint {}, {}", number, self.describe(rip));
+                break;
+            }
+        }
+        self.print_location();
+        Ok(())
+    }
+
+    fn examine(&self, format: &str, args: &str) -> Result<(), String> {
+        let digits = format.chars().take_while(char::is_ascii_digit).count();
+        let count: u64 = match digits {
+            0 => 1,
+            _ => format[..digits]
+                .parse()
+                .map_err(|_| format!("Invalid count: {}", &format[..digits]))?,
+        };
+        let mut radix = 'x';
+        let mut size = 4;
+        for c in format[digits..].chars() {
+            match c {
+                'x' | 'd' | 'u' => radix = c,
+                'b' => size = 1,
+                'h' => size = 2,
+                'w' => size = 4,
+                'g' => size = 8,
+                _ => return Err(format!("Invalid format letter '{}'.", c)),
+            }
+        }
+        let addr = self.eval(args)?;
+
+        let per_line = if size <= 2 { 8 } else { 16 / size };
+        let mut output = String::new();
+        for i in 0..count {
+            let unit_addr = addr.wrapping_add(i.wrapping_mul(size));
+            if i % per_line == 0 {
+                if i != 0 {
+                    output.push('\n');
+                }
+                output.push_str(&format!("{}:", self.describe(unit_addr)));
+            }
+
+            let mut value = 0;
+            for byte in 0..size {
+                let byte_addr = unit_addr.wrapping_add(byte);
+                let byte_value = self
+                    .emu
+                    .mmu
+                    .get_memory8(byte_addr as usize)
+                    .map_err(|_| format!("Cannot access memory at address {:#x}", byte_addr))?;
+                value |= (byte_value as u64) << (byte * 8);
+            }
+            let value = match radix {
+                'x' => format!("{:#0width$x}", value, width = size as usize * 2 + 2),
+                'd' => {
+                    let shift = 64 - size * 8;
+                    format!("{}", ((value << shift) as i64) >> shift)
+                }
+                _ => format!("{}", value),
+            };
+            output.push('\t');
+            output.push_str(&value);
+        }
+        println!("{}", output);
+        Ok(())
+    }
+
+    fn set(&mut self, args: &str) -> Result<(), String> {
+        let args = args.strip_prefix("var ").unwrap_or(args);
+        let (lhs, rhs) = match args.find('=') {
+            Some(pos) => (args[..pos].trim(), args[pos + 1..].trim()),
+            None => return Err("Usage: set $<reg> = <expr>".to_string()),
+        };
+        let value = self.eval(rhs)?;
+        match lhs.strip_prefix('$') {
+            Some("rip") | Some("pc") => self.emu.cpu.set_rip(value),
+            Some(name) => {
+                let reg =
+                    find_register(name).ok_or_else(|| format!("Invalid register: ${}", name))?;
+                self.emu.cpu.set_register64(&reg, value);
+            }
+            None => return Err("Only registers can be set.".to_string()),
+        }
+        Ok(())
+    }
+
+    fn disassemble(&self, args: &str) -> Result<(), String> {
+        let rip = self.emu.cpu.get_rip();
+        let insts = match args {
+            "" => {
+                // x86 can not be decoded backwards, so start from the function
+                let start = match self.emu.symbolize(rip) {
+                    Some((_, offset)) if offset <= 0x100 => rip - offset,
+                    _ => rip,
+                };
+                let mut insts = self.decode_from(start, rip, DISASSEMBLE_COUNT / 2);
+                let before = insts.iter().filter(|(addr, _, _)| *addr < rip).count();
+                insts.drain(..before.saturating_sub(DISASSEMBLE_COUNT / 2));
+                insts
+            }
+            _ => {
+                let start = self.eval(args)?;
+                self.decode_from(start, start, DISASSEMBLE_COUNT)
+            }
+        };
+
+        for (addr, inst, inst_len) in insts {
+            let marker = if addr == rip { "=>" } else { "  " };
+            println!(
+                "{} {}:\t{}",
+                marker,
+                self.describe(addr),
+                self.format_inst(addr, &inst, inst_len)
+            );
+        }
+        Ok(())
+    }
+
+    // decodes instructions from `start` until `count` of them are at or after `until`
+    fn decode_from(&self, start: u64, until: u64, count: usize) -> Vec<(u64, Instruction, usize)> {
+        let mut insts = Vec::new();
+        let mut addr = start;
+        let mut count_after = 0;
+        while count_after < count {
+            let (inst, inst_len) = match self.emu.decode_at(addr) {
+                Ok(decoded) => decoded,
+                Err(_) => break,
+            };
+            if addr >= until {
+                count_after += 1;
+            }
+            insts.push((addr, inst, inst_len));
+            addr += inst_len as u64;
+        }
+        insts
+    }
+
+    fn print_location(&self) {
+        let rip = self.emu.cpu.get_rip();
+        match self.emu.decode_at(rip) {
+            Ok((inst, inst_len)) => println!(
+                "{}:\t{}",
+                self.describe(rip),
+                self.format_inst(rip, &inst, inst_len)
+            ),
+            Err(fault) => println!("{}:\t{}", self.describe(rip), fault),
+        }
+    }
+
+    // relative targets are shown with their symbol
+    fn format_inst(&self, addr: u64, inst: &Instruction, inst_len: usize) -> String {
+        decode::format_inst(addr, inst, inst_len, |target| self.describe(target))
+    }
+
+    // e.g. `0x401005 <main+5>`
+    fn describe(&self, addr: u64) -> String {
+        match self.emu.symbolize(addr) {
+            Some((name, 0)) => format!("{:#x} <{}>", addr, name),
+            Some((name, offset)) => format!("{:#x} <{}+{}>", addr, name, offset),
+            None => format!("{:#x}", addr),
+        }
+    }
+
+    fn eval(&self, expr: &str) -> Result<u64, String> {
+        let expr = expr.trim();
+        if expr.is_empty() {
+            return Err("Argument required.".to_string());
+        }
+
+        let mut value: u64 = 0;
+        let mut negative = false;
+        let mut term_start = 0;
+        for (pos, c) in expr.char_indices().chain(Some((expr.len(), '+'))) {
+            if (c != '+' && c != '-') || pos == 0 {
+                continue;
+            }
+            let term = self.eval_term(expr[term_start..pos].trim())?;
+            value = if negative {
+                value.wrapping_sub(term)
+            } else {
+                value.wrapping_add(term)
+            };
+            negative = c == '-';
+            term_start = pos + 1;
+        }
+        Ok(value)
+    }
+
+    fn eval_term(&self, term: &str) -> Result<u64, String> {
+        // `break *addr` as in gdb
+        let term = term.strip_prefix('*').unwrap_or(term);
+        if let Some(name) = term.strip_prefix('$') {
+            return match name {
+                "rip" | "pc" => Ok(self.emu.cpu.get_rip()),
+                "sp" => Ok(self.emu.cpu.get_register64(&Register::Rsp)),
+                _ => find_register(name)
+                    .map(|reg| self.emu.cpu.get_register64(&reg))
+                    .ok_or_else(|| format!("Invalid register: ${}", name)),
+            };
+        }
+        if let Some(hex) = term.strip_prefix("0x") {
+            return u64::from_str_radix(hex, 16).map_err(|_| format!("Invalid number: {}", term));
+        }
+        if term.starts_with(|c: char| c.is_ascii_digit()) {
+            return term
+                .parse()
+                .map_err(|_| format!("Invalid number: {}", term));
+        }
+        self.emu
+            .symbols
+            .get(term)
+            .copied()
+            .ok_or_else(|| format!("No symbol \"{}\" in current context.", term))
+    }
+}
+
+fn find_register(name: &str) -> Option<Register> {
+    REGISTERS
+        .iter()
+        .find(|reg| reg.to_string() == name)
+        .cloned()
+}
+
+fn parse_count(args: &str) -> Result<usize, String> {
+    match args {
+        "" => Ok(1),
+        _ => args
+            .parse()
+            .ok()
+            .filter(|count| *count != 0)
+            .ok_or_else(|| format!("Invalid count: {}", args)),
+    }
+}
@@ -1,22 +1,70 @@
+pub mod cache;
 pub mod cpu;
 pub mod decode;
 pub mod execute;
+pub mod exit;
+pub mod fault;
+pub mod files;
 pub mod flags;
+pub mod io;
 pub mod mmu;
+pub mod stack;
+pub mod syscall;
 pub mod value;
 
-use std::fs;
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    fs,
+    hash::{BuildHasher, Hasher},
+    mem::size_of,
+};
 
+use cache::BlockCache;
 use cpu::{Cpu, Flags};
-use elfen::elf::Elf;
-use mmu::Mmu;
+use elfen::{
+    dynamic::{self, Dyn},
+    elf::Elf,
+    header,
+    rel::{self, Rela},
+    segment, symbol,
+};
+use exit::ExitReason;
+use fault::Fault;
+use files::FileTable;
+use io::{HostIo, Io};
+use mmu::{Mmu, Perms};
+use stack::{AT_ENTRY, AT_PHDR, AT_PHENT, AT_PHNUM};
+use syscall::Heap;
 use x86asm::instruction::operand::register::Register;
 
+const PAGE_SIZE: u64 = 0x1000;
+
 pub struct Emulator {
     pub cpu: Cpu,
     pub mmu: Mmu,
+    pub cache: BlockCache,
+
+    pub io: Box<dyn Io>,
+    pub files: FileTable,
+    pub heap: Heap,
+    // addresses of the symbols in the loaded ELF's .symtab
+    pub symbols: HashMap<String, u64>,
+    // auxiliary vector entries describing the loaded ELF, for `init_stack`
+    pub auxv: Vec<(u64, u64)>,
 
     pub dump_state: bool,
+    // `run` stops after this many instructions
+    pub instruction_limit: Option<u64>,
+    pub stats: Stats,
+}
+
+// counters for `eir --stats`
+#[derive(Debug, Default)]
+pub struct Stats {
+    // instructions executed without a fault
+    pub instructions: u64,
+    // basic blocks decoded, counting ones decoded again after their code changed
+    pub blocks: u64,
 }
 
 impl Emulator {
@@ -24,66 +72,258 @@ impl Emulator {
         let mut emu = Self {
             cpu: Cpu::new(),
             mmu: Mmu::new(),
+            cache: BlockCache::default(),
+            io: Box::new(HostIo),
+            files: FileTable::default(),
+            heap: Heap::default(),
+            symbols: HashMap::new(),
+            auxv: Vec::new(),
             dump_state: false,
+            instruction_limit: None,
+            stats: Stats::default(),
         };
         emu.cpu.set_rip(rip);
         emu.cpu.set_register64(&Register::Rsp, rsp);
-        emu.mmu.add_segment(0, vec![0; rsp as usize]);
 
         return emu;
     }
 
-    pub fn load_elf(&mut self, path: &str) {
-        let file_data = fs::read(path).expect("Failed to read file.");
-        let elf = Elf::read_from_file(path);
+    pub fn load_elf(&mut self, path: &str) -> Result<(), String> {
+        let file_data = fs::read(path).map_err(|err| err.to_string())?;
+        self.load_elf_bytes(&file_data)
+    }
+
+    pub fn load_elf_bytes(&mut self, file_data: &[u8]) -> Result<(), String> {
+        let elf = Elf::read_from_bytes(file_data);
+
+        // a PIE is linked at 0 and can be placed anywhere
+        let base = match elf.header.get_filetype() {
+            header::Type::Dyn => random_base(),
+            _ => 0,
+        };
 
+        let mut image_end = 0;
         for segment in &elf.segments {
+            if segment.get_type() != segment::Type::Load {
+                continue;
+            }
+
             let offset = segment.offset as usize;
             let size = segment.file_size as usize;
-            let virt_addr = segment.virt_addr as usize;
-            let data = file_data[offset..(offset + size)].to_vec();
-            self.mmu.add_segment(virt_addr, data);
+            let data = offset
+                .checked_add(size)
+                .and_then(|end| file_data.get(offset..end))
+                .ok_or_else(|| {
+                    format!(
+                        "segment at offset {:#x} of size {:#x} is past the end of the file",
+                        offset, size
+                    )
+                })?;
+            let virt_addr = (base + segment.virt_addr) as usize;
+            // the rest of the segment (e.g. .bss) is not in the file and stays zero
+            let perms = Perms::from_segment_flags(segment.flags);
+            self.mmu.map(virt_addr, segment.memory_size as usize, perms);
+            self.mmu
+                .poke(virt_addr, data)
+                .map_err(|fault| fault.to_string())?;
+            image_end = image_end.max(virt_addr as u64 + segment.memory_size);
+        }
+        // the program break starts after the image
+        self.heap = Heap::new(image_end);
+
+        if let Some(dynamic) = elf
+            .segments
+            .iter()
+            .find(|segment| segment.get_type() == segment::Type::Dynamic)
+        {
+            self.relocate(base, base + dynamic.virt_addr)?;
         }
 
-        let entrypoint = elf.header.entrypoint;
+        self.load_symbols(&elf, base);
+
+        let entrypoint = base + elf.header.entrypoint;
         self.cpu.set_rip(entrypoint);
+
+        self.auxv = vec![
+            (AT_PHENT, elf.header.program_header_size as u64),
+            (AT_PHNUM, elf.header.program_header_num as u64),
+            (AT_ENTRY, entrypoint),
+        ];
+        // the program headers as mapped by the segment which contains them
+        let phoff = elf.header.program_header_offset;
+        if let Some(segment) = elf.segments.iter().find(|segment| {
+            segment.get_type() == segment::Type::Load
+                && segment.offset <= phoff
+                && phoff < segment.offset + segment.file_size
+        }) {
+            let phdr = base + segment.virt_addr + (phoff - segment.offset);
+            self.auxv.push((AT_PHDR, phdr));
+        }
+        Ok(())
+    }
+
+    // symbols are only for showing addresses, so a file without them is loaded as is
+    fn load_symbols(&mut self, elf: &Elf, base: u64) {
+        let symbols = match elf
+            .get_section(".symtab")
+            .and_then(|symtab| symtab.data.as_symbols())
+        {
+            Some(symbols) => symbols,
+            None => return,
+        };
+        let strtab = match elf
+            .get_section(".strtab")
+            .and_then(|strtab| strtab.data.as_strtab())
+        {
+            Some(strtab) => strtab,
+            None => return,
+        };
+        for symbol in symbols {
+            let name = strtab.get(symbol.name as usize);
+            let typ = symbol.get_type();
+            if name.is_empty() || typ == symbol::Type::Section || typ == symbol::Type::File {
+                continue;
+            }
+            if let symbol::IndexType::Index(_) = symbol.get_index_type() {
+                self.symbols.insert(name, base + symbol.value);
+            }
+        }
+    }
+
+    // the symbol at or closest below `addr` with only mapped pages in between,
+    // with the offset from it
+    pub fn symbolize(&self, addr: u64) -> Option<(&str, u64)> {
+        self.symbols
+            .iter()
+            .filter(|(_, symbol_addr)| {
+                **symbol_addr <= addr
+                    && (**symbol_addr..=addr)
+                        .step_by(PAGE_SIZE as usize)
+                        .chain(Some(addr))
+                        .all(|page_addr| self.mmu.is_mapped(page_addr as usize))
+            })
+            .max_by_key(|(name, symbol_addr)| (**symbol_addr, std::cmp::Reverse(*name)))
+            .map(|(name, symbol_addr)| (name.as_str(), addr - symbol_addr))
     }
 
-    pub fn run(&mut self) {
+    // applies R_X86_64_RELATIVE relocations listed in .dynamic at `dynamic_addr`
+    // in place of a dynamic loader
+    fn relocate(&mut self, base: u64, dynamic_addr: u64) -> Result<(), String> {
+        let read64 = |mmu: &Mmu, addr: usize| mmu.get_memory64(addr).map_err(|f| f.to_string());
+        let mut relas_addr = 0;
+        let mut relas_size = 0;
+        let mut entry_addr = dynamic_addr as usize;
+        loop {
+            let tag = dynamic::Tag::from(read64(&self.mmu, entry_addr)? as i64);
+            let val = read64(&self.mmu, entry_addr + 8)?;
+            match tag {
+                dynamic::Tag::Null => break,
+                dynamic::Tag::Rela => relas_addr = base + val,
+                dynamic::Tag::RelaSz => relas_size = val,
+                _ => {}
+            }
+            entry_addr += size_of::<Dyn>();
+        }
+
+        let rela_size = size_of::<Rela>() as u64;
+        for rela_addr in (relas_addr..relas_addr + relas_size).step_by(rela_size as usize) {
+            let rela_addr = rela_addr as usize;
+            let rela = Rela {
+                offset: read64(&self.mmu, rela_addr)?,
+                info: read64(&self.mmu, rela_addr + 8)?,
+                addend: read64(&self.mmu, rela_addr + 16)? as i64,
+            };
+            match rela.get_type() {
+                rel::Type::Relative => {
+                    // the place may be read-only, like .got after relocation
+                    let value = base.wrapping_add(rela.addend as u64);
+                    self.mmu
+                        .poke((base + rela.offset) as usize, &value.to_le_bytes())
+                        .map_err(|f| f.to_string())?;
+                }
+                typ => return Err(format!("unsupported relocation: {}", typ)),
+            }
+        }
+        Ok(())
+    }
+
+    // loads a flat image such as `herja --oformat binary` output, which starts at `addr`
+    pub fn load_binary(&mut self, path: &str, addr: u64) -> Result<(), String> {
+        let data = fs::read(path).map_err(|err| err.to_string())?;
+        // a flat image has no process stack, and uses the memory below rsp
+        let rsp = self.cpu.get_register64(&Register::Rsp);
+        self.mmu.map(0, rsp as usize, Perms::RW);
+        // a flat image has no permissions of its own
+        self.mmu.map(addr as usize, data.len(), Perms::RWX);
+        self.mmu
+            .poke(addr as usize, &data)
+            .map_err(|fault| fault.to_string())?;
+        self.cpu.set_rip(addr);
+        Ok(())
+    }
+
+    pub fn run(&mut self) -> ExitReason {
+        self.run_with(|_, _| {})
+    }
+
+    // like `run`, calling `after_step` with the address of each instruction once
+    // it has been executed, or has faulted
+    pub fn run_with(&mut self, mut after_step: impl FnMut(&mut Self, u64)) -> ExitReason {
         if self.dump_state {
             self.dump();
         }
+        let mut executed = 0;
         loop {
-            match self.decode() {
-                Ok(inst) => {
-                    if self.dump_state {
-                        println!("Decoded: {:?}", inst);
-                    }
-                    self.exec(inst);
-                    if self.dump_state {
-                        self.dump();
-                    }
-                }
-                Err(err) => {
-                    self.dump();
-                    println!("Error: {}", err);
-                    std::process::exit(1);
-                }
+            if self
+                .instruction_limit
+                .is_some_and(|limit| executed >= limit)
+            {
+                return ExitReason::InstructionLimit;
             }
+            let rip = self.cpu.get_rip();
+            let exit = self.step();
+            after_step(self, rip);
+            if let Some(reason) = exit {
+                return reason;
+            }
+            executed += 1;
+        }
+    }
+
+    // executes a single instruction. on a fault, rip is left at the instruction
+    // which caused it
+    pub fn step(&mut self) -> Option<ExitReason> {
+        let rip = self.cpu.get_rip();
+        let inst = match self.decode() {
+            Ok(inst) => inst,
+            Err(fault) => return Some(ExitReason::Fault(fault)),
+        };
+        if self.dump_state {
+            println!("Decoded: {:?}", inst);
+        }
+        let exit = self.exec(inst, rip);
+        match exit {
+            Some(ExitReason::Fault(_)) => self.cpu.set_rip(rip),
+            _ => self.stats.instructions += 1,
         }
+        if self.dump_state {
+            self.dump();
+        }
+        exit
     }
 
-    pub fn push64(&mut self, value: u64) -> Result<(), String> {
-        let new_rsp = self.cpu.get_register64(&Register::Rsp) - 8;
+    pub fn push64(&mut self, value: u64) -> Result<(), Fault> {
+        let new_rsp = self.cpu.get_register64(&Register::Rsp).wrapping_sub(8);
         self.mmu.set_memory64(new_rsp as usize, value)?;
         self.cpu.set_register64(&Register::Rsp, new_rsp);
         Ok(())
     }
 
-    pub fn pop64(&mut self) -> Result<u64, String> {
+    pub fn pop64(&mut self) -> Result<u64, Fault> {
         let rsp = self.cpu.get_register64(&Register::Rsp);
-        self.cpu.set_register64(&Register::Rsp, rsp + 8);
-        self.mmu.get_memory64(rsp as usize)
+        let value = self.mmu.get_memory64(rsp as usize)?;
+        self.cpu.set_register64(&Register::Rsp, rsp.wrapping_add(8));
+        Ok(value)
     }
 
     pub fn dump(&self) {
@@ -99,9 +339,10 @@ impl Emulator {
 
     pub fn dump_flags(&self) {
         println!(
-            "flag: [Carry: {}, Parity: {}, Zero: {}, Sign: {}, Overflow: {}]",
+            "flag: [Carry: {}, Parity: {}, Adjust: {}, Zero: {}, Sign: {}, Overflow: {}]",
             self.cpu.get_flag(Flags::CF),
             self.cpu.get_flag(Flags::PF),
+            self.cpu.get_flag(Flags::AF),
             self.cpu.get_flag(Flags::ZF),
             self.cpu.get_flag(Flags::SF),
             self.cpu.get_flag(Flags::OF),
@@ -139,3 +380,14 @@ impl Emulator {
         }
     }
 }
+
+// where a PIE is loaded, like Linux's ELF_ET_DYN_BASE plus a random
+// page-aligned offset
+fn random_base() -> u64 {
+    const PIE_BASE: u64 = 0x5555_5555_4000;
+    PIE_BASE + (random_u64() % 0x10000) * PAGE_SIZE
+}
+
+fn random_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
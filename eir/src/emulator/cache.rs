@@ -0,0 +1,98 @@
+use std::{collections::HashMap, rc::Rc};
+
+use x86asm::instruction::{mnemonic::Mnemonic, Instruction};
+
+use super::PAGE_SIZE;
+
+// a block ends at the first instruction which may not continue with the next
+const MAX_BLOCK_LEN: usize = 64;
+
+// instructions decoded from consecutive addresses, up to a branch
+pub struct Block {
+    // each instruction with its address and length
+    pub insts: Vec<(u64, Instruction, usize)>,
+}
+
+impl Block {
+    pub fn end(&self) -> u64 {
+        self.insts
+            .last()
+            .map_or(0, |(addr, _, len)| addr + *len as u64)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.insts.len() >= MAX_BLOCK_LEN
+            || self
+                .insts
+                .last()
+                .is_some_and(|(_, inst, _)| ends_block(&inst.mnenomic))
+    }
+}
+
+// decoded blocks keyed by their first address
+#[derive(Default)]
+pub struct BlockCache {
+    blocks: HashMap<u64, Rc<Block>>,
+    // first addresses of the blocks with code on each page
+    pages: HashMap<u64, Vec<u64>>,
+    // the block being executed and the index of its next instruction
+    current: Option<(Rc<Block>, usize)>,
+}
+
+impl BlockCache {
+    // the next instruction of the current block if it is at `rip`, which it is
+    // unless the last one branched
+    pub fn next(&mut self, rip: u64) -> Option<(Instruction, usize)> {
+        let (block, index) = self.current.as_mut()?;
+        match block.insts.get(*index) {
+            Some((addr, inst, len)) if *addr == rip => {
+                *index += 1;
+                Some((inst.clone(), *len))
+            }
+            _ => None,
+        }
+    }
+
+    // makes the block at `addr` current, if it has been decoded
+    pub fn enter(&mut self, addr: u64) -> bool {
+        self.current = self.blocks.get(&addr).map(|block| (block.clone(), 0));
+        self.current.is_some()
+    }
+
+    pub fn insert(&mut self, block: Block) {
+        let start = block.insts[0].0;
+        for page in start / PAGE_SIZE..=(block.end() - 1) / PAGE_SIZE {
+            self.pages.entry(page).or_default().push(start);
+        }
+        self.blocks.insert(start, Rc::new(block));
+    }
+
+    // drops the blocks with code on the page, which has been written or unmapped
+    pub fn invalidate_page(&mut self, page: u64) {
+        for start in self.pages.remove(&page).unwrap_or_default() {
+            if let Some(block) = self.blocks.remove(&start) {
+                // the rest of the current block may be stale
+                if self
+                    .current
+                    .as_ref()
+                    .is_some_and(|(current, _)| Rc::ptr_eq(current, &block))
+                {
+                    self.current = None;
+                }
+            }
+        }
+    }
+}
+
+// whether the instruction may branch or change the memory map
+fn ends_block(mnemonic: &Mnemonic) -> bool {
+    matches!(
+        mnemonic,
+        Mnemonic::Call
+            | Mnemonic::Je
+            | Mnemonic::Jmp
+            | Mnemonic::Ret
+            | Mnemonic::Hlt
+            | Mnemonic::Syscall
+    )
+}
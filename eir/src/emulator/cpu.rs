@@ -2,6 +2,27 @@ use x86asm::instruction::operand::register::{self, Register};
 
 use super::value::Value;
 
+// the general purpose registers, in the order of `info registers` and of gdb's
+// amd64 `g` packet
+pub const REGISTERS: [Register; 16] = [
+    Register::Rax,
+    Register::Rbx,
+    Register::Rcx,
+    Register::Rdx,
+    Register::Rsi,
+    Register::Rdi,
+    Register::Rbp,
+    Register::Rsp,
+    Register::R8,
+    Register::R9,
+    Register::R10,
+    Register::R11,
+    Register::R12,
+    Register::R13,
+    Register::R14,
+    Register::R15,
+];
+
 #[derive(Debug, Default)]
 pub struct Cpu {
     regs: [u64; 16],
@@ -11,9 +32,11 @@ pub struct Cpu {
     rip: u64,
 }
 
+#[derive(Clone, Copy)]
 pub enum Flags {
     CF = 0,
     PF = 2,
+    AF = 4,
     ZF = 6,
     SF = 7,
     OF = 11,
@@ -39,6 +62,7 @@ impl Cpu {
         }
         match value {
             Value::Value8(value) => self.set_register8(reg, value),
+            // writing a 32-bit register clears the upper half
             Value::Value32(value) => self.set_register64(reg, value as u64),
             Value::Value64(value) => self.set_register64(reg, value),
         }
@@ -48,8 +72,9 @@ impl Cpu {
         self.regs[reg_num(reg)] as u8
     }
 
+    // the other 56 bits are kept
     pub fn set_register8(&mut self, reg: &Register, value: u8) {
-        self.regs[reg_num(reg)] = (self.regs[reg_num(reg)] & 0xffffff00) | value as u64;
+        self.regs[reg_num(reg)] = (self.regs[reg_num(reg)] & !0xff) | value as u64;
     }
 
     pub fn get_register64(&self, reg: &Register) -> u64 {
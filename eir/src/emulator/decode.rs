@@ -1,26 +1,101 @@
-use x86asm::{decode, instruction::Instruction};
+use x86asm::{
+    decode::{self, DecodeErrorKind},
+    instruction::{
+        operand::{offset::Offset, Operand},
+        Instruction,
+    },
+};
 
-use crate::emulator::Emulator;
+use crate::emulator::{cache::Block, fault::Fault, Emulator};
+
+// an x86 instruction is at most 15 bytes long
+const MAX_INST_LEN: usize = 15;
 
 impl Emulator {
-    pub fn decode(&mut self) -> Result<Instruction, String> {
-        let rip = self.cpu.get_rip() as usize;
-        let mut bytes = Vec::new();
-        for i in 0..8 {
-            match self.mmu.get_memory8(rip + i) {
-                Ok(value) => bytes.push(value),
+    // takes the instruction at rip from the block cache, decoding the block it
+    // starts if it is not cached
+    pub fn decode(&mut self) -> Result<Instruction, Fault> {
+        for page in self.mmu.take_modified_code() {
+            self.cache.invalidate_page(page);
+        }
+        let rip = self.cpu.get_rip();
+        let (inst, inst_len) = match self.cache.next(rip) {
+            Some(next) => next,
+            None => {
+                if !self.cache.enter(rip) {
+                    let block = self.decode_block(rip)?;
+                    self.cache.insert(block);
+                    self.cache.enter(rip);
+                }
+                self.cache.next(rip).unwrap()
+            }
+        };
+        self.cpu.set_rip(rip + inst_len as u64);
+        Ok(inst)
+    }
+
+    // decodes instructions from `addr` up to a branch. an instruction after the
+    // first which can not be decoded ends the block, and faults once it is reached
+    fn decode_block(&mut self, addr: u64) -> Result<Block, Fault> {
+        let mut block = Block { insts: Vec::new() };
+        let mut inst_addr = addr;
+        while !block.is_full() {
+            match self.decode_at(inst_addr) {
+                Ok((inst, inst_len)) => {
+                    block.insts.push((inst_addr, inst, inst_len));
+                    inst_addr += inst_len as u64;
+                }
+                Err(fault) if block.insts.is_empty() => return Err(fault),
                 Err(_) => break,
             }
         }
+        self.mmu
+            .mark_code(addr as usize, (inst_addr - addr) as usize);
+        self.stats.blocks += 1;
+        Ok(block)
+    }
+
+    // decodes the instruction at `addr` and returns it with its length
+    pub fn decode_at(&self, addr: u64) -> Result<(Instruction, usize), Fault> {
+        let addr = addr as usize;
+        // the fault tells why fewer than MAX_INST_LEN bytes were fetched
+        let (bytes, fetch_fault) = self.mmu.fetch(addr, MAX_INST_LEN);
         if self.dump_state {
             println!("Fetched: {:X?}", bytes);
         }
 
         let mut decoder = decode::Decoder::new(&bytes);
-        let inst = decoder.decode_inst();
-        let inst_len = decoder.pos();
-        self.cpu.set_rip((rip + inst_len) as u64);
+        match decoder.decode_inst() {
+            Ok(inst) => Ok((inst, *decoder.pos())),
+            Err(err) => match fetch_fault {
+                // the instruction runs into memory which is not executable
+                Some(fault) if err.kind == DecodeErrorKind::UnexpectedEnd => Err(fault),
+                _ => Err(Fault::InvalidOpcode {
+                    rip: addr as u64,
+                    bytes: err.bytes,
+                }),
+            },
+        }
+    }
+}
 
-        Ok(inst)
+// disassembles the instruction of `inst_len` bytes at `addr`, showing a relative
+// target as the address which `describe` gives for it
+pub fn format_inst(
+    addr: u64,
+    inst: &Instruction,
+    inst_len: usize,
+    describe: impl Fn(u64) -> String,
+) -> String {
+    match &inst.operand1 {
+        Some(Operand::Offset(offset)) => {
+            let offset = match offset {
+                Offset::Off8(value) => *value as i64,
+                Offset::Off32(value) => *value as i64,
+            };
+            let target = (addr + inst_len as u64).wrapping_add(offset as u64);
+            format!("{} {}", inst.mnenomic, describe(target))
+        }
+        _ => inst.to_string(),
     }
 }
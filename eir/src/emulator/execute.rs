@@ -1,5 +1,3 @@
-use std::cmp::min;
-
 use x86asm::instruction::{
     mnemonic::{self, Mnemonic},
     operand::{
@@ -12,139 +10,116 @@ use x86asm::instruction::{
     Instruction,
 };
 
-use crate::emulator::{cpu::Flags, Emulator};
+use crate::emulator::{
+    cpu::Flags,
+    exit::ExitReason,
+    fault::Fault,
+    flags::{bits, sign_extend},
+    Emulator,
+};
 
 use super::value::Value;
 
+// why an instruction did not complete
+enum ExecError {
+    Fault(Fault),
+    // a form which decodes but which can not be executed, such as lea with a
+    // register source. it raises #UD like an undefined opcode
+    Unsupported,
+}
+
+impl From<Fault> for ExecError {
+    fn from(fault: Fault) -> Self {
+        ExecError::Fault(fault)
+    }
+}
+
 impl Emulator {
-    pub fn exec(&mut self, inst: Instruction) {
+    // returns the reason if the instruction ends the program. `rip` is the
+    // address of the instruction, and rip has already been moved past it
+    pub fn exec(&mut self, inst: Instruction, rip: u64) -> Option<ExitReason> {
+        let inst_len = self.cpu.get_rip().wrapping_sub(rip) as usize;
+        match self.exec_inst(inst) {
+            Ok(exit) => exit,
+            Err(ExecError::Fault(fault)) => Some(ExitReason::Fault(fault)),
+            Err(ExecError::Unsupported) => {
+                let (bytes, _) = self.mmu.fetch(rip as usize, inst_len);
+                Some(ExitReason::Fault(Fault::InvalidOpcode { rip, bytes }))
+            }
+        }
+    }
+
+    fn exec_inst(&mut self, inst: Instruction) -> Result<Option<ExitReason>, ExecError> {
         let typ = inst.mnenomic.typ();
         match typ {
-            mnemonic::Type::Nullary => self.exec_nullary(inst.mnenomic),
+            mnemonic::Type::Nullary => return self.exec_nullary(inst.mnenomic),
             mnemonic::Type::Unary => {
                 let opr1 = inst.operand1.unwrap();
-                self.exec_unary(inst.mnenomic, opr1);
+                self.exec_unary(inst.mnenomic, opr1)?;
             }
             mnemonic::Type::Binary => {
                 let opr1 = inst.operand1.unwrap();
                 let opr2 = inst.operand2.unwrap();
-                self.exec_binary(inst.mnenomic, opr1, opr2);
+                self.exec_binary(inst.mnenomic, opr1, opr2)?;
             }
         }
+        Ok(None)
     }
 
-    fn exec_nullary(&mut self, mnemonic: Mnemonic) {
+    fn exec_nullary(&mut self, mnemonic: Mnemonic) -> Result<Option<ExitReason>, ExecError> {
         match mnemonic {
             Mnemonic::Hlt => {
                 let exit_code = self.cpu.get_register8(&Register::Rax);
-                println!("Exited with code {}", exit_code);
-                std::process::exit(0);
+                return Ok(Some(ExitReason::Exited(exit_code)));
             }
             Mnemonic::Ret => {
-                let new_rip = self.pop64().unwrap();
+                let new_rip = self.pop64()?;
                 self.cpu.set_rip(new_rip);
             }
-            Mnemonic::Syscall => self.exec_syscall(),
-            _ => panic!(),
-        }
-    }
-
-    fn exec_syscall(&mut self) {
-        let rax = self.cpu.get_register64(&Register::Rax);
-        match rax {
-            0 => {
-                let fd = self.cpu.get_register64(&Register::Rdi);
-                let buf_addr = self.cpu.get_register64(&Register::Rsi) as usize;
-                let count = self.cpu.get_register64(&Register::Rdx) as usize;
-
-                if fd != 0 {
-                    unimplemented!();
-                }
-                let mut buf = String::new();
-                let mut buf_len = std::io::stdin().read_line(&mut buf).unwrap();
-                buf_len = min(buf_len, count);
-
-                let buf_bytes = buf.as_bytes();
-                for i in 0..buf_len {
-                    let addr = buf_addr + i;
-                    let value = buf_bytes[i];
-                    self.mmu.set_memory8(addr, value).unwrap();
-                }
-
-                self.cpu.set_register64(&Register::Rax, buf_len as u64);
-            }
-            1 => {
-                let fd = self.cpu.get_register64(&Register::Rdi);
-                let buf_addr = self.cpu.get_register64(&Register::Rsi) as usize;
-                let count = self.cpu.get_register64(&Register::Rdx) as usize;
-
-                let mut buf = String::new();
-                for i in 0..count {
-                    let addr = buf_addr + i;
-                    let value = self.mmu.get_memory8(addr).unwrap();
-                    buf.push(value as char);
-                }
-
-                match fd {
-                    1 => print!("{}", buf),
-                    2 => eprint!("{}", buf),
-                    _ => unimplemented!(),
-                }
-
-                self.cpu.set_register64(&Register::Rax, count as u64);
-            }
-            60 => {
-                let exit_code = self.cpu.get_register8(&Register::Rdi);
-                println!("Exited with code {}", exit_code);
-                std::process::exit(0);
-            }
-            x => unimplemented!("syscall with {}", x),
+            Mnemonic::Syscall => return Ok(self.exec_syscall()),
+            _ => return Err(ExecError::Unsupported),
         }
+        Ok(None)
     }
 
-    fn exec_unary(&mut self, mnemonic: Mnemonic, opr1: Operand) {
+    fn exec_unary(&mut self, mnemonic: Mnemonic, opr1: Operand) -> Result<(), ExecError> {
         match mnemonic {
             Mnemonic::Call => {
-                let opr1 = self.get_operand64(&opr1);
-                self.push64(self.cpu.get_rip()).unwrap();
+                let opr1 = self.get_operand64(&opr1)?;
+                self.push64(self.cpu.get_rip())?;
                 self.cpu.set_rip(opr1);
             }
-            Mnemonic::IDiv => {
-                let lhs = self.cpu.get_register64(&Register::Rax);
-                let rhs = self.get_operand64(&opr1);
-                let result = self.calc_div(lhs, rhs);
-                self.cpu.set_register64(&Register::Rax, result);
-                self.cpu.set_register64(&Register::Rdx, lhs % rhs);
-            }
+            Mnemonic::IDiv => self.exec_idiv(&opr1)?,
             Mnemonic::Je => {
                 if self.cpu.get_flag(Flags::ZF) {
-                    let opr1 = self.get_operand64(&opr1);
+                    let opr1 = self.get_operand64(&opr1)?;
                     self.cpu.set_rip(opr1);
                 }
             }
             Mnemonic::Jmp => {
-                let opr1 = self.get_operand64(&opr1);
+                let opr1 = self.get_operand64(&opr1)?;
                 self.cpu.set_rip(opr1);
             }
             Mnemonic::Push => {
-                let opr1 = self.get_operand64(&opr1);
-                self.push64(opr1).unwrap();
+                let opr1 = self.get_operand64(&opr1)?;
+                self.push64(opr1)?;
             }
             Mnemonic::Pop => {
-                let value = self.pop64().unwrap();
-                self.set_operand(&opr1, Value::Value64(value));
+                let value = self.pop64()?;
+                self.set_operand(&opr1, Value::Value64(value))?;
             }
             Mnemonic::Sete
             | Mnemonic::Setg
             | Mnemonic::Setge
             | Mnemonic::Setl
             | Mnemonic::Setle
-            | Mnemonic::Setne => self.exec_set(mnemonic, opr1),
-            _ => panic!(),
+            | Mnemonic::Setne => self.exec_set(mnemonic, opr1)?,
+            _ => return Err(ExecError::Unsupported),
         }
+        Ok(())
     }
 
-    fn exec_set(&mut self, mnemonic: Mnemonic, opr1: Operand) {
+    fn exec_set(&mut self, mnemonic: Mnemonic, opr1: Operand) -> Result<(), ExecError> {
         use Flags::*;
         use Mnemonic::*;
         let flag = match mnemonic {
@@ -157,99 +132,138 @@ impl Emulator {
             _ => panic!(),
         };
 
-        self.set_operand8(&opr1, flag as u8);
+        self.set_operand8(&opr1, flag as u8)
     }
 
-    fn exec_binary(&mut self, mnemonic: Mnemonic, opr1: Operand, opr2: Operand) {
-        let size = self.check_size(&opr1, &opr2);
+    fn exec_binary(
+        &mut self,
+        mnemonic: Mnemonic,
+        opr1: Operand,
+        opr2: Operand,
+    ) -> Result<(), ExecError> {
+        // the count of a shift may be cl whatever the size of the operand
+        if let Mnemonic::Sar | Mnemonic::Shl | Mnemonic::Shr = mnemonic {
+            return self.exec_shift(mnemonic, &opr1, &opr2);
+        }
+        // the source of movsx is a byte
+        if let Mnemonic::Movsx = mnemonic {
+            return self.exec_movsx(&opr1, &opr2);
+        }
+
+        let size = check_size(&opr1, &opr2)?;
         match mnemonic {
-            Mnemonic::Add => {
-                let lhs = self.get_operand64(&opr1);
-                let rhs = self.get_operand64(&opr2);
-                let result = self.calc_add(lhs, rhs);
-                self.set_operand64(&opr1, result);
-            }
-            Mnemonic::And => {
-                let lhs = self.get_operand64(&opr1);
-                let rhs = self.get_operand64(&opr2);
-                let result = self.calc_and(lhs, rhs);
-                self.set_operand64(&opr1, result);
-            }
-            Mnemonic::Cmp => {
-                let lhs = self.get_operand64(&opr1);
-                let rhs = self.get_operand64(&opr2);
-                self.calc_sub(lhs, rhs);
-            }
-            Mnemonic::IMul => {
-                let lhs = self.get_operand64(&opr1);
-                let rhs = self.get_operand64(&opr2);
-                let result = self.calc_mul(lhs, rhs);
-                self.set_operand64(&opr1, result);
-            }
+            Mnemonic::Add => self.exec_alu(&opr1, &opr2, &size, Self::calc_add, true)?,
+            Mnemonic::And => self.exec_alu(&opr1, &opr2, &size, Self::calc_and, true)?,
+            Mnemonic::Cmp => self.exec_alu(&opr1, &opr2, &size, Self::calc_sub, false)?,
+            Mnemonic::IMul => self.exec_alu(&opr1, &opr2, &size, Self::calc_imul, true)?,
             Mnemonic::Lea => match opr2 {
                 Operand::Memory(mem) => {
                     let addr = self.calc_address(&mem);
-                    self.set_operand64(&opr1, addr as u64);
+                    self.set_operand(&opr1, Value::from_u64(addr as u64, &size))?;
                 }
-                _ => panic!(),
+                _ => return Err(ExecError::Unsupported),
             },
             Mnemonic::Mov => {
-                let value = self.get_operand(&opr2, &size);
-                self.set_operand(&opr1, value);
-            }
-            Mnemonic::Movsx => match opr2 {
-                Operand::Memory(mem) => {
-                    let addr = self.calc_address(&mem);
-                    let value = self.mmu.get_memory8(addr).unwrap();
-                    // TODO
-                    self.set_operand64(&opr1, value as i8 as i64 as u64);
-                }
-                _ => panic!(),
-            },
-            Mnemonic::Or => {
-                let lhs = self.get_operand64(&opr1);
-                let rhs = self.get_operand64(&opr2);
-                let result = self.calc_or(lhs, rhs);
-                self.set_operand64(&opr1, result);
-            }
-            Mnemonic::Sub => {
-                let lhs = self.get_operand64(&opr1);
-                let rhs = self.get_operand64(&opr2);
-                let result = self.calc_sub(lhs, rhs);
-                self.set_operand64(&opr1, result);
-            }
-            Mnemonic::Xor => {
-                let lhs = self.get_operand64(&opr1);
-                let rhs = self.get_operand64(&opr2);
-                let result = self.calc_xor(lhs, rhs);
-                self.set_operand64(&opr1, result);
+                let value = self.get_operand(&opr2, &size)?;
+                self.set_operand(&opr1, value)?;
             }
-            _ => panic!(),
+            Mnemonic::Or => self.exec_alu(&opr1, &opr2, &size, Self::calc_or, true)?,
+            Mnemonic::Sub => self.exec_alu(&opr1, &opr2, &size, Self::calc_sub, true)?,
+            Mnemonic::Xor => self.exec_alu(&opr1, &opr2, &size, Self::calc_xor, true)?,
+            _ => return Err(ExecError::Unsupported),
         }
+        Ok(())
     }
 
-    fn get_operand64(&self, opr: &Operand) -> u64 {
-        self.get_operand(opr, &register::Size::QWord).as_u64()
+    fn exec_movsx(&mut self, opr1: &Operand, opr2: &Operand) -> Result<(), ExecError> {
+        let size = operand_size(opr1);
+        let value = self.get_operand(opr2, &register::Size::Byte)?.as_u64();
+        let value = value as u8 as i8 as i64 as u64;
+        self.set_operand(opr1, Value::from_u64(value, &size))
     }
 
-    fn set_operand64(&mut self, opr: &Operand, value: u64) {
-        self.set_operand(opr, Value::Value64(value));
+    // sets the flags of `opr1 op opr2`, and stores the result in opr1 if `store`
+    fn exec_alu(
+        &mut self,
+        opr1: &Operand,
+        opr2: &Operand,
+        size: &register::Size,
+        calc: fn(&mut Self, u64, u64, &register::Size) -> u64,
+        store: bool,
+    ) -> Result<(), ExecError> {
+        let lhs = self.get_operand(opr1, size)?.as_u64();
+        let rhs = self.get_operand(opr2, size)?.as_u64();
+        let result = calc(self, lhs, rhs, size);
+        if store {
+            self.set_operand(opr1, Value::from_u64(result, size))?;
+        }
+        Ok(())
     }
 
-    fn set_operand8(&mut self, opr: &Operand, value: u8) {
-        self.set_operand(opr, Value::Value8(value));
+    fn exec_shift(
+        &mut self,
+        mnemonic: Mnemonic,
+        opr1: &Operand,
+        opr2: &Operand,
+    ) -> Result<(), ExecError> {
+        let size = operand_size(opr1);
+        let value = self.get_operand(opr1, &size)?.as_u64();
+        let count = self.get_operand(opr2, &register::Size::Byte)?.as_u64();
+        let result = match mnemonic {
+            Mnemonic::Sar => self.calc_sar(value, count, &size),
+            Mnemonic::Shl => self.calc_shl(value, count, &size),
+            Mnemonic::Shr => self.calc_shr(value, count, &size),
+            _ => panic!(),
+        };
+        self.set_operand(opr1, Value::from_u64(result, &size))
     }
 
-    fn get_operand(&self, opr: &Operand, size: &register::Size) -> Value {
-        match opr {
+    // divides rdx:rax, or edx:eax, by the operand. the flags are left alone as
+    // they are undefined
+    fn exec_idiv(&mut self, opr: &Operand) -> Result<(), ExecError> {
+        let size = operand_size(opr);
+        let bits = bits(&size);
+        let divisor = sign_extend(self.get_operand(opr, &size)?.as_u64(), bits) as i128;
+        let rax = self.cpu.get_register64(&Register::Rax);
+        let rdx = self.cpu.get_register64(&Register::Rdx);
+        let dividend = match size {
+            register::Size::QWord => ((rdx as u128) << 64 | rax as u128) as i128,
+            register::Size::DWord => ((rdx as u32 as u64) << 32 | rax as u32 as u64) as i64 as i128,
+            _ => return Err(ExecError::Unsupported),
+        };
+
+        // dividing by 0 and a quotient which does not fit both raise #DE
+        let quotient = dividend.checked_div(divisor).ok_or(Fault::DivideError)?;
+        let limit = 1i128 << (bits - 1);
+        if quotient < -limit || quotient >= limit {
+            return Err(Fault::DivideError.into());
+        }
+        let remainder = dividend % divisor;
+        let truncate = |value: i128| Value::from_u64(value as u64, &size).as_u64();
+        self.cpu.set_register64(&Register::Rax, truncate(quotient));
+        self.cpu.set_register64(&Register::Rdx, truncate(remainder));
+        Ok(())
+    }
+
+    fn get_operand64(&self, opr: &Operand) -> Result<u64, ExecError> {
+        Ok(self.get_operand(opr, &register::Size::QWord)?.as_u64())
+    }
+
+    fn set_operand8(&mut self, opr: &Operand, value: u8) -> Result<(), ExecError> {
+        self.set_operand(opr, Value::Value8(value))
+    }
+
+    fn get_operand(&self, opr: &Operand, size: &register::Size) -> Result<Value, ExecError> {
+        let value = match opr {
+            // immediates are sign-extended to the size of the operation
             Operand::Immediate(imm) => match imm {
-                Immediate::Imm8(value) => Value::Value8(*value as u8),
-                Immediate::Imm32(value) => Value::Value64(*value as u64),
+                Immediate::Imm8(value) => Value::from_u64(*value as i64 as u64, size),
+                Immediate::Imm32(value) => Value::from_u64(*value as i64 as u64, size),
             },
             Operand::Register(reg) => self.cpu.get_register(reg),
             Operand::Memory(mem) => {
                 let addr = self.calc_address(mem);
-                self.mmu.get_memory(addr, size).unwrap()
+                self.mmu.get_memory(addr, size)?
             }
             Operand::Offset(off) => {
                 let rip = self.cpu.get_rip() as i64;
@@ -257,39 +271,24 @@ impl Emulator {
                     Offset::Off8(off) => *off as i64,
                     Offset::Off32(off) => *off as i64,
                 };
-                Value::Value64((rip + off) as u64)
+                Value::Value64(rip.wrapping_add(off) as u64)
             }
-        }
+        };
+        Ok(value)
     }
 
-    fn set_operand(&mut self, opr: &Operand, value: Value) {
+    fn set_operand(&mut self, opr: &Operand, value: Value) -> Result<(), ExecError> {
         match opr {
-            Operand::Register(reg) => self.cpu.set_register(reg, value),
+            Operand::Register(reg) if reg.size() == value.size() => {
+                self.cpu.set_register(reg, value)
+            }
             Operand::Memory(mem) => {
                 let addr = self.calc_address(mem);
-                self.mmu.set_memory(addr, value).unwrap();
-            }
-            _ => panic!(),
-        }
-    }
-
-    fn check_size(&self, opr1: &Operand, opr2: &Operand) -> register::Size {
-        match (opr1, opr2) {
-            (Operand::Register(reg1), Operand::Register(reg2)) => {
-                if reg1.size() != reg2.size() {
-                    panic!("operand type mismatch");
-                }
-                reg1.size()
+                self.mmu.set_memory(addr, value)?;
             }
-            (Operand::Register(reg), Operand::Memory(_))
-            | (Operand::Memory(_), Operand::Register(reg))
-            | (Operand::Register(reg), Operand::Immediate(_)) => reg.size(),
-            (Operand::Memory(_), Operand::Immediate(imm)) => match imm {
-                Immediate::Imm8(_) => register::Size::Byte,
-                Immediate::Imm32(_) => register::Size::QWord,
-            },
-            _ => register::Size::QWord,
+            _ => return Err(ExecError::Unsupported),
         }
+        Ok(())
     }
 
     fn calc_address(&self, mem: &Memory) -> usize {
@@ -302,11 +301,39 @@ impl Emulator {
             0
         } as isize;
 
+        let index = mem.index.as_ref().map_or(0, |index| {
+            (self.cpu.get_register64(&index.reg) as isize).wrapping_mul(index.scale as isize)
+        });
+
         let disp = mem.disp.as_ref().map_or(0, |disp| match disp {
             Displacement::Disp8(value) => *value as isize,
             Displacement::Disp32(value) => *value as isize,
         });
 
-        (base + disp) as usize
+        base.wrapping_add(index).wrapping_add(disp) as usize
+    }
+}
+
+fn check_size(opr1: &Operand, opr2: &Operand) -> Result<register::Size, ExecError> {
+    match (opr1, opr2) {
+        (Operand::Register(reg1), Operand::Register(reg2)) => {
+            if reg1.size() != reg2.size() {
+                return Err(ExecError::Unsupported);
+            }
+            Ok(reg1.size())
+        }
+        (Operand::Register(reg), Operand::Memory(_))
+        | (Operand::Memory(_), Operand::Register(reg))
+        | (Operand::Register(reg), Operand::Immediate(_)) => Ok(reg.size()),
+        // memory with an immediate, which x86asm encodes with REX.W
+        _ => Ok(register::Size::QWord),
+    }
+}
+
+// memory operands are 64-bit, as x86asm encodes them with REX.W
+fn operand_size(opr: &Operand) -> register::Size {
+    match opr {
+        Operand::Register(reg) => reg.size(),
+        _ => register::Size::QWord,
     }
 }
@@ -0,0 +1,23 @@
+use std::fmt;
+
+use super::fault::Fault;
+
+// why `Emulator::run` stopped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExitReason {
+    // the exit syscall or hlt, with the exit code
+    Exited(u8),
+    Fault(Fault),
+    // `instruction_limit` instructions were executed
+    InstructionLimit,
+}
+
+impl fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitReason::Exited(code) => write!(f, "Exited with code {}", code),
+            ExitReason::Fault(fault) => write!(f, "Fault: {}", fault),
+            ExitReason::InstructionLimit => write!(f, "Instruction limit reached"),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use std::fmt;
+
+// exceptions raised by the emulated CPU
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fault {
+    // #UD, with the bytes of the instruction which could not be decoded
+    InvalidOpcode { rip: u64, bytes: Vec<u8> },
+    // #PF, for an unmapped page or one which does not allow the access
+    PageFault { addr: u64, access: Access },
+    // #DE, from dividing by 0 or a quotient which does not fit
+    DivideError,
+}
+
+// what memory was accessed for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    Exec,
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fault::InvalidOpcode { rip, bytes } => {
+                let bytes: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+                write!(f, "#UD (invalid opcode) at {:#x}: {}", rip, bytes.join(" "))
+            }
+            Fault::PageFault { addr, access } => {
+                write!(f, "#PF (page fault) on {} at {:#x}", access, addr)
+            }
+            Fault::DivideError => write!(f, "#DE (divide error)"),
+        }
+    }
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let access = match self {
+            Access::Read => "read",
+            Access::Write => "write",
+            Access::Exec => "execute",
+        };
+        f.pad(access)
+    }
+}
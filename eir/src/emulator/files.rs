@@ -0,0 +1,186 @@
+use std::{
+    collections::VecDeque,
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io,
+    os::unix::fs::OpenOptionsExt,
+    path::{Component, Path, PathBuf},
+};
+
+// errno values, which syscalls return negated
+pub const ENOENT: i64 = 2;
+pub const EIO: i64 = 5;
+pub const EBADF: i64 = 9;
+pub const ENOMEM: i64 = 12;
+pub const EACCES: i64 = 13;
+pub const EFAULT: i64 = 14;
+pub const EINVAL: i64 = 22;
+pub const ESPIPE: i64 = 29;
+pub const ENOSYS: i64 = 38;
+pub const ELOOP: i64 = 40;
+
+// flags of open(2)
+const O_ACCMODE: u64 = 0o3;
+const O_WRONLY: u64 = 0o1;
+const O_RDWR: u64 = 0o2;
+const O_CREAT: u64 = 0o100;
+const O_EXCL: u64 = 0o200;
+const O_TRUNC: u64 = 0o1000;
+const O_APPEND: u64 = 0o2000;
+
+// symlinks followed while resolving one path, as in Linux
+const MAX_SYMLINKS: usize = 40;
+
+pub enum OpenFile {
+    // the standard streams go through `Emulator::io`
+    Stdin,
+    Stdout,
+    Stderr,
+    Host(File),
+}
+
+// the guest's file descriptors
+pub struct FileTable {
+    files: Vec<Option<OpenFile>>,
+    // canonical directory which guest paths are confined to, if any
+    sandbox: Option<PathBuf>,
+}
+
+impl Default for FileTable {
+    fn default() -> Self {
+        Self {
+            files: vec![
+                Some(OpenFile::Stdin),
+                Some(OpenFile::Stdout),
+                Some(OpenFile::Stderr),
+            ],
+            sandbox: None,
+        }
+    }
+}
+
+impl FileTable {
+    // makes `dir` the root directory and the working directory of the guest
+    pub fn set_sandbox(&mut self, dir: &Path) -> io::Result<()> {
+        self.sandbox = Some(dir.canonicalize()?);
+        Ok(())
+    }
+
+    pub fn open(&mut self, path: &str, flags: u64, mode: u64) -> Result<u64, i64> {
+        let host_path = self.resolve(path)?;
+        let mut options = OpenOptions::new();
+        match flags & O_ACCMODE {
+            O_WRONLY => options.write(true),
+            O_RDWR => options.read(true).write(true),
+            _ => options.read(true),
+        };
+        options
+            .append(flags & O_APPEND != 0)
+            .truncate(flags & O_TRUNC != 0)
+            .mode(mode as u32);
+        if flags & O_EXCL != 0 && flags & O_CREAT != 0 {
+            options.create_new(true);
+        } else {
+            options.create(flags & O_CREAT != 0);
+        }
+        // resolve has followed every symlink, so one which appears afterwards is
+        // not followed out of the sandbox
+        if self.sandbox.is_some() {
+            options.custom_flags(libc::O_NOFOLLOW);
+        }
+
+        let file = options.open(host_path).map_err(|err| errno(&err))?;
+        Ok(self.insert(OpenFile::Host(file)))
+    }
+
+    pub fn close(&mut self, fd: u64) -> Result<(), i64> {
+        match self.files.get_mut(fd as usize) {
+            Some(file) if file.is_some() => {
+                *file = None;
+                Ok(())
+            }
+            _ => Err(EBADF),
+        }
+    }
+
+    pub fn get_mut(&mut self, fd: u64) -> Result<&mut OpenFile, i64> {
+        self.files
+            .get_mut(fd as usize)
+            .and_then(Option::as_mut)
+            .ok_or(EBADF)
+    }
+
+    // maps a guest path to a host path. in a sandbox, `..` stops at the sandbox
+    // like at the root directory, and symlinks are followed as the guest sees
+    // them, so an absolute target starts from the sandbox too
+    pub fn resolve(&self, path: &str) -> Result<PathBuf, i64> {
+        let root = match &self.sandbox {
+            Some(root) => root,
+            None => return Ok(PathBuf::from(path)),
+        };
+
+        let mut resolved = root.clone();
+        let mut pending = components(Path::new(path));
+        let mut symlinks = 0;
+        while let Some(name) = pending.pop_front() {
+            if name == ".." {
+                if resolved != *root {
+                    resolved.pop();
+                }
+                continue;
+            }
+            resolved.push(name);
+
+            // the rest of the path may not exist yet, and then has no symlinks
+            let metadata = match fs::symlink_metadata(&resolved) {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+            if !metadata.file_type().is_symlink() {
+                continue;
+            }
+            symlinks += 1;
+            if symlinks > MAX_SYMLINKS {
+                return Err(ELOOP);
+            }
+            let target = fs::read_link(&resolved).map_err(|err| errno(&err))?;
+            resolved.pop();
+            if target.has_root() {
+                resolved = root.clone();
+            }
+            for name in components(&target).into_iter().rev() {
+                pending.push_front(name);
+            }
+        }
+        Ok(resolved)
+    }
+
+    // uses the lowest free descriptor
+    fn insert(&mut self, file: OpenFile) -> u64 {
+        match self.files.iter().position(Option::is_none) {
+            Some(fd) => {
+                self.files[fd] = Some(file);
+                fd as u64
+            }
+            None => {
+                self.files.push(Some(file));
+                self.files.len() as u64 - 1
+            }
+        }
+    }
+}
+
+// the names in `path` and `..`, leaving out the root and `.`
+fn components(path: &Path) -> VecDeque<OsString> {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_os_string()),
+            Component::ParentDir => Some(OsString::from("..")),
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn errno(err: &io::Error) -> i64 {
+    err.raw_os_error().map_or(EIO, |errno| errno as i64)
+}
@@ -1,94 +1,163 @@
+use x86asm::instruction::operand::register::Size;
+
 use super::{cpu::Flags, Emulator};
 
-// TODO support 32/16/8bit value
+// operands are passed zero-extended from `size` and results are returned the same
+// way. of the flags an instruction leaves undefined, SF, ZF and PF still follow
+// the result and AF is cleared
 impl Emulator {
-    pub fn calc_add(&mut self, lhs: u64, rhs: u64) -> u64 {
-        let (result, result_overflow) = (lhs as i64).overflowing_add(rhs as i64);
-        let result_carry = (lhs as u128).wrapping_add(rhs as u128) >> 64;
-        let result_sign = result >> 63;
-        self.cpu.set_flag(Flags::CF, result_carry != 0);
-        self.cpu.set_flag(Flags::PF, check_parity(result as u8));
-        self.cpu.set_flag(Flags::ZF, result == 0);
-        self.cpu.set_flag(Flags::SF, result_sign != 0);
-        self.cpu.set_flag(Flags::OF, result_overflow);
+    pub fn calc_add(&mut self, lhs: u64, rhs: u64, size: &Size) -> u64 {
+        let bits = bits(size);
+        let result = lhs.wrapping_add(rhs) & mask(bits);
+        self.cpu
+            .set_flag(Flags::CF, lhs as u128 + rhs as u128 > mask(bits) as u128);
+        self.cpu
+            .set_flag(Flags::OF, sign((lhs ^ result) & (rhs ^ result), bits));
+        self.cpu
+            .set_flag(Flags::AF, (lhs ^ rhs ^ result) & 0x10 != 0);
+        self.set_result_flags(result, bits);
 
-        result as u64
+        result
     }
 
-    pub fn calc_sub(&mut self, lhs: u64, rhs: u64) -> u64 {
-        let (result, result_overflow) = (lhs as i64).overflowing_sub(rhs as i64);
-        let result_carry = (lhs as u128).wrapping_sub(rhs as u128) >> 64;
-        let result_sign = result >> 63;
-        self.cpu.set_flag(Flags::CF, result_carry != 0);
-        self.cpu.set_flag(Flags::PF, check_parity(result as u8));
-        self.cpu.set_flag(Flags::ZF, result == 0);
-        self.cpu.set_flag(Flags::SF, result_sign != 0);
-        self.cpu.set_flag(Flags::OF, result_overflow);
+    pub fn calc_sub(&mut self, lhs: u64, rhs: u64, size: &Size) -> u64 {
+        let bits = bits(size);
+        let result = lhs.wrapping_sub(rhs) & mask(bits);
+        self.cpu.set_flag(Flags::CF, lhs < rhs);
+        self.cpu
+            .set_flag(Flags::OF, sign((lhs ^ rhs) & (lhs ^ result), bits));
+        self.cpu
+            .set_flag(Flags::AF, (lhs ^ rhs ^ result) & 0x10 != 0);
+        self.set_result_flags(result, bits);
 
-        result as u64
+        result
     }
 
-    pub fn calc_mul(&mut self, lhs: u64, rhs: u64) -> u64 {
-        let (result, result_overflow) = (lhs as i64).overflowing_mul(rhs as i64);
-        let result_carry = (lhs as u128).wrapping_mul(rhs as u128) >> 64;
-        let result_sign = result >> 63;
-        self.cpu.set_flag(Flags::CF, result_carry != 0);
-        self.cpu.set_flag(Flags::PF, check_parity(result as u8));
-        self.cpu.set_flag(Flags::ZF, result == 0);
-        self.cpu.set_flag(Flags::SF, result_sign != 0);
-        self.cpu.set_flag(Flags::OF, result_overflow);
+    // the two and three operand forms, which keep the low half of the product
+    pub fn calc_imul(&mut self, lhs: u64, rhs: u64, size: &Size) -> u64 {
+        let bits = bits(size);
+        let product = sign_extend(lhs, bits) as i128 * sign_extend(rhs, bits) as i128;
+        let result = product as u64 & mask(bits);
+        let truncated = product != sign_extend(result, bits) as i128;
+        self.cpu.set_flag(Flags::CF, truncated);
+        self.cpu.set_flag(Flags::OF, truncated);
+        self.cpu.set_flag(Flags::AF, false);
+        self.set_result_flags(result, bits);
 
-        result as u64
+        result
     }
 
-    pub fn calc_div(&mut self, lhs: u64, rhs: u64) -> u64 {
-        let (result, result_overflow) = (lhs as i64).overflowing_div(rhs as i64);
-        let result_carry = (lhs as u128).wrapping_div(rhs as u128) >> 64;
-        let result_sign = result >> 63;
-        self.cpu.set_flag(Flags::CF, result_carry != 0);
-        self.cpu.set_flag(Flags::PF, check_parity(result as u8));
-        self.cpu.set_flag(Flags::ZF, result == 0);
-        self.cpu.set_flag(Flags::SF, result_sign != 0);
-        self.cpu.set_flag(Flags::OF, result_overflow);
+    pub fn calc_and(&mut self, lhs: u64, rhs: u64, size: &Size) -> u64 {
+        self.set_logic_flags(lhs & rhs, size)
+    }
 
-        result as u64
+    pub fn calc_or(&mut self, lhs: u64, rhs: u64, size: &Size) -> u64 {
+        self.set_logic_flags(lhs | rhs, size)
     }
 
-    pub fn calc_and(&mut self, lhs: u64, rhs: u64) -> u64 {
-        let result = lhs & rhs;
-        let result_sign = result >> 63;
-        self.cpu.set_flag(Flags::CF, false);
-        self.cpu.set_flag(Flags::PF, check_parity(result as u8));
-        self.cpu.set_flag(Flags::ZF, result == 0);
-        self.cpu.set_flag(Flags::SF, result_sign != 0);
-        self.cpu.set_flag(Flags::OF, false);
+    pub fn calc_xor(&mut self, lhs: u64, rhs: u64, size: &Size) -> u64 {
+        self.set_logic_flags(lhs ^ rhs, size)
+    }
+
+    // shifts by 0 leave the flags alone. OF is only defined for shifts by 1
+    pub fn calc_shl(&mut self, lhs: u64, count: u64, size: &Size) -> u64 {
+        let bits = bits(size);
+        let count = count & count_mask(bits);
+        if count == 0 {
+            return lhs;
+        }
+        let (result, carry) = if count <= bits {
+            let result = lhs.checked_shl(count as u32).unwrap_or(0) & mask(bits);
+            (result, lhs >> (bits - count) & 1 != 0)
+        } else {
+            (0, false)
+        };
+        self.cpu.set_flag(Flags::CF, carry);
+        self.cpu.set_flag(Flags::OF, sign(result, bits) != carry);
+        self.cpu.set_flag(Flags::AF, false);
+        self.set_result_flags(result, bits);
 
         result
     }
 
-    pub fn calc_or(&mut self, lhs: u64, rhs: u64) -> u64 {
-        let result = lhs | rhs;
-        let result_sign = result >> 63;
-        self.cpu.set_flag(Flags::CF, false);
-        self.cpu.set_flag(Flags::PF, check_parity(result as u8));
-        self.cpu.set_flag(Flags::ZF, result == 0);
-        self.cpu.set_flag(Flags::SF, result_sign != 0);
+    pub fn calc_shr(&mut self, lhs: u64, count: u64, size: &Size) -> u64 {
+        let bits = bits(size);
+        let count = count & count_mask(bits);
+        if count == 0 {
+            return lhs;
+        }
+        let result = lhs.checked_shr(count as u32).unwrap_or(0);
+        self.cpu.set_flag(Flags::CF, lhs >> (count - 1) & 1 != 0);
+        self.cpu.set_flag(Flags::OF, sign(lhs, bits));
+        self.cpu.set_flag(Flags::AF, false);
+        self.set_result_flags(result, bits);
+
+        result
+    }
+
+    pub fn calc_sar(&mut self, lhs: u64, count: u64, size: &Size) -> u64 {
+        let bits = bits(size);
+        let count = count & count_mask(bits);
+        if count == 0 {
+            return lhs;
+        }
+        let value = sign_extend(lhs, bits);
+        let result = (value >> count.min(63)) as u64 & mask(bits);
+        self.cpu
+            .set_flag(Flags::CF, value >> (count - 1).min(63) & 1 != 0);
         self.cpu.set_flag(Flags::OF, false);
+        self.cpu.set_flag(Flags::AF, false);
+        self.set_result_flags(result, bits);
 
         result
     }
 
-    pub fn calc_xor(&mut self, lhs: u64, rhs: u64) -> u64 {
-        let result = lhs ^ rhs;
-        let result_sign = result >> 63;
+    fn set_logic_flags(&mut self, result: u64, size: &Size) -> u64 {
         self.cpu.set_flag(Flags::CF, false);
-        self.cpu.set_flag(Flags::PF, check_parity(result as u8));
-        self.cpu.set_flag(Flags::ZF, result == 0);
-        self.cpu.set_flag(Flags::SF, result_sign != 0);
         self.cpu.set_flag(Flags::OF, false);
+        self.cpu.set_flag(Flags::AF, false);
+        self.set_result_flags(result, bits(size));
 
         result
     }
+
+    // ZF, SF and PF
+    fn set_result_flags(&mut self, result: u64, bits: u64) {
+        self.cpu.set_flag(Flags::ZF, result == 0);
+        self.cpu.set_flag(Flags::SF, sign(result, bits));
+        self.cpu.set_flag(Flags::PF, check_parity(result as u8));
+    }
+}
+
+pub fn bits(size: &Size) -> u64 {
+    match size {
+        Size::Byte => 8,
+        Size::Word => 16,
+        Size::DWord => 32,
+        Size::QWord => 64,
+    }
+}
+
+fn mask(bits: u64) -> u64 {
+    u64::MAX >> (64 - bits)
+}
+
+fn sign(value: u64, bits: u64) -> bool {
+    value >> (bits - 1) & 1 != 0
+}
+
+pub fn sign_extend(value: u64, bits: u64) -> i64 {
+    let shift = 64 - bits;
+    ((value << shift) as i64) >> shift
+}
+
+// shift counts are taken modulo 64 for 64-bit operands and modulo 32 otherwise
+fn count_mask(bits: u64) -> u64 {
+    if bits == 64 {
+        0x3f
+    } else {
+        0x1f
+    }
 }
 
 /// 1になっているビットが偶数個の場合にtrueを返す
@@ -0,0 +1,78 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    io::{self, Read, Write},
+    rc::Rc,
+};
+
+// standard streams of the emulated program
+pub trait Io {
+    // reads at most `buf.len()` bytes and returns how many were read
+    fn read_stdin(&mut self, buf: &mut [u8]) -> usize;
+    fn write_stdout(&mut self, data: &[u8]);
+    fn write_stderr(&mut self, data: &[u8]);
+}
+
+// the streams of the eir process itself
+pub struct HostIo;
+
+impl Io for HostIo {
+    fn read_stdin(&mut self, buf: &mut [u8]) -> usize {
+        io::stdin().read(buf).unwrap_or(0)
+    }
+
+    fn write_stdout(&mut self, data: &[u8]) {
+        let mut stdout = io::stdout();
+        stdout.write_all(data).unwrap();
+        stdout.flush().unwrap();
+    }
+
+    fn write_stderr(&mut self, data: &[u8]) {
+        io::stderr().write_all(data).unwrap();
+    }
+}
+
+// in-memory streams for embedding eir, e.g. in tests.
+// clones share the buffers, so a clone kept outside the emulator sees the output
+#[derive(Clone, Default)]
+pub struct CapturedIo {
+    stdin: Rc<RefCell<VecDeque<u8>>>,
+    stdout: Rc<RefCell<Vec<u8>>>,
+    stderr: Rc<RefCell<Vec<u8>>>,
+}
+
+impl CapturedIo {
+    pub fn new(stdin: &[u8]) -> Self {
+        Self {
+            stdin: Rc::new(RefCell::new(stdin.iter().copied().collect())),
+            ..Self::default()
+        }
+    }
+
+    pub fn stdout(&self) -> Vec<u8> {
+        self.stdout.borrow().clone()
+    }
+
+    pub fn stderr(&self) -> Vec<u8> {
+        self.stderr.borrow().clone()
+    }
+}
+
+impl Io for CapturedIo {
+    fn read_stdin(&mut self, buf: &mut [u8]) -> usize {
+        let mut stdin = self.stdin.borrow_mut();
+        let len = buf.len().min(stdin.len());
+        for (dest, value) in buf.iter_mut().zip(stdin.drain(..len)) {
+            *dest = value;
+        }
+        len
+    }
+
+    fn write_stdout(&mut self, data: &[u8]) {
+        self.stdout.borrow_mut().extend_from_slice(data);
+    }
+
+    fn write_stderr(&mut self, data: &[u8]) {
+        self.stderr.borrow_mut().extend_from_slice(data);
+    }
+}
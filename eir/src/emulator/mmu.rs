@@ -1,69 +1,171 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use elfen::segment;
 use x86asm::instruction::operand::register;
 
-use super::value::Value;
+use super::{
+    fault::{Access, Fault},
+    value::Value,
+    PAGE_SIZE,
+};
 
-pub struct Mmu {
-    segments: Vec<Vec<u8>>,
-    segment_details: Vec<SegmentDetail>,
+const PAGE_LEN: usize = PAGE_SIZE as usize;
+
+// what a page may be accessed for
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Perms {
+    pub read: bool,
+    pub write: bool,
+    pub exec: bool,
 }
 
-struct SegmentDetail {
-    virt_addr: usize,
-    size: usize,
-    index: usize,
+impl Perms {
+    pub const RW: Perms = Perms {
+        read: true,
+        write: true,
+        exec: false,
+    };
+    pub const RWX: Perms = Perms {
+        read: true,
+        write: true,
+        exec: true,
+    };
+
+    // the permissions of a segment with the `flags` of its program header
+    pub fn from_segment_flags(flags: u32) -> Self {
+        Self {
+            read: segment::Flags::R.contained_in(flags),
+            write: segment::Flags::W.contained_in(flags),
+            exec: segment::Flags::X.contained_in(flags),
+        }
+    }
+
+    // PROT_READ, PROT_WRITE and PROT_EXEC of mmap(2)
+    pub fn from_prot(prot: u64) -> Self {
+        Self {
+            read: prot & 0x1 != 0,
+            write: prot & 0x2 != 0,
+            exec: prot & 0x4 != 0,
+        }
+    }
+
+    fn allows(self, access: Access) -> bool {
+        match access {
+            Access::Read => self.read,
+            Access::Write => self.write,
+            Access::Exec => self.exec,
+        }
+    }
+
+    fn union(self, other: Perms) -> Perms {
+        Perms {
+            read: self.read || other.read,
+            write: self.write || other.write,
+            exec: self.exec || other.exec,
+        }
+    }
 }
 
-pub struct PhysicalAddress {
-    pub segment_index: usize,
-    pub segment_offset: usize,
+struct Page {
+    data: Box<[u8; PAGE_LEN]>,
+    perms: Perms,
+}
+
+// the guest's memory as 4 KiB pages, keyed by virtual page number
+#[derive(Default)]
+pub struct Mmu {
+    pages: HashMap<u64, Page>,
+    // pages which decoded instructions are cached for
+    code_pages: HashSet<u64>,
+    // code pages written or unmapped since `take_modified_code` was last called
+    modified_code: Vec<u64>,
+    // whether the guest's writes are recorded for `take_writes`
+    pub log_writes: bool,
+    writes: Vec<(u64, Vec<u8>)>,
 }
 
 impl Mmu {
     pub fn new() -> Self {
-        Self {
-            segments: Vec::new(),
-            segment_details: Vec::new(),
+        Self::default()
+    }
+
+    // maps zeroed pages covering `len` bytes from `addr`. pages which are already
+    // mapped keep their data and gain `perms`, as segments may share a page
+    pub fn map(&mut self, addr: usize, len: usize, perms: Perms) {
+        for page_number in page_numbers(addr, len).into_iter().flatten() {
+            self.pages
+                .entry(page_number)
+                .and_modify(|page| page.perms = page.perms.union(perms))
+                .or_insert_with(|| Page {
+                    data: Box::new([0; PAGE_LEN]),
+                    perms,
+                });
         }
     }
 
-    pub fn add_segment(&mut self, virt_addr: usize, data: Vec<u8>) {
-        let detail = SegmentDetail {
-            virt_addr,
-            size: data.len(),
-            index: self.segments.len(),
-        };
+    pub fn unmap(&mut self, addr: usize, len: usize) {
+        for page_number in page_numbers(addr, len).into_iter().flatten() {
+            self.pages.remove(&page_number);
+            self.modify(page_number);
+        }
+    }
 
-        self.segments.push(data);
-        self.segment_details.push(detail);
+    // records that instructions from `len` bytes at `addr` are cached, so that
+    // changing them is reported by `take_modified_code`
+    pub fn mark_code(&mut self, addr: usize, len: usize) {
+        self.code_pages
+            .extend(page_numbers(addr, len).into_iter().flatten());
     }
 
-    pub fn calc_phys_addr(&self, virt_addr: usize) -> Result<PhysicalAddress, String> {
-        for detail in &self.segment_details {
-            let start_addr = detail.virt_addr;
-            let end_addr = start_addr + detail.size;
-            if virt_addr >= start_addr && virt_addr < end_addr {
-                let offset = virt_addr - detail.virt_addr;
-                return Ok(PhysicalAddress {
-                    segment_index: detail.index,
-                    segment_offset: offset,
-                });
+    pub fn take_modified_code(&mut self) -> Vec<u64> {
+        std::mem::take(&mut self.modified_code)
+    }
+
+    // the address and bytes of each write by the guest since the last call
+    pub fn take_writes(&mut self) -> Vec<(u64, Vec<u8>)> {
+        std::mem::take(&mut self.writes)
+    }
+
+    pub fn is_mapped(&self, addr: usize) -> bool {
+        self.pages.contains_key(&(addr as u64 / PAGE_SIZE))
+    }
+
+    // how many of `len` bytes from `addr` can be accessed for `access`, up to the
+    // first page which does not allow it
+    pub fn accessible_len(&self, address: usize, len: usize, access: Access) -> usize {
+        let mut done = 0;
+        while done < len {
+            let addr = match address.checked_add(done) {
+                Some(addr) => addr,
+                None => break,
+            };
+            if self.page(addr, Some(access)).is_err() {
+                break;
             }
+            done += PAGE_LEN - addr % PAGE_LEN;
         }
+        done.min(len)
+    }
 
-        Err("SEGV".to_string())
+    // writes regardless of the page permissions, as a loader or a debugger does
+    pub fn poke(&mut self, address: usize, bytes: &[u8]) -> Result<(), Fault> {
+        self.write(address, bytes, None)
     }
 
-    pub fn get_memory(&self, address: usize, size: &register::Size) -> Result<Value, String> {
+    pub fn get_memory(&self, address: usize, size: &register::Size) -> Result<Value, Fault> {
         use register::Size::*;
         match size {
-            Byte => self.get_memory8(address).map(|val| Value::Value8(val)),
+            Byte => self.get_memory8(address).map(Value::Value8),
             Word => unimplemented!(),
-            DWord => self.get_memory32(address).map(|val| Value::Value32(val)),
-            QWord => self.get_memory64(address).map(|val| Value::Value64(val)),
+            DWord => self.get_memory32(address).map(Value::Value32),
+            QWord => self.get_memory64(address).map(Value::Value64),
         }
     }
 
-    pub fn set_memory(&mut self, address: usize, value: Value) -> Result<(), String> {
+    pub fn set_memory(&mut self, address: usize, value: Value) -> Result<(), Fault> {
         match value {
             Value::Value8(value) => self.set_memory8(address, value),
             Value::Value32(value) => self.set_memory32(address, value),
@@ -71,44 +173,154 @@ impl Mmu {
         }
     }
 
-    pub fn get_memory8(&self, address: usize) -> Result<u8, String> {
-        let addr = self.calc_phys_addr(address)?;
-        Ok(self.segments[addr.segment_index][addr.segment_offset])
+    pub fn get_memory8(&self, address: usize) -> Result<u8, Fault> {
+        let mut bytes = [0];
+        self.read(address, &mut bytes, Access::Read)?;
+        Ok(bytes[0])
     }
 
-    pub fn set_memory8(&mut self, address: usize, value: u8) -> Result<(), String> {
-        let addr = self.calc_phys_addr(address)?;
-        self.segments[addr.segment_index][addr.segment_offset] = value;
-        Ok(())
+    pub fn set_memory8(&mut self, address: usize, value: u8) -> Result<(), Fault> {
+        self.write(address, &[value], Some(Access::Write))
+    }
+
+    pub fn get_memory32(&self, address: usize) -> Result<u32, Fault> {
+        let mut bytes = [0; 4];
+        self.read(address, &mut bytes, Access::Read)?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    pub fn set_memory32(&mut self, address: usize, value: u32) -> Result<(), Fault> {
+        self.write(address, &value.to_le_bytes(), Some(Access::Write))
+    }
+
+    pub fn get_memory64(&self, address: usize) -> Result<u64, Fault> {
+        let mut bytes = [0; 8];
+        self.read(address, &mut bytes, Access::Read)?;
+        Ok(u64::from_le_bytes(bytes))
     }
 
-    pub fn get_memory32(&self, address: usize) -> Result<u32, String> {
-        let mut ret: u32 = 0;
-        for i in 0..4 {
-            ret |= (self.get_memory8(address + i)? as u32) << (8 * i);
+    pub fn set_memory64(&mut self, address: usize, value: u64) -> Result<(), Fault> {
+        self.write(address, &value.to_le_bytes(), Some(Access::Write))
+    }
+
+    // reads up to `len` bytes of code, stopping at the first byte which is not
+    // executable, and returns the bytes with the fault which stopped it
+    pub fn fetch(&self, address: usize, len: usize) -> (Vec<u8>, Option<Fault>) {
+        let mut bytes = Vec::with_capacity(len);
+        while bytes.len() < len {
+            let addr = match checked_address(address, bytes.len(), Access::Exec) {
+                Ok(addr) => addr,
+                Err(fault) => return (bytes, Some(fault)),
+            };
+            let page = match self.page(addr, Some(Access::Exec)) {
+                Ok(page) => page,
+                Err(fault) => return (bytes, Some(fault)),
+            };
+            let offset = addr % PAGE_LEN;
+            let chunk = (PAGE_LEN - offset).min(len - bytes.len());
+            bytes.extend_from_slice(&page.data[offset..offset + chunk]);
         }
-        Ok(ret)
+        (bytes, None)
+    }
+
+    pub fn read_bytes(&self, address: usize, len: usize) -> Result<Vec<u8>, Fault> {
+        let mut bytes = vec![0; len];
+        self.read(address, &mut bytes, Access::Read)?;
+        Ok(bytes)
     }
 
-    pub fn set_memory32(&mut self, address: usize, value: u32) -> Result<(), String> {
-        for i in 0..4 {
-            self.set_memory8(address + i, (value >> (8 * i)) as u8)?;
+    pub fn write_bytes(&mut self, address: usize, bytes: &[u8]) -> Result<(), Fault> {
+        self.write(address, bytes, Some(Access::Write))
+    }
+
+    // reads a NUL-terminated string
+    pub fn read_c_string(&self, address: usize) -> Result<Vec<u8>, Fault> {
+        let mut bytes = Vec::new();
+        loop {
+            match self.get_memory8(checked_address(address, bytes.len(), Access::Read)?)? {
+                0 => return Ok(bytes),
+                value => bytes.push(value),
+            }
         }
-        Ok(())
     }
 
-    pub fn get_memory64(&self, address: usize) -> Result<u64, String> {
-        let mut ret = 0;
-        for i in 0..8 {
-            ret |= (self.get_memory8(address + i)? as u64) << (8 * i);
+    // copies page by page, so that an access within a page is a single lookup
+    fn read(&self, address: usize, buf: &mut [u8], access: Access) -> Result<(), Fault> {
+        let mut done = 0;
+        while done < buf.len() {
+            let addr = checked_address(address, done, access)?;
+            let page = self.page(addr, Some(access))?;
+            let offset = addr % PAGE_LEN;
+            let len = (PAGE_LEN - offset).min(buf.len() - done);
+            buf[done..done + len].copy_from_slice(&page.data[offset..offset + len]);
+            done += len;
         }
-        Ok(ret)
+        Ok(())
     }
 
-    pub fn set_memory64(&mut self, address: usize, value: u64) -> Result<(), String> {
-        for i in 0..8 {
-            self.set_memory8(address + i, (value >> (8 * i)) as u8)?;
+    // every page is checked before anything is written, so that a faulting
+    // write has no effect
+    fn write(&mut self, address: usize, bytes: &[u8], access: Option<Access>) -> Result<(), Fault> {
+        let page_numbers = page_numbers(address, bytes.len()).ok_or(Fault::PageFault {
+            addr: 0,
+            access: access.unwrap_or(Access::Write),
+        })?;
+        for page_number in page_numbers {
+            let addr = (page_number * PAGE_SIZE).max(address as u64);
+            self.page(addr as usize, access)?;
+        }
+
+        let mut done = 0;
+        while done < bytes.len() {
+            let addr = address + done;
+            let page_number = addr as u64 / PAGE_SIZE;
+            self.modify(page_number);
+            let page = self.pages.get_mut(&page_number).unwrap();
+            let offset = addr % PAGE_LEN;
+            let len = (PAGE_LEN - offset).min(bytes.len() - done);
+            page.data[offset..offset + len].copy_from_slice(&bytes[done..done + len]);
+            done += len;
+        }
+        if self.log_writes && access.is_some() {
+            self.writes.push((address as u64, bytes.to_vec()));
         }
         Ok(())
     }
+
+    fn modify(&mut self, page_number: u64) {
+        if self.code_pages.remove(&page_number) {
+            self.modified_code.push(page_number);
+        }
+    }
+
+    // the page containing `addr`, if it is mapped and allows `access`
+    fn page(&self, addr: usize, access: Option<Access>) -> Result<&Page, Fault> {
+        let fault = Fault::PageFault {
+            addr: addr as u64,
+            access: access.unwrap_or(Access::Write),
+        };
+        match self.pages.get(&(addr as u64 / PAGE_SIZE)) {
+            Some(page) if access.is_none_or(|access| page.perms.allows(access)) => Ok(page),
+            _ => Err(fault),
+        }
+    }
+}
+
+// the numbers of the pages which `len` bytes from `addr` touch, or None if they
+// run past the end of the address space
+fn page_numbers(addr: usize, len: usize) -> Option<Range<u64>> {
+    let first = addr as u64 / PAGE_SIZE;
+    if len == 0 {
+        return Some(first..first);
+    }
+    // from the last byte, as the end of the last page does not fit in a u64
+    let last = (addr as u64).checked_add(len as u64 - 1)?;
+    Some(first..last / PAGE_SIZE + 1)
+}
+
+// `address + offset`, where an access which wraps around faults at address 0
+fn checked_address(address: usize, offset: usize, access: Access) -> Result<usize, Fault> {
+    address
+        .checked_add(offset)
+        .ok_or(Fault::PageFault { addr: 0, access })
 }
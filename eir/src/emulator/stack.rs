@@ -0,0 +1,68 @@
+use x86asm::instruction::operand::register::Register;
+
+use super::{fault::Fault, mmu::Perms, random_u64, Emulator, PAGE_SIZE};
+
+// the initial stack ends here, like a Linux process's
+pub const STACK_TOP: u64 = 0x7fff_ffff_f000;
+pub const STACK_SIZE: u64 = 0x10_0000;
+
+// auxiliary vector entry types
+pub const AT_NULL: u64 = 0;
+pub const AT_PHDR: u64 = 3;
+pub const AT_PHENT: u64 = 4;
+pub const AT_PHNUM: u64 = 5;
+pub const AT_PAGESZ: u64 = 6;
+pub const AT_ENTRY: u64 = 9;
+pub const AT_RANDOM: u64 = 25;
+
+impl Emulator {
+    // builds the stack a Linux process starts with and points rsp at it:
+    // argc, argv, envp and auxv, followed by the strings they refer to
+    pub fn init_stack(&mut self, argv: &[String], envp: &[String]) -> Result<(), Fault> {
+        let stack_bottom = STACK_TOP - STACK_SIZE;
+        // not executable, so that jumping to the stack faults
+        self.mmu
+            .map(stack_bottom as usize, STACK_SIZE as usize, Perms::RW);
+
+        let mut addr = STACK_TOP;
+        let mut push_bytes = |emu: &mut Emulator, bytes: &[u8]| -> Result<u64, Fault> {
+            addr -= bytes.len() as u64;
+            emu.mmu.write_bytes(addr as usize, bytes)?;
+            Ok(addr)
+        };
+
+        let mut string_addrs = Vec::new();
+        for string in argv.iter().chain(envp) {
+            let mut bytes = string.as_bytes().to_vec();
+            bytes.push(0);
+            string_addrs.push(push_bytes(self, &bytes)?);
+        }
+        let mut random = random_u64().to_le_bytes().to_vec();
+        random.extend_from_slice(&random_u64().to_le_bytes());
+        let random_addr = push_bytes(self, &random)?;
+
+        let mut auxv = self.auxv.clone();
+        auxv.push((AT_PAGESZ, PAGE_SIZE));
+        auxv.push((AT_RANDOM, random_addr));
+        auxv.push((AT_NULL, 0));
+
+        let (argv_addrs, envp_addrs) = string_addrs.split_at(argv.len());
+        let mut words = vec![argv.len() as u64];
+        words.extend_from_slice(argv_addrs);
+        words.push(0);
+        words.extend_from_slice(envp_addrs);
+        words.push(0);
+        for (typ, value) in auxv {
+            words.push(typ);
+            words.push(value);
+        }
+
+        // rsp points at argc and is 16-byte aligned
+        let rsp = (addr - words.len() as u64 * 8) & !0xf;
+        for (i, word) in words.iter().enumerate() {
+            self.mmu.set_memory64(rsp as usize + i * 8, *word)?;
+        }
+        self.cpu.set_register64(&Register::Rsp, rsp);
+        Ok(())
+    }
+}
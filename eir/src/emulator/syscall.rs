@@ -0,0 +1,340 @@
+use std::{
+    io::{Read, Seek, SeekFrom, Write},
+    os::unix::fs::{FileExt, MetadataExt},
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use x86asm::instruction::operand::register::Register;
+
+use super::{
+    exit::ExitReason,
+    fault::Access,
+    files::{errno, OpenFile, EBADF, EFAULT, EINVAL, ENOMEM, ENOSYS, ESPIPE},
+    mmu::Perms,
+    random_u64, Emulator, PAGE_SIZE,
+};
+
+const SYS_READ: u64 = 0;
+const SYS_WRITE: u64 = 1;
+const SYS_OPEN: u64 = 2;
+const SYS_CLOSE: u64 = 3;
+const SYS_FSTAT: u64 = 5;
+const SYS_LSEEK: u64 = 8;
+const SYS_MMAP: u64 = 9;
+const SYS_MUNMAP: u64 = 11;
+const SYS_BRK: u64 = 12;
+const SYS_GETPID: u64 = 39;
+const SYS_EXIT: u64 = 60;
+const SYS_CLOCK_GETTIME: u64 = 228;
+const SYS_EXIT_GROUP: u64 = 231;
+const SYS_OPENAT: u64 = 257;
+const SYS_GETRANDOM: u64 = 318;
+
+const AT_FDCWD: i64 = -100;
+const MAP_FIXED: u64 = 0x10;
+const MAP_ANONYMOUS: u64 = 0x20;
+
+// anonymous mappings are placed upwards from here
+const MMAP_BASE: u64 = 0x7f00_0000_0000;
+// pages are allocated as soon as they are mapped, so one mmap or brk can not map
+// more than this
+const MAX_MAP_LEN: u64 = 1 << 30;
+
+// st_mode of the standard streams, a character device
+const S_IFCHR: u32 = 0o020000;
+const STAT_SIZE: usize = 144;
+
+// the value of a syscall, or an errno
+type SyscallResult = Result<u64, i64>;
+
+// the program break and the mappings made by mmap
+#[derive(Default)]
+pub struct Heap {
+    brk_start: u64,
+    brk: u64,
+    // memory is mapped up to here for the break
+    brk_end: u64,
+    mmap_next: u64,
+}
+
+impl Heap {
+    // the break starts at `brk_start`, usually the end of the loaded ELF
+    pub fn new(brk_start: u64) -> Self {
+        // a break at the end of the address space can not be moved
+        let brk_start = page_align(brk_start).unwrap_or(0);
+        Self {
+            brk_start,
+            brk: brk_start,
+            brk_end: brk_start,
+            mmap_next: MMAP_BASE,
+        }
+    }
+}
+
+impl Emulator {
+    pub fn exec_syscall(&mut self) -> Option<ExitReason> {
+        let number = self.cpu.get_register64(&Register::Rax);
+        let args = [
+            self.cpu.get_register64(&Register::Rdi),
+            self.cpu.get_register64(&Register::Rsi),
+            self.cpu.get_register64(&Register::Rdx),
+            self.cpu.get_register64(&Register::R10),
+            self.cpu.get_register64(&Register::R8),
+            self.cpu.get_register64(&Register::R9),
+        ];
+
+        let result = match number {
+            SYS_READ => self.sys_read(args[0], args[1], args[2]),
+            SYS_WRITE => self.sys_write(args[0], args[1], args[2]),
+            SYS_OPEN => self.sys_open(args[0], args[1], args[2]),
+            SYS_OPENAT => self.sys_openat(args[0], args[1], args[2], args[3]),
+            SYS_CLOSE => self.files.close(args[0]).map(|_| 0),
+            SYS_FSTAT => self.sys_fstat(args[0], args[1]),
+            SYS_LSEEK => self.sys_lseek(args[0], args[1], args[2]),
+            SYS_MMAP => self.sys_mmap(args[0], args[1], args[2], args[3], args[4], args[5]),
+            SYS_MUNMAP => self.sys_munmap(args[0], args[1]),
+            SYS_BRK => Ok(self.sys_brk(args[0])),
+            SYS_GETPID => Ok(process::id() as u64),
+            SYS_CLOCK_GETTIME => self.sys_clock_gettime(args[1]),
+            SYS_GETRANDOM => self.sys_getrandom(args[0], args[1]),
+            SYS_EXIT | SYS_EXIT_GROUP => {
+                let exit_code = self.cpu.get_register8(&Register::Rdi);
+                return Some(ExitReason::Exited(exit_code));
+            }
+            _ => Err(ENOSYS),
+        };
+
+        let value = match result {
+            Ok(value) => value,
+            Err(errno) => -errno as u64,
+        };
+        self.cpu.set_register64(&Register::Rax, value);
+        None
+    }
+
+    // reads no more than fits in the memory mapped at `buf_addr`
+    fn sys_read(&mut self, fd: u64, buf_addr: u64, count: u64) -> SyscallResult {
+        let len = self
+            .mmu
+            .accessible_len(buf_addr as usize, count as usize, Access::Write);
+        if len == 0 && count != 0 {
+            return Err(EFAULT);
+        }
+        let mut buf = vec![0; len];
+        let len = match self.files.get_mut(fd)? {
+            OpenFile::Stdin => self.io.read_stdin(&mut buf),
+            OpenFile::Host(file) => file.read(&mut buf).map_err(|err| errno(&err))?,
+            _ => return Err(EBADF),
+        };
+        self.mmu
+            .write_bytes(buf_addr as usize, &buf[..len])
+            .map_err(|_| EFAULT)?;
+        Ok(len as u64)
+    }
+
+    fn sys_write(&mut self, fd: u64, buf_addr: u64, count: u64) -> SyscallResult {
+        let readable = self
+            .mmu
+            .accessible_len(buf_addr as usize, count as usize, Access::Read);
+        if (readable as u64) < count {
+            return Err(EFAULT);
+        }
+        let buf = self
+            .mmu
+            .read_bytes(buf_addr as usize, count as usize)
+            .map_err(|_| EFAULT)?;
+        match self.files.get_mut(fd)? {
+            OpenFile::Stdout => self.io.write_stdout(&buf),
+            OpenFile::Stderr => self.io.write_stderr(&buf),
+            OpenFile::Host(file) => file.write_all(&buf).map_err(|err| errno(&err))?,
+            OpenFile::Stdin => return Err(EBADF),
+        }
+        Ok(count)
+    }
+
+    fn sys_open(&mut self, path_addr: u64, flags: u64, mode: u64) -> SyscallResult {
+        let path = self
+            .mmu
+            .read_c_string(path_addr as usize)
+            .map_err(|_| EFAULT)?;
+        self.files
+            .open(&String::from_utf8_lossy(&path), flags, mode)
+    }
+
+    // only paths relative to the working directory and absolute ones are supported
+    fn sys_openat(&mut self, dirfd: u64, path_addr: u64, flags: u64, mode: u64) -> SyscallResult {
+        let path = self
+            .mmu
+            .read_c_string(path_addr as usize)
+            .map_err(|_| EFAULT)?;
+        if dirfd as i64 != AT_FDCWD && path.first() != Some(&b'/') {
+            return Err(EBADF);
+        }
+        self.files
+            .open(&String::from_utf8_lossy(&path), flags, mode)
+    }
+
+    fn sys_fstat(&mut self, fd: u64, stat_addr: u64) -> SyscallResult {
+        let mut stat = [0; STAT_SIZE];
+        let mut put = |offset: usize, value: u64, size: usize| {
+            stat[offset..offset + size].copy_from_slice(&value.to_le_bytes()[..size]);
+        };
+        match self.files.get_mut(fd)? {
+            OpenFile::Host(file) => {
+                let metadata = file.metadata().map_err(|err| errno(&err))?;
+                put(0, metadata.dev(), 8);
+                put(8, metadata.ino(), 8);
+                put(16, metadata.nlink(), 8);
+                put(24, metadata.mode() as u64, 4);
+                put(28, metadata.uid() as u64, 4);
+                put(32, metadata.gid() as u64, 4);
+                put(40, metadata.rdev(), 8);
+                put(48, metadata.size(), 8);
+                put(56, metadata.blksize(), 8);
+                put(64, metadata.blocks(), 8);
+                put(72, metadata.atime() as u64, 8);
+                put(80, metadata.atime_nsec() as u64, 8);
+                put(88, metadata.mtime() as u64, 8);
+                put(96, metadata.mtime_nsec() as u64, 8);
+                put(104, metadata.ctime() as u64, 8);
+                put(112, metadata.ctime_nsec() as u64, 8);
+            }
+            _ => {
+                put(24, (S_IFCHR | 0o620) as u64, 4);
+                put(56, PAGE_SIZE, 8);
+            }
+        }
+        self.mmu
+            .write_bytes(stat_addr as usize, &stat)
+            .map_err(|_| EFAULT)?;
+        Ok(0)
+    }
+
+    fn sys_lseek(&mut self, fd: u64, offset: u64, whence: u64) -> SyscallResult {
+        let file = match self.files.get_mut(fd)? {
+            OpenFile::Host(file) => file,
+            _ => return Err(ESPIPE),
+        };
+        let pos = match whence {
+            0 => SeekFrom::Start(offset),
+            1 => SeekFrom::Current(offset as i64),
+            2 => SeekFrom::End(offset as i64),
+            _ => return Err(EINVAL),
+        };
+        file.seek(pos).map_err(|err| errno(&err))
+    }
+
+    // the address hint is ignored, and file mappings are private copies
+    fn sys_mmap(
+        &mut self,
+        _addr: u64,
+        len: u64,
+        prot: u64,
+        flags: u64,
+        fd: u64,
+        offset: u64,
+    ) -> SyscallResult {
+        if len == 0 || flags & MAP_FIXED != 0 {
+            return Err(EINVAL);
+        }
+        let len = match page_align(len) {
+            Some(len) if len <= MAX_MAP_LEN => len,
+            _ => return Err(ENOMEM),
+        };
+        let addr = self.heap.mmap_next;
+        let next = addr.checked_add(len).ok_or(ENOMEM)?;
+
+        // only the file's bytes are read, and the rest of the mapping stays zero
+        let mut data = Vec::new();
+        if flags & MAP_ANONYMOUS == 0 {
+            match self.files.get_mut(fd)? {
+                OpenFile::Host(file) => {
+                    let mut chunk = [0; PAGE_SIZE as usize];
+                    while (data.len() as u64) < len {
+                        let pos = offset.checked_add(data.len() as u64).ok_or(EINVAL)?;
+                        match file.read_at(&mut chunk, pos) {
+                            Ok(0) => break,
+                            Ok(read) => data.extend_from_slice(&chunk[..read]),
+                            Err(err) => return Err(errno(&err)),
+                        }
+                    }
+                    data.truncate(len as usize);
+                }
+                _ => return Err(EBADF),
+            }
+        }
+
+        self.heap.mmap_next = next;
+        self.mmu
+            .map(addr as usize, len as usize, Perms::from_prot(prot));
+        self.mmu.poke(addr as usize, &data).map_err(|_| EFAULT)?;
+        Ok(addr)
+    }
+
+    fn sys_munmap(&mut self, addr: u64, len: u64) -> SyscallResult {
+        if !addr.is_multiple_of(PAGE_SIZE) || len == 0 || addr.checked_add(len).is_none() {
+            return Err(EINVAL);
+        }
+        self.mmu.unmap(addr as usize, len as usize);
+        Ok(0)
+    }
+
+    // returns the new break, or the current one if it can not be moved
+    fn sys_brk(&mut self, addr: u64) -> u64 {
+        let heap = &mut self.heap;
+        if heap.brk_start == 0 || addr < heap.brk_start {
+            return heap.brk;
+        }
+        if addr > heap.brk_end {
+            let new_end = match page_align(addr) {
+                Some(new_end) if new_end - heap.brk_end <= MAX_MAP_LEN => new_end,
+                _ => return heap.brk,
+            };
+            self.mmu.map(
+                heap.brk_end as usize,
+                (new_end - heap.brk_end) as usize,
+                Perms::RW,
+            );
+            heap.brk_end = new_end;
+        }
+        heap.brk = addr;
+        addr
+    }
+
+    // every clock is the real time
+    fn sys_clock_gettime(&mut self, timespec_addr: u64) -> SyscallResult {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let mut timespec = now.as_secs().to_le_bytes().to_vec();
+        timespec.extend_from_slice(&(now.subsec_nanos() as u64).to_le_bytes());
+        self.mmu
+            .write_bytes(timespec_addr as usize, &timespec)
+            .map_err(|_| EFAULT)?;
+        Ok(0)
+    }
+
+    // fills no more than the memory mapped at `buf_addr`
+    fn sys_getrandom(&mut self, buf_addr: u64, len: u64) -> SyscallResult {
+        let len = match self
+            .mmu
+            .accessible_len(buf_addr as usize, len as usize, Access::Write)
+        {
+            0 if len != 0 => return Err(EFAULT),
+            writable => writable as u64,
+        };
+        let bytes: Vec<u8> = (0..len)
+            .step_by(8)
+            .flat_map(|_| random_u64().to_le_bytes())
+            .take(len as usize)
+            .collect();
+        self.mmu
+            .write_bytes(buf_addr as usize, &bytes)
+            .map_err(|_| EFAULT)?;
+        Ok(len)
+    }
+}
+
+// None if rounding up passes the end of the address space
+fn page_align(addr: u64) -> Option<u64> {
+    Some(addr.checked_add(PAGE_SIZE - 1)? & !(PAGE_SIZE - 1))
+}
@@ -8,6 +8,16 @@ pub enum Value {
 }
 
 impl Value {
+    // the low bits of `value` which fit in `size`
+    pub fn from_u64(value: u64, size: &register::Size) -> Self {
+        match size {
+            register::Size::Byte => Value::Value8(value as u8),
+            register::Size::DWord => Value::Value32(value as u32),
+            register::Size::QWord => Value::Value64(value),
+            register::Size::Word => unimplemented!(),
+        }
+    }
+
     pub fn size(&self) -> register::Size {
         match self {
             Value::Value8(_) => register::Size::Byte,
@@ -0,0 +1,377 @@
+use std::{
+    collections::HashSet,
+    io::{self, Read, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+};
+
+use crate::emulator::{
+    cpu::{Flags, REGISTERS},
+    exit::ExitReason,
+    fault::Fault,
+    Emulator,
+};
+
+// numbers of rip and eflags in the `g` packet, after the general purpose registers
+const RIP_NUMBER: usize = 16;
+const EFLAGS_NUMBER: usize = 17;
+// cs, ss, ds, es, fs and gs, which are always 0
+const SEGMENT_COUNT: usize = 6;
+
+const FLAGS: [Flags; 6] = [
+    Flags::CF,
+    Flags::PF,
+    Flags::AF,
+    Flags::ZF,
+    Flags::SF,
+    Flags::OF,
+];
+
+// how often `continue` checks for an interrupt from gdb
+const INTERRUPT_INTERVAL: usize = 0x1000;
+
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+const SIGFPE: u8 = 8;
+const SIGSEGV: u8 = 11;
+
+// serves the GDB remote serial protocol for a single connection
+pub struct GdbStub<'a> {
+    emu: &'a mut Emulator,
+    stream: TcpStream,
+    breakpoints: HashSet<u64>,
+    // set once the program has ended
+    exit: Option<ExitReason>,
+}
+
+impl<'a> GdbStub<'a> {
+    // waits for gdb to connect on the loopback interface
+    pub fn listen(emu: &'a mut Emulator, port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        eprintln!("Waiting for gdb on 127.0.0.1:{}", port);
+        Self::accept(emu, &listener)
+    }
+
+    // waits for gdb to connect on a listener which is already bound
+    pub fn accept(emu: &'a mut Emulator, listener: &TcpListener) -> io::Result<Self> {
+        let (stream, _) = listener.accept()?;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            emu,
+            stream,
+            breakpoints: HashSet::new(),
+            exit: None,
+        })
+    }
+
+    // handles packets until gdb detaches or kills the program, and returns how the
+    // program ended if it did
+    pub fn serve(&mut self) -> io::Result<Option<ExitReason>> {
+        while let Some(packet) = self.read_packet()? {
+            let reply = match packet.as_bytes().first() {
+                Some(b'D') => {
+                    self.write_packet("OK")?;
+                    // the program runs on without gdb
+                    return Ok(Some(self.emu.run()));
+                }
+                Some(b'k') => return Ok(self.exit.clone()),
+                _ => self.handle(&packet)?,
+            };
+            self.write_packet(&reply)?;
+        }
+        Ok(self.exit.clone())
+    }
+
+    fn handle(&mut self, packet: &str) -> io::Result<String> {
+        let command = packet.get(..1).unwrap_or("");
+        let args = packet.get(1..).unwrap_or("");
+        let reply = match command {
+            "?" => self.stop_reply(None),
+            "g" => self.read_registers(),
+            "G" => self.write_registers(args),
+            "p" => match usize::from_str_radix(args, 16) {
+                Ok(number) => self.read_register(number),
+                Err(_) => "E01".to_string(),
+            },
+            "P" => self.write_register(args),
+            "m" => self.read_memory(args),
+            "M" => self.write_memory(args),
+            "Z" | "z" => self.breakpoint(command == "Z", args),
+            "s" => {
+                let reason = self.resume(true)?;
+                self.stop_reply(reason)
+            }
+            "c" => {
+                let reason = self.resume(false)?;
+                self.stop_reply(reason)
+            }
+            "H" => "OK".to_string(),
+            "q" => self.query(args),
+            // unsupported packets get an empty reply
+            _ => String::new(),
+        };
+        Ok(reply)
+    }
+
+    fn query(&self, args: &str) -> String {
+        if args.starts_with("Supported") {
+            return "PacketSize=4000;swbreak+".to_string();
+        }
+        match args {
+            "Attached" => "1".to_string(),
+            "C" => "QC1".to_string(),
+            "fThreadInfo" => "m1".to_string(),
+            "sThreadInfo" => "l".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    // executes until a breakpoint, the end of the program or an interrupt from gdb
+    fn resume(&mut self, single_step: bool) -> io::Result<Option<ExitReason>> {
+        if self.exit.is_some() {
+            return Ok(self.exit.clone());
+        }
+
+        let mut executed = 0;
+        loop {
+            if let Some(reason) = self.emu.step() {
+                self.exit = Some(reason.clone());
+                return Ok(Some(reason));
+            }
+            executed += 1;
+            if single_step || self.breakpoints.contains(&self.emu.cpu.get_rip()) {
+                return Ok(None);
+            }
+            if executed % INTERRUPT_INTERVAL == 0 && self.interrupted()? {
+                return Ok(None);
+            }
+        }
+    }
+
+    // whether gdb sent ^C
+    fn interrupted(&mut self) -> io::Result<bool> {
+        let mut byte = [0];
+        self.stream.set_nonblocking(true)?;
+        let interrupted = matches!(self.stream.read(&mut byte), Ok(1) if byte[0] == 0x03);
+        self.stream.set_nonblocking(false)?;
+        Ok(interrupted)
+    }
+
+    fn stop_reply(&self, reason: Option<ExitReason>) -> String {
+        match reason {
+            // tells gdb that rip is already at the breakpoint
+            None if self.breakpoints.contains(&self.emu.cpu.get_rip()) => {
+                format!("T{:02x}swbreak:;", SIGTRAP)
+            }
+            None => format!("S{:02x}", SIGTRAP),
+            Some(ExitReason::Exited(code)) => format!("W{:02x}", code),
+            Some(ExitReason::Fault(Fault::InvalidOpcode { .. })) => format!("S{:02x}", SIGILL),
+            Some(ExitReason::Fault(Fault::PageFault { .. })) => format!("S{:02x}", SIGSEGV),
+            Some(ExitReason::Fault(Fault::DivideError)) => format!("S{:02x}", SIGFPE),
+            Some(ExitReason::InstructionLimit) => format!("S{:02x}", SIGTRAP),
+        }
+    }
+
+    fn read_registers(&self) -> String {
+        (0..EFLAGS_NUMBER + 1 + SEGMENT_COUNT)
+            .map(|number| self.read_register(number))
+            .collect()
+    }
+
+    fn write_registers(&mut self, args: &str) -> String {
+        let bytes = match decode_hex(args) {
+            Some(bytes) => bytes,
+            None => return "E01".to_string(),
+        };
+        let mut pos = 0;
+        for number in 0..EFLAGS_NUMBER + 1 {
+            let size = register_size(number);
+            if pos + size > bytes.len() {
+                break;
+            }
+            self.set_register(number, le_value(&bytes[pos..pos + size]));
+            pos += size;
+        }
+        "OK".to_string()
+    }
+
+    fn read_register(&self, number: usize) -> String {
+        let value = match number {
+            0..=15 => self.emu.cpu.get_register64(&REGISTERS[number]),
+            RIP_NUMBER => self.emu.cpu.get_rip(),
+            EFLAGS_NUMBER => FLAGS
+                .iter()
+                .filter(|flag| self.emu.cpu.get_flag(**flag))
+                .fold(0b10, |eflags, flag| eflags | 1 << *flag as u64),
+            _ if number < EFLAGS_NUMBER + 1 + SEGMENT_COUNT => 0,
+            _ => return "E01".to_string(),
+        };
+        encode_hex(&value.to_le_bytes()[..register_size(number)])
+    }
+
+    fn write_register(&mut self, args: &str) -> String {
+        let (number, value) = match args.split_once('=') {
+            Some((number, value)) => (number, value),
+            None => return "E01".to_string(),
+        };
+        match (usize::from_str_radix(number, 16), decode_hex(value)) {
+            (Ok(number), Some(bytes)) if number <= EFLAGS_NUMBER => {
+                self.set_register(number, le_value(&bytes));
+                "OK".to_string()
+            }
+            // segment registers can not be changed
+            (Ok(_), Some(_)) => "OK".to_string(),
+            _ => "E01".to_string(),
+        }
+    }
+
+    fn set_register(&mut self, number: usize, value: u64) {
+        match number {
+            0..=15 => self.emu.cpu.set_register64(&REGISTERS[number], value),
+            RIP_NUMBER => self.emu.cpu.set_rip(value),
+            EFLAGS_NUMBER => {
+                for flag in FLAGS.iter().copied() {
+                    let bit = flag as u64;
+                    self.emu.cpu.set_flag(flag, value & 1 << bit != 0);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn read_memory(&self, args: &str) -> String {
+        let (addr, len) = match parse_range(args) {
+            Some(range) => range,
+            None => return "E01".to_string(),
+        };
+        let mut bytes = Vec::new();
+        for i in 0..len {
+            // the end of the address space is not readable either
+            let value = addr
+                .checked_add(i)
+                .and_then(|addr| self.emu.mmu.get_memory8(addr as usize).ok());
+            match value {
+                Some(value) => bytes.push(value),
+                // gdb accepts fewer bytes than it asked for
+                None if i != 0 => break,
+                None => return "E14".to_string(),
+            }
+        }
+        encode_hex(&bytes)
+    }
+
+    fn write_memory(&mut self, args: &str) -> String {
+        let (range, data) = match args.split_once(':') {
+            Some(parts) => parts,
+            None => return "E01".to_string(),
+        };
+        let (addr, bytes) = match (parse_range(range), decode_hex(data)) {
+            (Some((addr, len)), Some(bytes)) if bytes.len() as u64 == len => (addr, bytes),
+            _ => return "E01".to_string(),
+        };
+        // gdb may patch code, which the guest itself can not write
+        match self.emu.mmu.poke(addr as usize, &bytes) {
+            Ok(()) => "OK".to_string(),
+            Err(_) => "E14".to_string(),
+        }
+    }
+
+    // only software breakpoints, which eir checks without patching the code
+    fn breakpoint(&mut self, insert: bool, args: &str) -> String {
+        let mut fields = args.split(',');
+        let addr = match (fields.next(), fields.next()) {
+            (Some("0"), Some(addr)) => u64::from_str_radix(addr, 16),
+            _ => return String::new(),
+        };
+        match addr {
+            Ok(addr) if insert => self.breakpoints.insert(addr),
+            Ok(addr) => self.breakpoints.remove(&addr),
+            Err(_) => return "E01".to_string(),
+        };
+        "OK".to_string()
+    }
+
+    // returns the content of the next packet, or None when gdb disconnects
+    fn read_packet(&mut self) -> io::Result<Option<String>> {
+        let mut byte = [0];
+        loop {
+            // skip acks and interrupts sent while the program is stopped
+            loop {
+                if self.stream.read(&mut byte)? == 0 {
+                    return Ok(None);
+                }
+                if byte[0] == b'$' {
+                    break;
+                }
+            }
+
+            let mut data = Vec::new();
+            loop {
+                if self.stream.read(&mut byte)? == 0 {
+                    return Ok(None);
+                }
+                if byte[0] == b'#' {
+                    break;
+                }
+                data.push(byte[0]);
+            }
+            let mut checksum = [0; 2];
+            self.stream.read_exact(&mut checksum)?;
+
+            let expected = std::str::from_utf8(&checksum)
+                .ok()
+                .and_then(|checksum| u8::from_str_radix(checksum, 16).ok());
+            if expected == Some(checksum_of(&data)) {
+                self.stream.write_all(b"+")?;
+                return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+            }
+            self.stream.write_all(b"-")?;
+        }
+    }
+
+    fn write_packet(&mut self, data: &str) -> io::Result<()> {
+        let packet = format!("${}#{:02x}", data, checksum_of(data.as_bytes()));
+        self.stream.write_all(packet.as_bytes())
+    }
+}
+
+fn register_size(number: usize) -> usize {
+    if number <= RIP_NUMBER {
+        8
+    } else {
+        4
+    }
+}
+
+fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0, |sum, byte| sum.wrapping_add(*byte))
+}
+
+// `addr,len` in hex
+fn parse_range(args: &str) -> Option<(u64, u64)> {
+    let (addr, len) = args.split_once(',')?;
+    Some((
+        u64::from_str_radix(addr, 16).ok()?,
+        u64::from_str_radix(len, 16).ok()?,
+    ))
+}
+
+fn le_value(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0, |value, byte| value << 8 | *byte as u64)
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|pos| u8::from_str_radix(hex.get(pos..pos + 2)?, 16).ok())
+        .collect()
+}
@@ -1 +1,5 @@
+pub mod debugger;
 pub mod emulator;
+pub mod gdb;
+pub mod profile;
+pub mod trace;
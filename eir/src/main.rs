@@ -1,20 +1,163 @@
-use eir::emulator::Emulator;
-use std::env;
+use eir::{
+    debugger::Debugger,
+    emulator::{exit::ExitReason, Emulator},
+    gdb::GdbStub,
+    profile::Profile,
+    trace::Tracer,
+};
+use std::{env, fs::File, io::BufWriter, path::Path, process, time::Instant};
 
 extern crate eir;
 
+// flat images are loaded here by default, like a boot sector
+const DEFAULT_ORIGIN: u64 = 0x7c00;
+
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if !matches!(args.len(), 2 | 3) {
-        println!("Usage: eir <file>");
+    let mut files = Vec::new();
+    let mut dump = false;
+    let mut debug = false;
+    let mut gdb_port = None;
+    let mut origin = None;
+    let mut instruction_limit = None;
+    let mut sandbox = None;
+    let mut stats = false;
+    let mut trace_path = None;
+    let mut profile = false;
+    // the guest's argv after argv[0]
+    let mut guest_args = Vec::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dump" => dump = true,
+            "--debug" => debug = true,
+            "--gdb" => gdb_port = args.next().and_then(|port| port.parse().ok()),
+            "--binary" => origin = Some(DEFAULT_ORIGIN),
+            "--origin" => origin = args.next().and_then(|addr| parse_number(&addr)),
+            "--max-instructions" => {
+                instruction_limit = args.next().and_then(|limit| parse_number(&limit))
+            }
+            "--sandbox" => sandbox = args.next(),
+            "--stats" => stats = true,
+            "--trace" => trace_path = args.next(),
+            "--profile" => profile = true,
+            "--" => guest_args.extend(args.by_ref()),
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 1 {
+        println!(
+            "Usage: eir <file> [--dump] [--binary] [--origin <addr>] [--max-instructions <n>] [--debug] [--gdb <port>] [--sandbox <dir>] [--stats] [--trace <file>] [--profile] [-- <args>...]"
+        );
         return;
     }
-
-    let file = args[1].as_str();
-    let dump = args.get(2).map_or(false, |arg| arg == "--dump");
+    let file = &files[0];
 
     let mut emu = Emulator::new(0x7C00, 0x7c00);
     emu.dump_state = dump;
-    emu.load_elf(file);
-    emu.run()
+    emu.instruction_limit = instruction_limit;
+    if let Some(dir) = sandbox {
+        emu.files
+            .set_sandbox(Path::new(&dir))
+            .expect("Failed to open the sandbox directory.");
+    }
+    let loaded = match origin {
+        Some(origin) => emu.load_binary(file, origin),
+        None => emu.load_elf(file),
+    };
+    if let Err(err) = loaded {
+        println!("failed to load {}: {}", file, err);
+        process::exit(1);
+    }
+    if origin.is_none() {
+        let argv: Vec<String> = Some(file.clone()).into_iter().chain(guest_args).collect();
+        let envp: Vec<String> = env::vars()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        emu.init_stack(&argv, &envp)
+            .expect("Failed to set up the stack.");
+    }
+
+    if let Some(port) = gdb_port {
+        let reason = GdbStub::listen(&mut emu, port).and_then(|mut stub| stub.serve());
+        match reason {
+            Ok(Some(reason)) => {
+                println!("{}", reason);
+                exit(&reason);
+            }
+            Ok(None) => return,
+            Err(err) => {
+                println!("gdb connection failed: {}", err);
+                process::exit(1);
+            }
+        }
+    }
+
+    if debug {
+        // the debugger reports how the program ended itself
+        if let Some(reason) = Debugger::new(&mut emu).run() {
+            exit(&reason);
+        }
+        return;
+    }
+
+    let mut tracer = trace_path.map(|path| {
+        let file = File::create(path).expect("Failed to create the trace file.");
+        Tracer::new(&mut emu, BufWriter::new(file))
+    });
+    let mut profile = if profile {
+        Some(Profile::default())
+    } else {
+        None
+    };
+
+    let start = Instant::now();
+    let reason = if tracer.is_some() || profile.is_some() {
+        emu.run_with(|emu, rip| {
+            if let Some(tracer) = &mut tracer {
+                tracer.record(emu, rip).expect("Failed to write the trace.");
+            }
+            if let Some(profile) = &mut profile {
+                profile.record(rip);
+            }
+        })
+    } else {
+        emu.run()
+    };
+    let elapsed = start.elapsed().as_secs_f64();
+    // flushes the trace
+    drop(tracer);
+    if let ExitReason::Fault(_) = reason {
+        emu.dump();
+    }
+    println!("{}", reason);
+    if stats {
+        // on stderr, apart from the guest's output
+        let instructions = emu.stats.instructions;
+        eprintln!(
+            "{} instructions in {:.3}s ({:.0} instructions/s), {} blocks decoded",
+            instructions,
+            elapsed,
+            instructions as f64 / elapsed,
+            emu.stats.blocks
+        );
+    }
+    if let Some(profile) = profile {
+        eprint!("{}", profile.report(&emu));
+    }
+    exit(&reason);
+}
+
+fn exit(reason: &ExitReason) -> ! {
+    match reason {
+        ExitReason::Exited(code) => process::exit(*code as i32),
+        _ => process::exit(1),
+    }
+}
+
+fn parse_number(s: &str) -> Option<u64> {
+    match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
 }
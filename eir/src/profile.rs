@@ -0,0 +1,39 @@
+use std::collections::HashMap;
+
+use crate::emulator::Emulator;
+
+// counts executed instructions per address, which are attributed to symbols
+// once the program has ended
+#[derive(Default)]
+pub struct Profile {
+    counts: HashMap<u64, u64>,
+}
+
+impl Profile {
+    pub fn record(&mut self, rip: u64) {
+        *self.counts.entry(rip).or_insert(0) += 1;
+    }
+
+    // a flat profile with the symbols executing the most instructions first.
+    // instructions outside any symbol are counted under their page
+    pub fn report(&self, emu: &Emulator) -> String {
+        let mut per_symbol: HashMap<String, u64> = HashMap::new();
+        for (rip, count) in &self.counts {
+            let name = match emu.symbolize(*rip) {
+                Some((name, _)) => name.to_string(),
+                None => format!("{:#x}", rip & !0xfff),
+            };
+            *per_symbol.entry(name).or_insert(0) += count;
+        }
+        let total: u64 = per_symbol.values().sum();
+        let mut entries: Vec<(String, u64)> = per_symbol.into_iter().collect();
+        entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let mut report = format!("{:>7}  {:>12}  symbol\n", "%", "instructions");
+        for (name, count) in entries {
+            let percent = count as f64 * 100.0 / total as f64;
+            report.push_str(&format!("{:>7.2}  {:>12}  {}\n", percent, count, name));
+        }
+        report
+    }
+}
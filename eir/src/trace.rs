@@ -0,0 +1,90 @@
+use std::io::{self, Write};
+
+use crate::emulator::{
+    cpu::{Flags, REGISTERS},
+    decode::format_inst,
+    Emulator,
+};
+
+const FLAGS: [(Flags, &str); 6] = [
+    (Flags::CF, "cf"),
+    (Flags::PF, "pf"),
+    (Flags::AF, "af"),
+    (Flags::ZF, "zf"),
+    (Flags::SF, "sf"),
+    (Flags::OF, "of"),
+];
+
+// writes a line per executed instruction: its address, its disassembly, and the
+// registers, flags and memory it changed, e.g.
+// `0x401004	push rbp	rsp=0x7ffffffee2d8 [0x7ffffffee2d8]=0x0`
+pub struct Tracer<W: Write> {
+    out: W,
+    registers: [u64; 16],
+    flags: [bool; 6],
+}
+
+impl<W: Write> Tracer<W> {
+    // starts recording the emulator's memory writes
+    pub fn new(emu: &mut Emulator, out: W) -> Self {
+        emu.mmu.log_writes = true;
+        Self {
+            out,
+            registers: read_registers(emu),
+            flags: read_flags(emu),
+        }
+    }
+
+    // traces the instruction at `rip`, which has just been executed
+    pub fn record(&mut self, emu: &mut Emulator, rip: u64) -> io::Result<()> {
+        let inst = match emu.decode_at(rip) {
+            Ok((inst, inst_len)) => {
+                format_inst(rip, &inst, inst_len, |target| format!("{:#x}", target))
+            }
+            Err(fault) => fault.to_string(),
+        };
+
+        let mut changes = Vec::new();
+        let registers = read_registers(emu);
+        for (i, reg) in REGISTERS.iter().enumerate() {
+            if registers[i] != self.registers[i] {
+                changes.push(format!("{}={:#x}", reg, registers[i]));
+            }
+        }
+        let flags = read_flags(emu);
+        for (i, (_, name)) in FLAGS.iter().enumerate() {
+            if flags[i] != self.flags[i] {
+                changes.push(format!("{}={}", name, flags[i] as u8));
+            }
+        }
+        // writes of up to 8 bytes as a little-endian value, longer ones as bytes
+        for (addr, bytes) in emu.mmu.take_writes() {
+            let value = if bytes.len() <= 8 {
+                let value = bytes
+                    .iter()
+                    .rev()
+                    .fold(0, |value, byte| value << 8 | *byte as u64);
+                format!("{:#x}", value)
+            } else {
+                bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+            };
+            changes.push(format!("[{:#x}]={}", addr, value));
+        }
+        self.registers = registers;
+        self.flags = flags;
+
+        if changes.is_empty() {
+            writeln!(self.out, "{:#x}\t{}", rip, inst)
+        } else {
+            writeln!(self.out, "{:#x}\t{}\t{}", rip, inst, changes.join(" "))
+        }
+    }
+}
+
+fn read_registers(emu: &Emulator) -> [u64; 16] {
+    REGISTERS.map(|reg| emu.cpu.get_register64(&reg))
+}
+
+fn read_flags(emu: &Emulator) -> [bool; 6] {
+    FLAGS.map(|(flag, _)| emu.cpu.get_flag(flag))
+}
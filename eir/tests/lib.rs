@@ -0,0 +1,531 @@
+use eir::{
+    debugger::Debugger,
+    emulator::{
+        cpu::Flags,
+        exit::ExitReason,
+        fault::{Access, Fault},
+        files::{ELOOP, ENOENT},
+        io::CapturedIo,
+        mmu::Perms,
+        stack::{STACK_SIZE, STACK_TOP},
+        syscall::Heap,
+        Emulator,
+    },
+    gdb::GdbStub,
+    profile::Profile,
+    trace::Tracer,
+};
+use std::{
+    convert::TryInto,
+    env, fs,
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    os::unix::fs::symlink,
+    path::Path,
+    process, thread,
+};
+use x86asm::instruction::operand::register::Register;
+
+#[test]
+fn run_in_emulator() {
+    let bytes = fs::read("tests/testcases/hello").unwrap();
+
+    let io = CapturedIo::new(b"");
+    let mut emu = Emulator::new(0x7c00, 0x7c00);
+    emu.io = Box::new(io.clone());
+    emu.load_elf_bytes(&bytes).unwrap();
+    assert_eq!(emu.run(), ExitReason::Exited(42));
+    assert_eq!(io.stdout(), b"hello\n");
+
+    let mut emu = Emulator::new(0x7c00, 0x7c00);
+    emu.io = Box::new(CapturedIo::default());
+    emu.instruction_limit = Some(3);
+    emu.load_elf_bytes(&bytes).unwrap();
+    assert_eq!(emu.run(), ExitReason::InstructionLimit);
+}
+
+#[test]
+fn load_errors() {
+    let mut emu = Emulator::new(0x7c00, 0x7c00);
+    assert!(emu.load_elf("tests/testcases/missing").is_err());
+    assert!(emu.load_binary("tests/testcases/missing", 0x7c00).is_err());
+
+    // a loadable segment which claims more of the file than there is
+    let mut bytes = fs::read("tests/testcases/hello").unwrap();
+    let phoff = u64::from_le_bytes(bytes[0x20..0x28].try_into().unwrap()) as usize;
+    let phnum = u16::from_le_bytes(bytes[0x38..0x3a].try_into().unwrap()) as usize;
+    let load = (0..phnum)
+        .map(|i| phoff + i * 0x38)
+        .find(|&phdr| bytes[phdr..phdr + 4] == [1, 0, 0, 0])
+        .unwrap();
+    bytes[load + 0x20..load + 0x28].copy_from_slice(&u64::MAX.to_le_bytes());
+    let mut emu = Emulator::new(0x7c00, 0x7c00);
+    let err = emu.load_elf_bytes(&bytes).unwrap_err();
+    assert!(err.contains("past the end of the file"), "{}", err);
+}
+
+#[test]
+fn process_stack() {
+    let bytes = fs::read("tests/testcases/args").unwrap();
+
+    let io = CapturedIo::new(b"");
+    let mut emu = Emulator::new(0x7c00, 0x7c00);
+    emu.io = Box::new(io.clone());
+    emu.load_elf_bytes(&bytes).unwrap();
+    let argv = vec!["args".to_string(), "hi".to_string(), "x".to_string()];
+    emu.init_stack(&argv, &["HOME=/".to_string()]).unwrap();
+    // exits with argc after writing argv[1]
+    assert_eq!(emu.run(), ExitReason::Exited(3));
+    assert_eq!(io.stdout(), b"hi");
+}
+
+#[test]
+fn file_syscalls() {
+    let bytes = fs::read("tests/testcases/files").unwrap();
+    let sandbox = Path::new("tests/testcases/sandbox");
+
+    let io = CapturedIo::new(b"");
+    let mut emu = Emulator::new(0x7c00, 0x7c00);
+    emu.io = Box::new(io.clone());
+    emu.files.set_sandbox(sandbox).unwrap();
+    emu.load_elf_bytes(&bytes).unwrap();
+    assert_eq!(emu.run(), ExitReason::Exited(0));
+    assert_eq!(io.stdout(), b"from the sandbox\n");
+
+    // `..` does not leave the sandbox
+    let data = sandbox.canonicalize().unwrap().join("data.txt");
+    assert_eq!(emu.files.resolve("../../data.txt"), Ok(data.clone()));
+    assert_eq!(emu.files.resolve("/data.txt"), Ok(data));
+}
+
+#[test]
+fn sandbox_symlinks() {
+    let dir = env::temp_dir().join(format!("eir-sandbox-{}", process::id()));
+    let sandbox = dir.join("sandbox");
+    fs::create_dir_all(sandbox.join("sub")).unwrap();
+    fs::write(sandbox.join("data.txt"), "inside").unwrap();
+    fs::write(dir.join("outside.txt"), "outside").unwrap();
+    symlink("data.txt", sandbox.join("inner")).unwrap();
+    symlink("../data.txt", sandbox.join("sub/up")).unwrap();
+    symlink("/sub", sandbox.join("absolute")).unwrap();
+    symlink("../outside.txt", sandbox.join("escape")).unwrap();
+    symlink(dir.join("outside.txt"), sandbox.join("host")).unwrap();
+    symlink("missing.txt", sandbox.join("dangling")).unwrap();
+    symlink("loop", sandbox.join("loop")).unwrap();
+
+    let mut emu = Emulator::new(0x7c00, 0x7c00);
+    emu.files.set_sandbox(&sandbox).unwrap();
+    let root = sandbox.canonicalize().unwrap();
+    let data = root.join("data.txt");
+
+    // links within the sandbox are followed, with absolute targets from its root
+    assert_eq!(emu.files.resolve("inner"), Ok(data.clone()));
+    assert_eq!(emu.files.resolve("sub/up"), Ok(data.clone()));
+    assert_eq!(emu.files.resolve("absolute/up"), Ok(data));
+    assert!(emu.files.open("inner", 0, 0).is_ok());
+
+    // and ones which lead out of it stay in it
+    assert_eq!(emu.files.resolve("escape"), Ok(root.join("outside.txt")));
+    assert_eq!(emu.files.open("escape", 0, 0), Err(ENOENT));
+    assert_eq!(emu.files.open("host", 0, 0), Err(ENOENT));
+
+    // a dangling link creates its target in the sandbox
+    assert_eq!(emu.files.open("dangling", 0o101, 0o644).map(|_| ()), Ok(()));
+    assert!(root.join("missing.txt").exists());
+    assert_eq!(emu.files.open("loop", 0, 0), Err(ELOOP));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// runs a syscall with a page of code at 0x1000 and a page of memory after it
+fn run_syscall(emu: &mut Emulator, number: u64, args: &[u64]) -> u64 {
+    let regs = [Register::Rdi, Register::Rsi, Register::Rdx];
+    emu.cpu.set_rip(0x1000);
+    emu.mmu.map(0x1000, 0x2000, Perms::RWX);
+    emu.mmu.poke(0x1000, &[0x0f, 0x05]).unwrap();
+    emu.cpu.set_register64(&Register::Rax, number);
+    for (reg, arg) in regs.iter().zip(args) {
+        emu.cpu.set_register64(reg, *arg);
+    }
+    assert_eq!(emu.step(), None);
+    emu.cpu.get_register64(&Register::Rax)
+}
+
+#[test]
+fn syscall_sizes() {
+    let mut emu = Emulator::new(0x1000, 0x1000);
+    emu.io = Box::new(CapturedIo::new(b"hello"));
+    emu.heap = Heap::new(0x10_0000);
+
+    // reads and getrandom stop at the end of the mapped memory
+    assert_eq!(run_syscall(&mut emu, 0, &[0, 0x2ffe, u64::MAX]), 2);
+    assert_eq!(emu.mmu.read_bytes(0x2ffe, 2).unwrap(), b"he");
+    assert_eq!(run_syscall(&mut emu, 318, &[0x2f00, u64::MAX]), 0x100);
+    assert_eq!(run_syscall(&mut emu, 318, &[0x4000, 8]), -14i64 as u64);
+    assert_eq!(
+        run_syscall(&mut emu, 1, &[1, 0x2ffe, u64::MAX]),
+        -14i64 as u64
+    );
+
+    // mmap and brk refuse sizes which do not fit
+    assert_eq!(run_syscall(&mut emu, 9, &[0, u64::MAX, 3]), -12i64 as u64);
+    assert_eq!(run_syscall(&mut emu, 9, &[0, 1 << 40, 3]), -12i64 as u64);
+    assert_eq!(run_syscall(&mut emu, 12, &[u64::MAX]), 0x10_0000);
+    assert_eq!(run_syscall(&mut emu, 12, &[0x10_0010]), 0x10_0010);
+}
+
+#[test]
+fn page_permissions() {
+    let bytes = fs::read("tests/testcases/perms").unwrap();
+
+    let mut emu = Emulator::new(0x7c00, 0x7c00);
+    emu.load_elf_bytes(&bytes).unwrap();
+    emu.init_stack(&["perms".to_string()], &[]).unwrap();
+    let start = emu.symbols["_start"];
+    assert_eq!(
+        emu.run(),
+        ExitReason::Fault(Fault::PageFault {
+            addr: start,
+            access: Access::Write,
+        })
+    );
+    // rip stays at the faulting mov, and the code is unchanged
+    assert_eq!(emu.cpu.get_rip(), start + 7);
+    assert_eq!(emu.mmu.get_memory8(start as usize), Ok(0x48));
+
+    let mut emu = Emulator::new(0x7c00, 0x7c00);
+    emu.load_elf_bytes(&bytes).unwrap();
+    emu.init_stack(&["perms".to_string()], &[]).unwrap();
+    emu.cpu.set_rip(emu.symbols["exec_stack"]);
+    match emu.run() {
+        ExitReason::Fault(Fault::PageFault { addr, access }) => {
+            assert_eq!(access, Access::Exec);
+            assert!((STACK_TOP - STACK_SIZE..STACK_TOP).contains(&addr));
+        }
+        reason => panic!("unexpected exit: {}", reason),
+    }
+}
+
+#[test]
+fn address_space_edges() {
+    // mov rax, [rcx]
+    let code = &[0x48, 0x8b, 0x01];
+    let (_, reason) = run_code(code, 0, 0, 0);
+    let fault = Fault::PageFault {
+        addr: 0,
+        access: Access::Read,
+    };
+    assert_eq!(reason, Some(ExitReason::Fault(fault)));
+
+    // an access which wraps around the end of the address space
+    let mut emu = Emulator::new(0x1000, 0x1000);
+    let top = 0xffff_ffff_ffff_f000;
+    emu.mmu.map(top, 0x1000, Perms::RW);
+    let fault = Fault::PageFault {
+        addr: 0,
+        access: Access::Read,
+    };
+    assert_eq!(emu.mmu.get_memory64(usize::MAX - 3), Err(fault));
+    assert!(emu.mmu.set_memory64(usize::MAX - 3, 0).is_err());
+    assert_eq!(emu.mmu.get_memory32(usize::MAX - 3), Ok(0));
+}
+
+#[test]
+fn self_modifying_code() {
+    let bytes = fs::read("tests/testcases/smc").unwrap();
+
+    let mut emu = Emulator::new(0x7c00, 0x7c00);
+    emu.load_elf_bytes(&bytes).unwrap();
+    emu.init_stack(&["smc".to_string()], &[]).unwrap();
+    // the second call runs the rewritten code rather than the cached block
+    assert_eq!(emu.run(), ExitReason::Exited(41));
+    assert_eq!(emu.stats.blocks, 6);
+}
+
+#[test]
+fn trace_and_profile() {
+    let bytes = fs::read("tests/testcases/hello").unwrap();
+
+    let mut emu = Emulator::new(0x7c00, 0x7c00);
+    emu.io = Box::new(CapturedIo::default());
+    emu.load_elf_bytes(&bytes).unwrap();
+    let entry = emu.cpu.get_rip();
+    let mut trace = Vec::new();
+    let mut tracer = Tracer::new(&mut emu, &mut trace);
+    let mut profile = Profile::default();
+    let reason = emu.run_with(|emu, rip| {
+        tracer.record(emu, rip).unwrap();
+        profile.record(rip);
+    });
+    assert_eq!(reason, ExitReason::Exited(42));
+
+    let trace = String::from_utf8(trace).unwrap();
+    let lines: Vec<&str> = trace.lines().collect();
+    assert_eq!(lines.len(), 8);
+    assert_eq!(lines[0], format!("{:#x}\tmov rax, 1\trax=0x1", entry));
+    // write(2) returns the number of bytes written
+    assert!(lines[4].ends_with("\tsyscall\trax=0x6"));
+
+    let report = profile.report(&emu);
+    assert_eq!(report.lines().nth(1), Some(" 100.00             8  _start"));
+}
+
+// (instruction, code, rax, rcx, rax after, defined flags after, defined flags)
+type AluResult = (&'static str, &'static [u8], u64, u64, u64, u64, u64);
+
+// results of the same instructions on an x86-64 CPU, from alu_results.c
+include!("testcases/alu_results.rs");
+
+const ALL_FLAGS: [Flags; 6] = [
+    Flags::CF,
+    Flags::PF,
+    Flags::AF,
+    Flags::ZF,
+    Flags::SF,
+    Flags::OF,
+];
+
+// runs the instruction `code` with `rax` and `rcx` and all flags set
+fn run_code(code: &[u8], rax: u64, rcx: u64, rdx: u64) -> (Emulator, Option<ExitReason>) {
+    let mut emu = Emulator::new(0x1000, 0x1000);
+    emu.mmu.map(0x1000, code.len(), Perms::RWX);
+    emu.mmu.poke(0x1000, code).unwrap();
+    emu.cpu.set_register64(&Register::Rax, rax);
+    emu.cpu.set_register64(&Register::Rcx, rcx);
+    emu.cpu.set_register64(&Register::Rdx, rdx);
+    for flag in ALL_FLAGS.iter() {
+        emu.cpu.set_flag(*flag, true);
+    }
+    let reason = emu.step();
+    (emu, reason)
+}
+
+#[test]
+fn alu_flags_match_hardware() {
+    for &(text, code, rax, rcx, rax_after, flags_after, defined) in ALU_RESULTS {
+        let (emu, reason) = run_code(code, rax, rcx, 0);
+        assert_eq!(reason, None, "{} with {:#x}, {:#x}", text, rax, rcx);
+        let flags = ALL_FLAGS
+            .iter()
+            .filter(|flag| emu.cpu.get_flag(**flag))
+            .fold(0, |flags, flag| flags | 1 << *flag as u64);
+        assert_eq!(
+            (emu.cpu.get_register64(&Register::Rax), flags & defined),
+            (rax_after, flags_after),
+            "{} with {:#x}, {:#x}",
+            text,
+            rax,
+            rcx
+        );
+    }
+}
+
+#[test]
+fn idiv() {
+    // idiv rcx
+    let code = &[0x48, 0xf7, 0xf9];
+    let (emu, reason) = run_code(code, 7, -2i64 as u64, 0);
+    assert_eq!(reason, None);
+    assert_eq!(emu.cpu.get_register64(&Register::Rax), -3i64 as u64);
+    assert_eq!(emu.cpu.get_register64(&Register::Rdx), 1);
+
+    // rdx:rax is a 128-bit dividend
+    let (emu, _) = run_code(code, 0, 4, 1);
+    assert_eq!(emu.cpu.get_register64(&Register::Rax), 1 << 62);
+    assert_eq!(emu.cpu.get_register64(&Register::Rdx), 0);
+
+    // idiv ecx takes edx:eax and clears the upper halves
+    let (emu, _) = run_code(&[0xf7, 0xf9], 0xffff_ffff_ffff_fff9, 2, 0xffff_ffff);
+    assert_eq!(emu.cpu.get_register64(&Register::Rax), 0xffff_fffd);
+    assert_eq!(emu.cpu.get_register64(&Register::Rdx), 0xffff_ffff);
+
+    // dividing by 0 or a quotient which does not fit is #DE, and rip stays
+    for &(rax, rcx, rdx) in &[(1, 0, 0), (1 << 63, -1i64 as u64, -1i64 as u64)] {
+        let (emu, reason) = run_code(code, rax, rcx, rdx);
+        assert_eq!(reason, Some(ExitReason::Fault(Fault::DivideError)));
+        assert_eq!(emu.cpu.get_rip(), 0x1000);
+    }
+}
+
+#[test]
+fn undefined_forms() {
+    // pop rsi, which pops its own bytes as rsp points at the code
+    let (emu, reason) = run_code(&[0x8f, 0xc6], 0, 0, 0);
+    assert_eq!(reason, None);
+    assert_eq!(emu.cpu.get_register64(&Register::Rsi), 0xc68f);
+
+    // 8f is only defined with /0
+    let (_, reason) = run_code(&[0x8f, 0xde], 0, 0, 0);
+    let fault = Fault::InvalidOpcode {
+        rip: 0x1000,
+        bytes: vec![0x8f, 0xde],
+    };
+    assert_eq!(reason, Some(ExitReason::Fault(fault)));
+
+    // movsx eax, al
+    let (emu, reason) = run_code(&[0x0f, 0xbe, 0xc0], 0x1234_5680, 0, 0);
+    assert_eq!(reason, None);
+    assert_eq!(emu.cpu.get_register64(&Register::Rax), 0xffff_ff80);
+
+    // lea with a register source is #UD, and rip stays
+    let (emu, reason) = run_code(&[0x48, 0x8d, 0xc0], 0, 0, 0);
+    let fault = Fault::InvalidOpcode {
+        rip: 0x1000,
+        bytes: vec![0x48, 0x8d, 0xc0],
+    };
+    assert_eq!(reason, Some(ExitReason::Fault(fault)));
+    assert_eq!(emu.cpu.get_rip(), 0x1000);
+}
+
+// calls square(5) and exits with the result
+fn load_calls() -> Emulator {
+    let bytes = fs::read("tests/testcases/calls").unwrap();
+    let mut emu = Emulator::new(0x7c00, 0x7c00);
+    emu.io = Box::new(CapturedIo::default());
+    emu.load_elf_bytes(&bytes).unwrap();
+    emu.init_stack(&["calls".to_string()], &[]).unwrap();
+    emu
+}
+
+#[test]
+fn debugger_breakpoints() {
+    let mut emu = load_calls();
+    let square = emu.symbols["square"];
+    let mut debugger = Debugger::new(&mut emu);
+    debugger.execute("break square").unwrap();
+    debugger.execute("continue").unwrap();
+    drop(debugger);
+    assert_eq!(emu.cpu.get_rip(), square);
+    assert_eq!(emu.cpu.get_register64(&Register::Rdi), 5);
+
+    // finish stops after the ret, and set changes the argument on the way
+    let mut debugger = Debugger::new(&mut emu);
+    debugger.execute("set $rdi = 7").unwrap();
+    debugger.execute("finish").unwrap();
+    drop(debugger);
+    // mov rdi, 5 and the call are 12 bytes
+    let return_addr = emu.symbols["_start"] + 12;
+    assert_eq!(emu.cpu.get_rip(), return_addr);
+    assert_eq!(emu.cpu.get_register64(&Register::Rax), 49);
+
+    // the program exits with the result
+    let mut debugger = Debugger::new(&mut emu);
+    assert_eq!(debugger.execute("continue"), Ok(true));
+    assert_eq!(
+        debugger.execute("step"),
+        Err("The program is not being run.".to_string())
+    );
+    drop(debugger);
+    assert_eq!(emu.cpu.get_register64(&Register::Rdi), 49);
+}
+
+#[test]
+fn debugger_next() {
+    let mut emu = load_calls();
+    let square = emu.symbols["square"];
+    // steps over the call to square
+    let mut debugger = Debugger::new(&mut emu);
+    debugger.execute("next 2").unwrap();
+    drop(debugger);
+    assert_eq!(emu.cpu.get_rip(), emu.symbols["_start"] + 12);
+    assert_eq!(emu.cpu.get_register64(&Register::Rax), 25);
+
+    // unless there is a breakpoint in it
+    let mut emu = load_calls();
+    let mut debugger = Debugger::new(&mut emu);
+    debugger.execute("break square").unwrap();
+    debugger.execute("next 2").unwrap();
+    drop(debugger);
+    assert_eq!(emu.cpu.get_rip(), square);
+}
+
+#[test]
+fn debugger_examine() {
+    let mut emu = load_calls();
+    let mut debugger = Debugger::new(&mut emu);
+    assert_eq!(debugger.execute("x/2g $rsp"), Ok(true));
+    assert_eq!(debugger.execute("x/4xb _start"), Ok(true));
+    assert_eq!(
+        debugger.execute("x/99999999999999999999x 0"),
+        Err("Invalid count: 99999999999999999999".to_string())
+    );
+    assert_eq!(
+        debugger.execute("x/2g 0xffffffffffffffff"),
+        Err("Cannot access memory at address 0xffffffffffffffff".to_string())
+    );
+    assert_eq!(
+        debugger.execute("x/2q 0"),
+        Err("Invalid format letter 'q'.".to_string())
+    );
+}
+
+// sends a packet and returns the reply, checking both checksums
+fn gdb_request(stream: &mut TcpStream, data: &str) -> String {
+    let checksum = data.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+    write!(stream, "${}#{:02x}", data, checksum).unwrap();
+
+    let mut reply = Vec::new();
+    let mut byte = [0];
+    stream.read_exact(&mut byte).unwrap();
+    assert_eq!(&byte, b"+");
+    stream.read_exact(&mut byte).unwrap();
+    assert_eq!(&byte, b"$");
+    loop {
+        stream.read_exact(&mut byte).unwrap();
+        if byte[0] == b'#' {
+            break;
+        }
+        reply.push(byte[0]);
+    }
+    let mut checksum = [0; 2];
+    stream.read_exact(&mut checksum).unwrap();
+    let expected = reply.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    assert_eq!(checksum, format!("{:02x}", expected).as_bytes());
+    stream.write_all(b"+").unwrap();
+    String::from_utf8(reply).unwrap()
+}
+
+#[test]
+fn gdb_stub() {
+    let mut emu = load_calls();
+    let entry = emu.cpu.get_rip();
+    let rsp = emu.cpu.get_register64(&Register::Rsp);
+    let square = emu.symbols["square"];
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let gdb = thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        // rip follows the 16 general purpose registers
+        let registers = gdb_request(&mut stream, "g");
+        assert_eq!(registers.len(), (17 * 8 + 7 * 4) * 2);
+        assert_eq!(registers[256..272], hex_le(entry));
+        // argc
+        let argc = gdb_request(&mut stream, &format!("m{:x},8", rsp));
+        assert_eq!(argc, "0100000000000000");
+        assert_eq!(gdb_request(&mut stream, "mffffffffffffffff,2"), "E14");
+
+        let breakpoint = format!("Z0,{:x},1", square);
+        assert_eq!(gdb_request(&mut stream, &breakpoint), "OK");
+        assert_eq!(gdb_request(&mut stream, "c"), "T05swbreak:;");
+        assert_eq!(gdb_request(&mut stream, "s"), "S05");
+        // mov rax, rdi is 3 bytes
+        assert_eq!(gdb_request(&mut stream, "p10"), hex_le(square + 3));
+        // exits with 25
+        assert_eq!(gdb_request(&mut stream, "c"), "W19");
+        stream.write_all(b"$k#6b").unwrap();
+    });
+
+    let mut stub = GdbStub::accept(&mut emu, &listener).unwrap();
+    let reason = stub.serve().unwrap();
+    gdb.join().unwrap();
+    assert_eq!(reason, Some(ExitReason::Exited(25)));
+}
+
+fn hex_le(value: u64) -> String {
+    value
+        .to_le_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -0,0 +1,139 @@
+// prints alu_results.rs, the results of ALU instructions on the host CPU, which
+// eir is tested against. run `make alu_results.rs` on x86-64 to regenerate it
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/mman.h>
+
+// CF, PF, AF, ZF, SF and OF
+#define ALL_FLAGS 0x8d5
+#define NO_AF 0x8c5
+
+struct inst {
+    const char *text;
+    uint8_t code[8];
+    int len;
+    // 0 for shifts, whose defined flags depend on the count
+    uint64_t defined;
+};
+
+static const struct inst alu[] = {
+    {"add rax, rcx", {0x48, 0x01, 0xc8}, 3, ALL_FLAGS},
+    {"add eax, ecx", {0x01, 0xc8}, 2, ALL_FLAGS},
+    {"sub rax, rcx", {0x48, 0x29, 0xc8}, 3, ALL_FLAGS},
+    {"sub eax, ecx", {0x29, 0xc8}, 2, ALL_FLAGS},
+    {"cmp rax, rcx", {0x48, 0x39, 0xc8}, 3, ALL_FLAGS},
+    {"cmp eax, ecx", {0x39, 0xc8}, 2, ALL_FLAGS},
+    {"and rax, rcx", {0x48, 0x21, 0xc8}, 3, NO_AF},
+    {"and eax, ecx", {0x21, 0xc8}, 2, NO_AF},
+    {"or rax, rcx", {0x48, 0x09, 0xc8}, 3, NO_AF},
+    {"or eax, ecx", {0x09, 0xc8}, 2, NO_AF},
+    {"xor rax, rcx", {0x48, 0x31, 0xc8}, 3, NO_AF},
+    {"xor eax, ecx", {0x31, 0xc8}, 2, NO_AF},
+    // only CF and OF are defined
+    {"imul rax, rcx", {0x48, 0x0f, 0xaf, 0xc1}, 4, 0x801},
+    {"imul eax, ecx", {0x0f, 0xaf, 0xc1}, 3, 0x801},
+    {"add rax, -1", {0x48, 0x83, 0xc0, 0xff}, 4, ALL_FLAGS},
+    {"cmp eax, -1", {0x83, 0xf8, 0xff}, 3, ALL_FLAGS},
+    {"and rax, 0xffffffff80000000", {0x48, 0x81, 0xe0, 0x00, 0x00, 0x00, 0x80}, 7, NO_AF},
+    // neither changes the flags
+    {"sete al", {0x0f, 0x94, 0xc0}, 3, ALL_FLAGS},
+    {"mov al, cl", {0x88, 0xc8}, 2, ALL_FLAGS},
+};
+
+static const uint64_t alu_inputs[][2] = {
+    {0, 0},
+    {1, 1},
+    {1, 0xffffffffffffffff},
+    {0x7fffffffffffffff, 1},
+    {0x8000000000000000, 0xffffffffffffffff},
+    {0x000000000000000f, 0x0000000000000001},
+    {0x123456789abcdef0, 0x0fedcba987654321},
+    {0xffffffff80000000, 0x000000007fffffff},
+    {0xdeadbeef7fffffff, 0x0000000100000001},
+    {0x0000000100000000, 0x0000000000000003},
+};
+
+static const struct inst shifts[] = {
+    {"shl rax, cl", {0x48, 0xd3, 0xe0}, 3, 0},
+    {"shl eax, cl", {0xd3, 0xe0}, 2, 0},
+    {"shr rax, cl", {0x48, 0xd3, 0xe8}, 3, 0},
+    {"shr eax, cl", {0xd3, 0xe8}, 2, 0},
+    {"sar rax, cl", {0x48, 0xd3, 0xf8}, 3, 0},
+    {"sar eax, cl", {0xd3, 0xf8}, 2, 0},
+};
+
+static const uint64_t shift_values[] = {
+    0x8000000000000001,
+    0x123456789abcdef0,
+    0xffffffff80000001,
+};
+
+static const uint64_t shift_counts[] = {0, 1, 3, 31, 32, 63, 65};
+
+// the instruction runs with rax = lhs, rcx = rhs and all of ALL_FLAGS set
+typedef void (*stub_fn)(uint64_t lhs, uint64_t rhs, uint64_t flags, uint64_t *out);
+
+static uint8_t *stub;
+
+static void run(const struct inst *inst, uint64_t lhs, uint64_t rhs, uint64_t defined) {
+    static const uint8_t prologue[] = {
+        0x49, 0x89, 0xc8, // mov r8, rcx
+        0x48, 0x89, 0xf8, // mov rax, rdi
+        0x48, 0x89, 0xf1, // mov rcx, rsi
+        0x52,             // push rdx
+        0x9d,             // popfq
+    };
+    static const uint8_t epilogue[] = {
+        0x9c,                   // pushfq
+        0x5a,                   // pop rdx
+        0x49, 0x89, 0x00,       // mov [r8], rax
+        0x49, 0x89, 0x50, 0x08, // mov [r8 + 8], rdx
+        0xc3,                   // ret
+    };
+    size_t len = 0;
+    memcpy(stub, prologue, sizeof(prologue));
+    len += sizeof(prologue);
+    memcpy(stub + len, inst->code, inst->len);
+    len += inst->len;
+    memcpy(stub + len, epilogue, sizeof(epilogue));
+
+    uint64_t out[2];
+    ((stub_fn)stub)(lhs, rhs, ALL_FLAGS | 0x2, out);
+
+    printf("    (\"%s\", &[", inst->text);
+    for (int i = 0; i < inst->len; i++) {
+        printf(i == 0 ? "0x%02x" : ", 0x%02x", inst->code[i]);
+    }
+    printf("], 0x%lx, 0x%lx, 0x%lx, 0x%lx, 0x%lx),\n", lhs, rhs, out[0], out[1] & defined,
+           defined);
+}
+
+int main(void) {
+    stub = mmap(NULL, 4096, PROT_READ | PROT_WRITE | PROT_EXEC, MAP_PRIVATE | MAP_ANONYMOUS,
+                -1, 0);
+    if (stub == MAP_FAILED) {
+        return 1;
+    }
+
+    printf("// generated by alu_results.c\n");
+    printf("const ALU_RESULTS: &[AluResult] = &[\n");
+    for (size_t i = 0; i < sizeof(alu) / sizeof(alu[0]); i++) {
+        for (size_t j = 0; j < sizeof(alu_inputs) / sizeof(alu_inputs[0]); j++) {
+            run(&alu[i], alu_inputs[j][0], alu_inputs[j][1], alu[i].defined);
+        }
+    }
+    for (size_t i = 0; i < sizeof(shifts) / sizeof(shifts[0]); i++) {
+        int is64 = shifts[i].code[0] == 0x48;
+        for (size_t j = 0; j < sizeof(shift_values) / sizeof(shift_values[0]); j++) {
+            for (size_t k = 0; k < sizeof(shift_counts) / sizeof(shift_counts[0]); k++) {
+                uint64_t count = shift_counts[k] & (is64 ? 0x3f : 0x1f);
+                // OF is only defined for shifts by 1, and AF never
+                uint64_t defined = count == 0 ? ALL_FLAGS : count == 1 ? NO_AF : 0xc5;
+                run(&shifts[i], shift_values[j], shift_counts[k], defined);
+            }
+        }
+    }
+    printf("];\n");
+    return 0;
+}
@@ -0,0 +1,319 @@
+// generated by alu_results.c
+const ALU_RESULTS: &[AluResult] = &[
+    ("add rax, rcx", &[0x48, 0x01, 0xc8], 0x0, 0x0, 0x0, 0x44, 0x8d5),
+    ("add rax, rcx", &[0x48, 0x01, 0xc8], 0x1, 0x1, 0x2, 0x0, 0x8d5),
+    ("add rax, rcx", &[0x48, 0x01, 0xc8], 0x1, 0xffffffffffffffff, 0x0, 0x55, 0x8d5),
+    ("add rax, rcx", &[0x48, 0x01, 0xc8], 0x7fffffffffffffff, 0x1, 0x8000000000000000, 0x894, 0x8d5),
+    ("add rax, rcx", &[0x48, 0x01, 0xc8], 0x8000000000000000, 0xffffffffffffffff, 0x7fffffffffffffff, 0x805, 0x8d5),
+    ("add rax, rcx", &[0x48, 0x01, 0xc8], 0xf, 0x1, 0x10, 0x10, 0x8d5),
+    ("add rax, rcx", &[0x48, 0x01, 0xc8], 0x123456789abcdef0, 0xfedcba987654321, 0x2222222222222211, 0x4, 0x8d5),
+    ("add rax, rcx", &[0x48, 0x01, 0xc8], 0xffffffff80000000, 0x7fffffff, 0xffffffffffffffff, 0x84, 0x8d5),
+    ("add rax, rcx", &[0x48, 0x01, 0xc8], 0xdeadbeef7fffffff, 0x100000001, 0xdeadbef080000000, 0x94, 0x8d5),
+    ("add rax, rcx", &[0x48, 0x01, 0xc8], 0x100000000, 0x3, 0x100000003, 0x4, 0x8d5),
+    ("add eax, ecx", &[0x01, 0xc8], 0x0, 0x0, 0x0, 0x44, 0x8d5),
+    ("add eax, ecx", &[0x01, 0xc8], 0x1, 0x1, 0x2, 0x0, 0x8d5),
+    ("add eax, ecx", &[0x01, 0xc8], 0x1, 0xffffffffffffffff, 0x0, 0x55, 0x8d5),
+    ("add eax, ecx", &[0x01, 0xc8], 0x7fffffffffffffff, 0x1, 0x0, 0x55, 0x8d5),
+    ("add eax, ecx", &[0x01, 0xc8], 0x8000000000000000, 0xffffffffffffffff, 0xffffffff, 0x84, 0x8d5),
+    ("add eax, ecx", &[0x01, 0xc8], 0xf, 0x1, 0x10, 0x10, 0x8d5),
+    ("add eax, ecx", &[0x01, 0xc8], 0x123456789abcdef0, 0xfedcba987654321, 0x22222211, 0x805, 0x8d5),
+    ("add eax, ecx", &[0x01, 0xc8], 0xffffffff80000000, 0x7fffffff, 0xffffffff, 0x84, 0x8d5),
+    ("add eax, ecx", &[0x01, 0xc8], 0xdeadbeef7fffffff, 0x100000001, 0x80000000, 0x894, 0x8d5),
+    ("add eax, ecx", &[0x01, 0xc8], 0x100000000, 0x3, 0x3, 0x4, 0x8d5),
+    ("sub rax, rcx", &[0x48, 0x29, 0xc8], 0x0, 0x0, 0x0, 0x44, 0x8d5),
+    ("sub rax, rcx", &[0x48, 0x29, 0xc8], 0x1, 0x1, 0x0, 0x44, 0x8d5),
+    ("sub rax, rcx", &[0x48, 0x29, 0xc8], 0x1, 0xffffffffffffffff, 0x2, 0x11, 0x8d5),
+    ("sub rax, rcx", &[0x48, 0x29, 0xc8], 0x7fffffffffffffff, 0x1, 0x7ffffffffffffffe, 0x0, 0x8d5),
+    ("sub rax, rcx", &[0x48, 0x29, 0xc8], 0x8000000000000000, 0xffffffffffffffff, 0x8000000000000001, 0x91, 0x8d5),
+    ("sub rax, rcx", &[0x48, 0x29, 0xc8], 0xf, 0x1, 0xe, 0x0, 0x8d5),
+    ("sub rax, rcx", &[0x48, 0x29, 0xc8], 0x123456789abcdef0, 0xfedcba987654321, 0x2468acf13579bcf, 0x14, 0x8d5),
+    ("sub rax, rcx", &[0x48, 0x29, 0xc8], 0xffffffff80000000, 0x7fffffff, 0xffffffff00000001, 0x90, 0x8d5),
+    ("sub rax, rcx", &[0x48, 0x29, 0xc8], 0xdeadbeef7fffffff, 0x100000001, 0xdeadbeee7ffffffe, 0x80, 0x8d5),
+    ("sub rax, rcx", &[0x48, 0x29, 0xc8], 0x100000000, 0x3, 0xfffffffd, 0x10, 0x8d5),
+    ("sub eax, ecx", &[0x29, 0xc8], 0x0, 0x0, 0x0, 0x44, 0x8d5),
+    ("sub eax, ecx", &[0x29, 0xc8], 0x1, 0x1, 0x0, 0x44, 0x8d5),
+    ("sub eax, ecx", &[0x29, 0xc8], 0x1, 0xffffffffffffffff, 0x2, 0x11, 0x8d5),
+    ("sub eax, ecx", &[0x29, 0xc8], 0x7fffffffffffffff, 0x1, 0xfffffffe, 0x80, 0x8d5),
+    ("sub eax, ecx", &[0x29, 0xc8], 0x8000000000000000, 0xffffffffffffffff, 0x1, 0x11, 0x8d5),
+    ("sub eax, ecx", &[0x29, 0xc8], 0xf, 0x1, 0xe, 0x0, 0x8d5),
+    ("sub eax, ecx", &[0x29, 0xc8], 0x123456789abcdef0, 0xfedcba987654321, 0x13579bcf, 0x14, 0x8d5),
+    ("sub eax, ecx", &[0x29, 0xc8], 0xffffffff80000000, 0x7fffffff, 0x1, 0x810, 0x8d5),
+    ("sub eax, ecx", &[0x29, 0xc8], 0xdeadbeef7fffffff, 0x100000001, 0x7ffffffe, 0x0, 0x8d5),
+    ("sub eax, ecx", &[0x29, 0xc8], 0x100000000, 0x3, 0xfffffffd, 0x91, 0x8d5),
+    ("cmp rax, rcx", &[0x48, 0x39, 0xc8], 0x0, 0x0, 0x0, 0x44, 0x8d5),
+    ("cmp rax, rcx", &[0x48, 0x39, 0xc8], 0x1, 0x1, 0x1, 0x44, 0x8d5),
+    ("cmp rax, rcx", &[0x48, 0x39, 0xc8], 0x1, 0xffffffffffffffff, 0x1, 0x11, 0x8d5),
+    ("cmp rax, rcx", &[0x48, 0x39, 0xc8], 0x7fffffffffffffff, 0x1, 0x7fffffffffffffff, 0x0, 0x8d5),
+    ("cmp rax, rcx", &[0x48, 0x39, 0xc8], 0x8000000000000000, 0xffffffffffffffff, 0x8000000000000000, 0x91, 0x8d5),
+    ("cmp rax, rcx", &[0x48, 0x39, 0xc8], 0xf, 0x1, 0xf, 0x0, 0x8d5),
+    ("cmp rax, rcx", &[0x48, 0x39, 0xc8], 0x123456789abcdef0, 0xfedcba987654321, 0x123456789abcdef0, 0x14, 0x8d5),
+    ("cmp rax, rcx", &[0x48, 0x39, 0xc8], 0xffffffff80000000, 0x7fffffff, 0xffffffff80000000, 0x90, 0x8d5),
+    ("cmp rax, rcx", &[0x48, 0x39, 0xc8], 0xdeadbeef7fffffff, 0x100000001, 0xdeadbeef7fffffff, 0x80, 0x8d5),
+    ("cmp rax, rcx", &[0x48, 0x39, 0xc8], 0x100000000, 0x3, 0x100000000, 0x10, 0x8d5),
+    ("cmp eax, ecx", &[0x39, 0xc8], 0x0, 0x0, 0x0, 0x44, 0x8d5),
+    ("cmp eax, ecx", &[0x39, 0xc8], 0x1, 0x1, 0x1, 0x44, 0x8d5),
+    ("cmp eax, ecx", &[0x39, 0xc8], 0x1, 0xffffffffffffffff, 0x1, 0x11, 0x8d5),
+    ("cmp eax, ecx", &[0x39, 0xc8], 0x7fffffffffffffff, 0x1, 0x7fffffffffffffff, 0x80, 0x8d5),
+    ("cmp eax, ecx", &[0x39, 0xc8], 0x8000000000000000, 0xffffffffffffffff, 0x8000000000000000, 0x11, 0x8d5),
+    ("cmp eax, ecx", &[0x39, 0xc8], 0xf, 0x1, 0xf, 0x0, 0x8d5),
+    ("cmp eax, ecx", &[0x39, 0xc8], 0x123456789abcdef0, 0xfedcba987654321, 0x123456789abcdef0, 0x14, 0x8d5),
+    ("cmp eax, ecx", &[0x39, 0xc8], 0xffffffff80000000, 0x7fffffff, 0xffffffff80000000, 0x810, 0x8d5),
+    ("cmp eax, ecx", &[0x39, 0xc8], 0xdeadbeef7fffffff, 0x100000001, 0xdeadbeef7fffffff, 0x0, 0x8d5),
+    ("cmp eax, ecx", &[0x39, 0xc8], 0x100000000, 0x3, 0x100000000, 0x91, 0x8d5),
+    ("and rax, rcx", &[0x48, 0x21, 0xc8], 0x0, 0x0, 0x0, 0x44, 0x8c5),
+    ("and rax, rcx", &[0x48, 0x21, 0xc8], 0x1, 0x1, 0x1, 0x0, 0x8c5),
+    ("and rax, rcx", &[0x48, 0x21, 0xc8], 0x1, 0xffffffffffffffff, 0x1, 0x0, 0x8c5),
+    ("and rax, rcx", &[0x48, 0x21, 0xc8], 0x7fffffffffffffff, 0x1, 0x1, 0x0, 0x8c5),
+    ("and rax, rcx", &[0x48, 0x21, 0xc8], 0x8000000000000000, 0xffffffffffffffff, 0x8000000000000000, 0x84, 0x8c5),
+    ("and rax, rcx", &[0x48, 0x21, 0xc8], 0xf, 0x1, 0x1, 0x0, 0x8c5),
+    ("and rax, rcx", &[0x48, 0x21, 0xc8], 0x123456789abcdef0, 0xfedcba987654321, 0x224422882244220, 0x0, 0x8c5),
+    ("and rax, rcx", &[0x48, 0x21, 0xc8], 0xffffffff80000000, 0x7fffffff, 0x0, 0x44, 0x8c5),
+    ("and rax, rcx", &[0x48, 0x21, 0xc8], 0xdeadbeef7fffffff, 0x100000001, 0x100000001, 0x0, 0x8c5),
+    ("and rax, rcx", &[0x48, 0x21, 0xc8], 0x100000000, 0x3, 0x0, 0x44, 0x8c5),
+    ("and eax, ecx", &[0x21, 0xc8], 0x0, 0x0, 0x0, 0x44, 0x8c5),
+    ("and eax, ecx", &[0x21, 0xc8], 0x1, 0x1, 0x1, 0x0, 0x8c5),
+    ("and eax, ecx", &[0x21, 0xc8], 0x1, 0xffffffffffffffff, 0x1, 0x0, 0x8c5),
+    ("and eax, ecx", &[0x21, 0xc8], 0x7fffffffffffffff, 0x1, 0x1, 0x0, 0x8c5),
+    ("and eax, ecx", &[0x21, 0xc8], 0x8000000000000000, 0xffffffffffffffff, 0x0, 0x44, 0x8c5),
+    ("and eax, ecx", &[0x21, 0xc8], 0xf, 0x1, 0x1, 0x0, 0x8c5),
+    ("and eax, ecx", &[0x21, 0xc8], 0x123456789abcdef0, 0xfedcba987654321, 0x82244220, 0x80, 0x8c5),
+    ("and eax, ecx", &[0x21, 0xc8], 0xffffffff80000000, 0x7fffffff, 0x0, 0x44, 0x8c5),
+    ("and eax, ecx", &[0x21, 0xc8], 0xdeadbeef7fffffff, 0x100000001, 0x1, 0x0, 0x8c5),
+    ("and eax, ecx", &[0x21, 0xc8], 0x100000000, 0x3, 0x0, 0x44, 0x8c5),
+    ("or rax, rcx", &[0x48, 0x09, 0xc8], 0x0, 0x0, 0x0, 0x44, 0x8c5),
+    ("or rax, rcx", &[0x48, 0x09, 0xc8], 0x1, 0x1, 0x1, 0x0, 0x8c5),
+    ("or rax, rcx", &[0x48, 0x09, 0xc8], 0x1, 0xffffffffffffffff, 0xffffffffffffffff, 0x84, 0x8c5),
+    ("or rax, rcx", &[0x48, 0x09, 0xc8], 0x7fffffffffffffff, 0x1, 0x7fffffffffffffff, 0x4, 0x8c5),
+    ("or rax, rcx", &[0x48, 0x09, 0xc8], 0x8000000000000000, 0xffffffffffffffff, 0xffffffffffffffff, 0x84, 0x8c5),
+    ("or rax, rcx", &[0x48, 0x09, 0xc8], 0xf, 0x1, 0xf, 0x4, 0x8c5),
+    ("or rax, rcx", &[0x48, 0x09, 0xc8], 0x123456789abcdef0, 0xfedcba987654321, 0x1ffddff99ffddff1, 0x0, 0x8c5),
+    ("or rax, rcx", &[0x48, 0x09, 0xc8], 0xffffffff80000000, 0x7fffffff, 0xffffffffffffffff, 0x84, 0x8c5),
+    ("or rax, rcx", &[0x48, 0x09, 0xc8], 0xdeadbeef7fffffff, 0x100000001, 0xdeadbeef7fffffff, 0x84, 0x8c5),
+    ("or rax, rcx", &[0x48, 0x09, 0xc8], 0x100000000, 0x3, 0x100000003, 0x4, 0x8c5),
+    ("or eax, ecx", &[0x09, 0xc8], 0x0, 0x0, 0x0, 0x44, 0x8c5),
+    ("or eax, ecx", &[0x09, 0xc8], 0x1, 0x1, 0x1, 0x0, 0x8c5),
+    ("or eax, ecx", &[0x09, 0xc8], 0x1, 0xffffffffffffffff, 0xffffffff, 0x84, 0x8c5),
+    ("or eax, ecx", &[0x09, 0xc8], 0x7fffffffffffffff, 0x1, 0xffffffff, 0x84, 0x8c5),
+    ("or eax, ecx", &[0x09, 0xc8], 0x8000000000000000, 0xffffffffffffffff, 0xffffffff, 0x84, 0x8c5),
+    ("or eax, ecx", &[0x09, 0xc8], 0xf, 0x1, 0xf, 0x4, 0x8c5),
+    ("or eax, ecx", &[0x09, 0xc8], 0x123456789abcdef0, 0xfedcba987654321, 0x9ffddff1, 0x80, 0x8c5),
+    ("or eax, ecx", &[0x09, 0xc8], 0xffffffff80000000, 0x7fffffff, 0xffffffff, 0x84, 0x8c5),
+    ("or eax, ecx", &[0x09, 0xc8], 0xdeadbeef7fffffff, 0x100000001, 0x7fffffff, 0x4, 0x8c5),
+    ("or eax, ecx", &[0x09, 0xc8], 0x100000000, 0x3, 0x3, 0x4, 0x8c5),
+    ("xor rax, rcx", &[0x48, 0x31, 0xc8], 0x0, 0x0, 0x0, 0x44, 0x8c5),
+    ("xor rax, rcx", &[0x48, 0x31, 0xc8], 0x1, 0x1, 0x0, 0x44, 0x8c5),
+    ("xor rax, rcx", &[0x48, 0x31, 0xc8], 0x1, 0xffffffffffffffff, 0xfffffffffffffffe, 0x80, 0x8c5),
+    ("xor rax, rcx", &[0x48, 0x31, 0xc8], 0x7fffffffffffffff, 0x1, 0x7ffffffffffffffe, 0x0, 0x8c5),
+    ("xor rax, rcx", &[0x48, 0x31, 0xc8], 0x8000000000000000, 0xffffffffffffffff, 0x7fffffffffffffff, 0x4, 0x8c5),
+    ("xor rax, rcx", &[0x48, 0x31, 0xc8], 0xf, 0x1, 0xe, 0x0, 0x8c5),
+    ("xor rax, rcx", &[0x48, 0x31, 0xc8], 0x123456789abcdef0, 0xfedcba987654321, 0x1dd99dd11dd99dd1, 0x4, 0x8c5),
+    ("xor rax, rcx", &[0x48, 0x31, 0xc8], 0xffffffff80000000, 0x7fffffff, 0xffffffffffffffff, 0x84, 0x8c5),
+    ("xor rax, rcx", &[0x48, 0x31, 0xc8], 0xdeadbeef7fffffff, 0x100000001, 0xdeadbeee7ffffffe, 0x80, 0x8c5),
+    ("xor rax, rcx", &[0x48, 0x31, 0xc8], 0x100000000, 0x3, 0x100000003, 0x4, 0x8c5),
+    ("xor eax, ecx", &[0x31, 0xc8], 0x0, 0x0, 0x0, 0x44, 0x8c5),
+    ("xor eax, ecx", &[0x31, 0xc8], 0x1, 0x1, 0x0, 0x44, 0x8c5),
+    ("xor eax, ecx", &[0x31, 0xc8], 0x1, 0xffffffffffffffff, 0xfffffffe, 0x80, 0x8c5),
+    ("xor eax, ecx", &[0x31, 0xc8], 0x7fffffffffffffff, 0x1, 0xfffffffe, 0x80, 0x8c5),
+    ("xor eax, ecx", &[0x31, 0xc8], 0x8000000000000000, 0xffffffffffffffff, 0xffffffff, 0x84, 0x8c5),
+    ("xor eax, ecx", &[0x31, 0xc8], 0xf, 0x1, 0xe, 0x0, 0x8c5),
+    ("xor eax, ecx", &[0x31, 0xc8], 0x123456789abcdef0, 0xfedcba987654321, 0x1dd99dd1, 0x4, 0x8c5),
+    ("xor eax, ecx", &[0x31, 0xc8], 0xffffffff80000000, 0x7fffffff, 0xffffffff, 0x84, 0x8c5),
+    ("xor eax, ecx", &[0x31, 0xc8], 0xdeadbeef7fffffff, 0x100000001, 0x7ffffffe, 0x0, 0x8c5),
+    ("xor eax, ecx", &[0x31, 0xc8], 0x100000000, 0x3, 0x3, 0x4, 0x8c5),
+    ("imul rax, rcx", &[0x48, 0x0f, 0xaf, 0xc1], 0x0, 0x0, 0x0, 0x0, 0x801),
+    ("imul rax, rcx", &[0x48, 0x0f, 0xaf, 0xc1], 0x1, 0x1, 0x1, 0x0, 0x801),
+    ("imul rax, rcx", &[0x48, 0x0f, 0xaf, 0xc1], 0x1, 0xffffffffffffffff, 0xffffffffffffffff, 0x0, 0x801),
+    ("imul rax, rcx", &[0x48, 0x0f, 0xaf, 0xc1], 0x7fffffffffffffff, 0x1, 0x7fffffffffffffff, 0x0, 0x801),
+    ("imul rax, rcx", &[0x48, 0x0f, 0xaf, 0xc1], 0x8000000000000000, 0xffffffffffffffff, 0x8000000000000000, 0x801, 0x801),
+    ("imul rax, rcx", &[0x48, 0x0f, 0xaf, 0xc1], 0xf, 0x1, 0xf, 0x0, 0x801),
+    ("imul rax, rcx", &[0x48, 0x0f, 0xaf, 0xc1], 0x123456789abcdef0, 0xfedcba987654321, 0x2236d88fe5618cf0, 0x801, 0x801),
+    ("imul rax, rcx", &[0x48, 0x0f, 0xaf, 0xc1], 0xffffffff80000000, 0x7fffffff, 0xc000000080000000, 0x0, 0x801),
+    ("imul rax, rcx", &[0x48, 0x0f, 0xaf, 0xc1], 0xdeadbeef7fffffff, 0x100000001, 0x5eadbeee7fffffff, 0x801, 0x801),
+    ("imul rax, rcx", &[0x48, 0x0f, 0xaf, 0xc1], 0x100000000, 0x3, 0x300000000, 0x0, 0x801),
+    ("imul eax, ecx", &[0x0f, 0xaf, 0xc1], 0x0, 0x0, 0x0, 0x0, 0x801),
+    ("imul eax, ecx", &[0x0f, 0xaf, 0xc1], 0x1, 0x1, 0x1, 0x0, 0x801),
+    ("imul eax, ecx", &[0x0f, 0xaf, 0xc1], 0x1, 0xffffffffffffffff, 0xffffffff, 0x0, 0x801),
+    ("imul eax, ecx", &[0x0f, 0xaf, 0xc1], 0x7fffffffffffffff, 0x1, 0xffffffff, 0x0, 0x801),
+    ("imul eax, ecx", &[0x0f, 0xaf, 0xc1], 0x8000000000000000, 0xffffffffffffffff, 0x0, 0x0, 0x801),
+    ("imul eax, ecx", &[0x0f, 0xaf, 0xc1], 0xf, 0x1, 0xf, 0x0, 0x801),
+    ("imul eax, ecx", &[0x0f, 0xaf, 0xc1], 0x123456789abcdef0, 0xfedcba987654321, 0xe5618cf0, 0x801, 0x801),
+    ("imul eax, ecx", &[0x0f, 0xaf, 0xc1], 0xffffffff80000000, 0x7fffffff, 0x80000000, 0x801, 0x801),
+    ("imul eax, ecx", &[0x0f, 0xaf, 0xc1], 0xdeadbeef7fffffff, 0x100000001, 0x7fffffff, 0x0, 0x801),
+    ("imul eax, ecx", &[0x0f, 0xaf, 0xc1], 0x100000000, 0x3, 0x0, 0x0, 0x801),
+    ("add rax, -1", &[0x48, 0x83, 0xc0, 0xff], 0x0, 0x0, 0xffffffffffffffff, 0x84, 0x8d5),
+    ("add rax, -1", &[0x48, 0x83, 0xc0, 0xff], 0x1, 0x1, 0x0, 0x55, 0x8d5),
+    ("add rax, -1", &[0x48, 0x83, 0xc0, 0xff], 0x1, 0xffffffffffffffff, 0x0, 0x55, 0x8d5),
+    ("add rax, -1", &[0x48, 0x83, 0xc0, 0xff], 0x7fffffffffffffff, 0x1, 0x7ffffffffffffffe, 0x11, 0x8d5),
+    ("add rax, -1", &[0x48, 0x83, 0xc0, 0xff], 0x8000000000000000, 0xffffffffffffffff, 0x7fffffffffffffff, 0x805, 0x8d5),
+    ("add rax, -1", &[0x48, 0x83, 0xc0, 0xff], 0xf, 0x1, 0xe, 0x11, 0x8d5),
+    ("add rax, -1", &[0x48, 0x83, 0xc0, 0xff], 0x123456789abcdef0, 0xfedcba987654321, 0x123456789abcdeef, 0x1, 0x8d5),
+    ("add rax, -1", &[0x48, 0x83, 0xc0, 0xff], 0xffffffff80000000, 0x7fffffff, 0xffffffff7fffffff, 0x85, 0x8d5),
+    ("add rax, -1", &[0x48, 0x83, 0xc0, 0xff], 0xdeadbeef7fffffff, 0x100000001, 0xdeadbeef7ffffffe, 0x91, 0x8d5),
+    ("add rax, -1", &[0x48, 0x83, 0xc0, 0xff], 0x100000000, 0x3, 0xffffffff, 0x5, 0x8d5),
+    ("cmp eax, -1", &[0x83, 0xf8, 0xff], 0x0, 0x0, 0x0, 0x11, 0x8d5),
+    ("cmp eax, -1", &[0x83, 0xf8, 0xff], 0x1, 0x1, 0x1, 0x11, 0x8d5),
+    ("cmp eax, -1", &[0x83, 0xf8, 0xff], 0x1, 0xffffffffffffffff, 0x1, 0x11, 0x8d5),
+    ("cmp eax, -1", &[0x83, 0xf8, 0xff], 0x7fffffffffffffff, 0x1, 0x7fffffffffffffff, 0x44, 0x8d5),
+    ("cmp eax, -1", &[0x83, 0xf8, 0xff], 0x8000000000000000, 0xffffffffffffffff, 0x8000000000000000, 0x11, 0x8d5),
+    ("cmp eax, -1", &[0x83, 0xf8, 0xff], 0xf, 0x1, 0xf, 0x1, 0x8d5),
+    ("cmp eax, -1", &[0x83, 0xf8, 0xff], 0x123456789abcdef0, 0xfedcba987654321, 0x123456789abcdef0, 0x91, 0x8d5),
+    ("cmp eax, -1", &[0x83, 0xf8, 0xff], 0xffffffff80000000, 0x7fffffff, 0xffffffff80000000, 0x91, 0x8d5),
+    ("cmp eax, -1", &[0x83, 0xf8, 0xff], 0xdeadbeef7fffffff, 0x100000001, 0xdeadbeef7fffffff, 0x885, 0x8d5),
+    ("cmp eax, -1", &[0x83, 0xf8, 0xff], 0x100000000, 0x3, 0x100000000, 0x11, 0x8d5),
+    ("and rax, 0xffffffff80000000", &[0x48, 0x81, 0xe0, 0x00, 0x00, 0x00, 0x80], 0x0, 0x0, 0x0, 0x44, 0x8c5),
+    ("and rax, 0xffffffff80000000", &[0x48, 0x81, 0xe0, 0x00, 0x00, 0x00, 0x80], 0x1, 0x1, 0x0, 0x44, 0x8c5),
+    ("and rax, 0xffffffff80000000", &[0x48, 0x81, 0xe0, 0x00, 0x00, 0x00, 0x80], 0x1, 0xffffffffffffffff, 0x0, 0x44, 0x8c5),
+    ("and rax, 0xffffffff80000000", &[0x48, 0x81, 0xe0, 0x00, 0x00, 0x00, 0x80], 0x7fffffffffffffff, 0x1, 0x7fffffff80000000, 0x4, 0x8c5),
+    ("and rax, 0xffffffff80000000", &[0x48, 0x81, 0xe0, 0x00, 0x00, 0x00, 0x80], 0x8000000000000000, 0xffffffffffffffff, 0x8000000000000000, 0x84, 0x8c5),
+    ("and rax, 0xffffffff80000000", &[0x48, 0x81, 0xe0, 0x00, 0x00, 0x00, 0x80], 0xf, 0x1, 0x0, 0x44, 0x8c5),
+    ("and rax, 0xffffffff80000000", &[0x48, 0x81, 0xe0, 0x00, 0x00, 0x00, 0x80], 0x123456789abcdef0, 0xfedcba987654321, 0x1234567880000000, 0x4, 0x8c5),
+    ("and rax, 0xffffffff80000000", &[0x48, 0x81, 0xe0, 0x00, 0x00, 0x00, 0x80], 0xffffffff80000000, 0x7fffffff, 0xffffffff80000000, 0x84, 0x8c5),
+    ("and rax, 0xffffffff80000000", &[0x48, 0x81, 0xe0, 0x00, 0x00, 0x00, 0x80], 0xdeadbeef7fffffff, 0x100000001, 0xdeadbeef00000000, 0x84, 0x8c5),
+    ("and rax, 0xffffffff80000000", &[0x48, 0x81, 0xe0, 0x00, 0x00, 0x00, 0x80], 0x100000000, 0x3, 0x100000000, 0x4, 0x8c5),
+    ("sete al", &[0x0f, 0x94, 0xc0], 0x0, 0x0, 0x1, 0x8d5, 0x8d5),
+    ("sete al", &[0x0f, 0x94, 0xc0], 0x1, 0x1, 0x1, 0x8d5, 0x8d5),
+    ("sete al", &[0x0f, 0x94, 0xc0], 0x1, 0xffffffffffffffff, 0x1, 0x8d5, 0x8d5),
+    ("sete al", &[0x0f, 0x94, 0xc0], 0x7fffffffffffffff, 0x1, 0x7fffffffffffff01, 0x8d5, 0x8d5),
+    ("sete al", &[0x0f, 0x94, 0xc0], 0x8000000000000000, 0xffffffffffffffff, 0x8000000000000001, 0x8d5, 0x8d5),
+    ("sete al", &[0x0f, 0x94, 0xc0], 0xf, 0x1, 0x1, 0x8d5, 0x8d5),
+    ("sete al", &[0x0f, 0x94, 0xc0], 0x123456789abcdef0, 0xfedcba987654321, 0x123456789abcde01, 0x8d5, 0x8d5),
+    ("sete al", &[0x0f, 0x94, 0xc0], 0xffffffff80000000, 0x7fffffff, 0xffffffff80000001, 0x8d5, 0x8d5),
+    ("sete al", &[0x0f, 0x94, 0xc0], 0xdeadbeef7fffffff, 0x100000001, 0xdeadbeef7fffff01, 0x8d5, 0x8d5),
+    ("sete al", &[0x0f, 0x94, 0xc0], 0x100000000, 0x3, 0x100000001, 0x8d5, 0x8d5),
+    ("mov al, cl", &[0x88, 0xc8], 0x0, 0x0, 0x0, 0x8d5, 0x8d5),
+    ("mov al, cl", &[0x88, 0xc8], 0x1, 0x1, 0x1, 0x8d5, 0x8d5),
+    ("mov al, cl", &[0x88, 0xc8], 0x1, 0xffffffffffffffff, 0xff, 0x8d5, 0x8d5),
+    ("mov al, cl", &[0x88, 0xc8], 0x7fffffffffffffff, 0x1, 0x7fffffffffffff01, 0x8d5, 0x8d5),
+    ("mov al, cl", &[0x88, 0xc8], 0x8000000000000000, 0xffffffffffffffff, 0x80000000000000ff, 0x8d5, 0x8d5),
+    ("mov al, cl", &[0x88, 0xc8], 0xf, 0x1, 0x1, 0x8d5, 0x8d5),
+    ("mov al, cl", &[0x88, 0xc8], 0x123456789abcdef0, 0xfedcba987654321, 0x123456789abcde21, 0x8d5, 0x8d5),
+    ("mov al, cl", &[0x88, 0xc8], 0xffffffff80000000, 0x7fffffff, 0xffffffff800000ff, 0x8d5, 0x8d5),
+    ("mov al, cl", &[0x88, 0xc8], 0xdeadbeef7fffffff, 0x100000001, 0xdeadbeef7fffff01, 0x8d5, 0x8d5),
+    ("mov al, cl", &[0x88, 0xc8], 0x100000000, 0x3, 0x100000003, 0x8d5, 0x8d5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0x8000000000000001, 0x0, 0x8000000000000001, 0x8d5, 0x8d5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0x8000000000000001, 0x1, 0x2, 0x801, 0x8c5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0x8000000000000001, 0x3, 0x8, 0x0, 0xc5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0x8000000000000001, 0x1f, 0x80000000, 0x4, 0xc5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0x8000000000000001, 0x20, 0x100000000, 0x4, 0xc5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0x8000000000000001, 0x3f, 0x8000000000000000, 0x84, 0xc5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0x8000000000000001, 0x41, 0x2, 0x801, 0x8c5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0x123456789abcdef0, 0x0, 0x123456789abcdef0, 0x8d5, 0x8d5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0x123456789abcdef0, 0x1, 0x2468acf13579bde0, 0x0, 0x8c5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0x123456789abcdef0, 0x3, 0x91a2b3c4d5e6f780, 0x80, 0xc5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0x123456789abcdef0, 0x1f, 0x4d5e6f7800000000, 0x4, 0xc5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0x123456789abcdef0, 0x20, 0x9abcdef000000000, 0x84, 0xc5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0x123456789abcdef0, 0x3f, 0x0, 0x44, 0xc5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0x123456789abcdef0, 0x41, 0x2468acf13579bde0, 0x0, 0x8c5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0xffffffff80000001, 0x0, 0xffffffff80000001, 0x8d5, 0x8d5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0xffffffff80000001, 0x1, 0xffffffff00000002, 0x81, 0x8c5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0xffffffff80000001, 0x3, 0xfffffffc00000008, 0x81, 0xc5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0xffffffff80000001, 0x1f, 0xc000000080000000, 0x85, 0xc5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0xffffffff80000001, 0x20, 0x8000000100000000, 0x85, 0xc5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0xffffffff80000001, 0x3f, 0x8000000000000000, 0x84, 0xc5),
+    ("shl rax, cl", &[0x48, 0xd3, 0xe0], 0xffffffff80000001, 0x41, 0xffffffff00000002, 0x81, 0x8c5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0x8000000000000001, 0x0, 0x1, 0x8d5, 0x8d5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0x8000000000000001, 0x1, 0x2, 0x0, 0x8c5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0x8000000000000001, 0x3, 0x8, 0x0, 0xc5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0x8000000000000001, 0x1f, 0x80000000, 0x84, 0xc5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0x8000000000000001, 0x20, 0x1, 0x8d5, 0x8d5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0x8000000000000001, 0x3f, 0x80000000, 0x84, 0xc5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0x8000000000000001, 0x41, 0x2, 0x0, 0x8c5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0x123456789abcdef0, 0x0, 0x9abcdef0, 0x8d5, 0x8d5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0x123456789abcdef0, 0x1, 0x3579bde0, 0x801, 0x8c5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0x123456789abcdef0, 0x3, 0xd5e6f780, 0x80, 0xc5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0x123456789abcdef0, 0x1f, 0x0, 0x44, 0xc5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0x123456789abcdef0, 0x20, 0x9abcdef0, 0x8d5, 0x8d5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0x123456789abcdef0, 0x3f, 0x0, 0x44, 0xc5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0x123456789abcdef0, 0x41, 0x3579bde0, 0x801, 0x8c5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0xffffffff80000001, 0x0, 0x80000001, 0x8d5, 0x8d5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0xffffffff80000001, 0x1, 0x2, 0x801, 0x8c5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0xffffffff80000001, 0x3, 0x8, 0x0, 0xc5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0xffffffff80000001, 0x1f, 0x80000000, 0x84, 0xc5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0xffffffff80000001, 0x20, 0x80000001, 0x8d5, 0x8d5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0xffffffff80000001, 0x3f, 0x80000000, 0x84, 0xc5),
+    ("shl eax, cl", &[0xd3, 0xe0], 0xffffffff80000001, 0x41, 0x2, 0x801, 0x8c5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0x8000000000000001, 0x0, 0x8000000000000001, 0x8d5, 0x8d5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0x8000000000000001, 0x1, 0x4000000000000000, 0x805, 0x8c5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0x8000000000000001, 0x3, 0x1000000000000000, 0x4, 0xc5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0x8000000000000001, 0x1f, 0x100000000, 0x4, 0xc5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0x8000000000000001, 0x20, 0x80000000, 0x4, 0xc5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0x8000000000000001, 0x3f, 0x1, 0x0, 0xc5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0x8000000000000001, 0x41, 0x4000000000000000, 0x805, 0x8c5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0x123456789abcdef0, 0x0, 0x123456789abcdef0, 0x8d5, 0x8d5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0x123456789abcdef0, 0x1, 0x91a2b3c4d5e6f78, 0x4, 0x8c5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0x123456789abcdef0, 0x3, 0x2468acf13579bde, 0x4, 0xc5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0x123456789abcdef0, 0x1f, 0x2468acf1, 0x0, 0xc5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0x123456789abcdef0, 0x20, 0x12345678, 0x5, 0xc5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0x123456789abcdef0, 0x3f, 0x0, 0x44, 0xc5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0x123456789abcdef0, 0x41, 0x91a2b3c4d5e6f78, 0x4, 0x8c5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0xffffffff80000001, 0x0, 0xffffffff80000001, 0x8d5, 0x8d5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0xffffffff80000001, 0x1, 0x7fffffffc0000000, 0x805, 0x8c5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0xffffffff80000001, 0x3, 0x1ffffffff0000000, 0x4, 0xc5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0xffffffff80000001, 0x1f, 0x1ffffffff, 0x4, 0xc5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0xffffffff80000001, 0x20, 0xffffffff, 0x5, 0xc5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0xffffffff80000001, 0x3f, 0x1, 0x1, 0xc5),
+    ("shr rax, cl", &[0x48, 0xd3, 0xe8], 0xffffffff80000001, 0x41, 0x7fffffffc0000000, 0x805, 0x8c5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0x8000000000000001, 0x0, 0x1, 0x8d5, 0x8d5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0x8000000000000001, 0x1, 0x0, 0x45, 0x8c5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0x8000000000000001, 0x3, 0x0, 0x44, 0xc5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0x8000000000000001, 0x1f, 0x0, 0x44, 0xc5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0x8000000000000001, 0x20, 0x1, 0x8d5, 0x8d5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0x8000000000000001, 0x3f, 0x0, 0x44, 0xc5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0x8000000000000001, 0x41, 0x0, 0x45, 0x8c5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0x123456789abcdef0, 0x0, 0x9abcdef0, 0x8d5, 0x8d5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0x123456789abcdef0, 0x1, 0x4d5e6f78, 0x804, 0x8c5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0x123456789abcdef0, 0x3, 0x13579bde, 0x4, 0xc5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0x123456789abcdef0, 0x1f, 0x1, 0x0, 0xc5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0x123456789abcdef0, 0x20, 0x9abcdef0, 0x8d5, 0x8d5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0x123456789abcdef0, 0x3f, 0x1, 0x0, 0xc5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0x123456789abcdef0, 0x41, 0x4d5e6f78, 0x804, 0x8c5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0xffffffff80000001, 0x0, 0x80000001, 0x8d5, 0x8d5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0xffffffff80000001, 0x1, 0x40000000, 0x805, 0x8c5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0xffffffff80000001, 0x3, 0x10000000, 0x4, 0xc5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0xffffffff80000001, 0x1f, 0x1, 0x0, 0xc5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0xffffffff80000001, 0x20, 0x80000001, 0x8d5, 0x8d5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0xffffffff80000001, 0x3f, 0x1, 0x0, 0xc5),
+    ("shr eax, cl", &[0xd3, 0xe8], 0xffffffff80000001, 0x41, 0x40000000, 0x805, 0x8c5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0x8000000000000001, 0x0, 0x8000000000000001, 0x8d5, 0x8d5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0x8000000000000001, 0x1, 0xc000000000000000, 0x85, 0x8c5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0x8000000000000001, 0x3, 0xf000000000000000, 0x84, 0xc5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0x8000000000000001, 0x1f, 0xffffffff00000000, 0x84, 0xc5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0x8000000000000001, 0x20, 0xffffffff80000000, 0x84, 0xc5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0x8000000000000001, 0x3f, 0xffffffffffffffff, 0x84, 0xc5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0x8000000000000001, 0x41, 0xc000000000000000, 0x85, 0x8c5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0x123456789abcdef0, 0x0, 0x123456789abcdef0, 0x8d5, 0x8d5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0x123456789abcdef0, 0x1, 0x91a2b3c4d5e6f78, 0x4, 0x8c5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0x123456789abcdef0, 0x3, 0x2468acf13579bde, 0x4, 0xc5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0x123456789abcdef0, 0x1f, 0x2468acf1, 0x0, 0xc5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0x123456789abcdef0, 0x20, 0x12345678, 0x5, 0xc5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0x123456789abcdef0, 0x3f, 0x0, 0x44, 0xc5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0x123456789abcdef0, 0x41, 0x91a2b3c4d5e6f78, 0x4, 0x8c5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0xffffffff80000001, 0x0, 0xffffffff80000001, 0x8d5, 0x8d5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0xffffffff80000001, 0x1, 0xffffffffc0000000, 0x85, 0x8c5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0xffffffff80000001, 0x3, 0xfffffffff0000000, 0x84, 0xc5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0xffffffff80000001, 0x1f, 0xffffffffffffffff, 0x84, 0xc5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0xffffffff80000001, 0x20, 0xffffffffffffffff, 0x85, 0xc5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0xffffffff80000001, 0x3f, 0xffffffffffffffff, 0x85, 0xc5),
+    ("sar rax, cl", &[0x48, 0xd3, 0xf8], 0xffffffff80000001, 0x41, 0xffffffffc0000000, 0x85, 0x8c5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0x8000000000000001, 0x0, 0x1, 0x8d5, 0x8d5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0x8000000000000001, 0x1, 0x0, 0x45, 0x8c5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0x8000000000000001, 0x3, 0x0, 0x44, 0xc5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0x8000000000000001, 0x1f, 0x0, 0x44, 0xc5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0x8000000000000001, 0x20, 0x1, 0x8d5, 0x8d5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0x8000000000000001, 0x3f, 0x0, 0x44, 0xc5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0x8000000000000001, 0x41, 0x0, 0x45, 0x8c5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0x123456789abcdef0, 0x0, 0x9abcdef0, 0x8d5, 0x8d5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0x123456789abcdef0, 0x1, 0xcd5e6f78, 0x84, 0x8c5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0x123456789abcdef0, 0x3, 0xf3579bde, 0x84, 0xc5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0x123456789abcdef0, 0x1f, 0xffffffff, 0x84, 0xc5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0x123456789abcdef0, 0x20, 0x9abcdef0, 0x8d5, 0x8d5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0x123456789abcdef0, 0x3f, 0xffffffff, 0x84, 0xc5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0x123456789abcdef0, 0x41, 0xcd5e6f78, 0x84, 0x8c5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0xffffffff80000001, 0x0, 0x80000001, 0x8d5, 0x8d5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0xffffffff80000001, 0x1, 0xc0000000, 0x85, 0x8c5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0xffffffff80000001, 0x3, 0xf0000000, 0x84, 0xc5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0xffffffff80000001, 0x1f, 0xffffffff, 0x84, 0xc5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0xffffffff80000001, 0x20, 0x80000001, 0x8d5, 0x8d5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0xffffffff80000001, 0x3f, 0xffffffff, 0x84, 0xc5),
+    ("sar eax, cl", &[0xd3, 0xf8], 0xffffffff80000001, 0x41, 0xc0000000, 0x85, 0x8c5),
+];
@@ -0,0 +1,11 @@
+.intel_syntax noprefix
+.global _start
+_start:
+    mov rax, 1
+    mov rdi, 1
+    mov rsi, [rsp + 16]
+    mov rdx, 2
+    syscall
+    mov rdi, [rsp]
+    mov rax, 60
+    syscall
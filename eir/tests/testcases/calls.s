@@ -0,0 +1,14 @@
+.intel_syntax noprefix
+.global _start
+_start:
+    mov rdi, 5
+    call square
+    mov rdi, rax
+    mov rax, 60
+    syscall
+
+.global square
+square:
+    mov rax, rdi
+    imul rax, rdi
+    ret
@@ -0,0 +1,44 @@
+.intel_syntax noprefix
+.global _start
+_start:
+    # a buffer from brk
+    mov rax, 12
+    mov rdi, 0
+    syscall
+    mov r12, rax
+    lea rdi, [rax + 4096]
+    mov rax, 12
+    syscall
+
+    # open("data.txt", O_RDONLY)
+    mov rax, 2
+    lea rdi, [rip + path]
+    mov rsi, 0
+    syscall
+    mov r13, rax
+
+    # read(fd, buf, 64)
+    mov rdi, rax
+    mov rax, 0
+    mov rsi, r12
+    mov rdx, 64
+    syscall
+
+    # write(1, buf, n)
+    mov rdx, rax
+    mov rax, 1
+    mov rdi, 1
+    mov rsi, r12
+    syscall
+
+    # exit_group(close(fd))
+    mov rax, 3
+    mov rdi, r13
+    syscall
+    mov rdi, rax
+    mov rax, 231
+    syscall
+
+    .section .rodata
+path:
+    .asciz "data.txt"
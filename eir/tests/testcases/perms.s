@@ -0,0 +1,15 @@
+.intel_syntax noprefix
+.global _start
+.global exec_stack
+# writes to its own code, which is read-only
+_start:
+    lea rax, [rip + _start]
+    mov qword ptr [rax], rdi
+    mov rax, 60
+    syscall
+
+# returns to an address on the stack, which is not executable
+exec_stack:
+    mov rax, rsp
+    push rax
+    ret
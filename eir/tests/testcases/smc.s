@@ -0,0 +1,41 @@
+.intel_syntax noprefix
+.global _start
+# calls code in an anonymous mapping, rewrites it and calls it again, then exits
+# with the sum of what the two versions returned in rdi. the calls push the
+# return address themselves, as `call rbx` is decoded with a 32-bit operand
+_start:
+    mov rax, 9
+    mov rdi, 0
+    mov rsi, 4096
+    mov rdx, 7
+    mov r10, 0x22
+    mov r8, -1
+    mov r9, 0
+    syscall
+    mov rbx, rax
+    mov rax, [rip + return1]
+    mov [rbx], rax
+    lea rcx, [rip + 1f]
+    push rcx
+    push rbx
+    ret
+1:
+    mov r12, rdi
+    mov rax, [rip + return40]
+    mov [rbx], rax
+    lea rcx, [rip + 2f]
+    push rcx
+    push rbx
+    ret
+2:
+    add rdi, r12
+    mov rax, 60
+    syscall
+
+.section .rodata
+# mov rdi, 1; ret
+return1:
+    .byte 0x48, 0xc7, 0xc7, 0x01, 0x00, 0x00, 0x00, 0xc3
+# mov rdi, 40; ret
+return40:
+    .byte 0x48, 0xc7, 0xc7, 0x28, 0x00, 0x00, 0x00, 0xc3
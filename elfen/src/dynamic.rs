@@ -0,0 +1,43 @@
+pub mod tag;
+
+use crate::*;
+
+// an entry of .dynamic
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Dyn {
+    pub tag: ElfSxword,
+    pub val: ElfXword,
+}
+
+#[derive(Eq, PartialEq, Debug)]
+pub enum Tag {
+    Null,
+    Rela,
+    RelaSz,
+    RelaEnt,
+    RelaCount,
+    Flags1,
+    Unknown(i64),
+}
+
+// DF_1_PIE in DT_FLAGS_1
+pub const FLAGS1_PIE: u64 = 0x0800_0000;
+
+impl Dyn {
+    pub fn new(tag: Tag, val: u64) -> Self {
+        Self {
+            tag: tag.into(),
+            val,
+        }
+    }
+
+    pub fn get_tag(&self) -> Tag {
+        Tag::from(self.tag)
+    }
+
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.extend(&self.tag.to_le_bytes());
+        buf.extend(&self.val.to_le_bytes());
+    }
+}
@@ -0,0 +1,29 @@
+use super::Tag;
+
+impl From<i64> for Tag {
+    fn from(bytes: i64) -> Self {
+        match bytes {
+            0 => Tag::Null,
+            7 => Tag::Rela,
+            8 => Tag::RelaSz,
+            9 => Tag::RelaEnt,
+            0x6fff_fff9 => Tag::RelaCount,
+            0x6fff_fffb => Tag::Flags1,
+            x => Tag::Unknown(x),
+        }
+    }
+}
+
+impl From<Tag> for i64 {
+    fn from(tag: Tag) -> Self {
+        match tag {
+            Tag::Null => 0,
+            Tag::Rela => 7,
+            Tag::RelaSz => 8,
+            Tag::RelaEnt => 9,
+            Tag::RelaCount => 0x6fff_fff9,
+            Tag::Flags1 => 0x6fff_fffb,
+            Tag::Unknown(x) => x,
+        }
+    }
+}
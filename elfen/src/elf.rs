@@ -67,7 +67,7 @@ impl Elf {
         offset += self
             .sections
             .iter()
-            .map(|section| section.header.offset + section.header.size)
+            .map(|section| section.header.offset + section.header.file_size())
             .max()
             .unwrap();
         self.header.section_header_offset = offset;
@@ -85,10 +85,11 @@ impl Elf {
         let mut offset = size_of::<Header>() as u64;
         // skip null section
         for section in self.sections.iter_mut().skip(1) {
-            let section_size = section.data.len() as u64;
             section.header.offset = offset;
-            section.header.size = section_size;
-            offset += section_size;
+            if !section.header.is_nobits() {
+                section.header.size = section.data.len() as u64;
+            }
+            offset += section.header.file_size();
         }
     }
 
@@ -1,3 +1,4 @@
+pub mod dynamic;
 pub mod elf;
 pub mod header;
 pub mod reader;
@@ -1,3 +1,4 @@
+use dynamic::Dyn;
 use header::Header;
 use rel::Rela;
 use section::{Section, SectionData};
@@ -12,6 +13,15 @@ use std::fs;
 impl Elf {
     pub fn read_from_file(path: &str) -> Self {
         let bytes = fs::read(path).unwrap();
+        Self::read(bytes)
+    }
+
+    pub fn read_from_bytes(bytes: &[u8]) -> Self {
+        // copy into a new buffer so that the headers are properly aligned
+        Self::read(bytes.to_vec())
+    }
+
+    fn read(bytes: Vec<u8>) -> Self {
         let header = Self::read_header(&bytes);
         let sections = Self::read_sections(&header, &bytes);
         let segments = Self::read_segments(&header, &bytes);
@@ -47,7 +57,7 @@ impl Elf {
 
             // read section data
             let start_addr = section_header.offset as usize;
-            let end_addr = start_addr + section_header.size as usize;
+            let end_addr = start_addr + section_header.file_size() as usize;
             let data = bytes[start_addr..end_addr].to_vec();
 
             // add section
@@ -92,6 +102,10 @@ impl Elf {
                 let symbols = Self::read_entries::<Symbol>(data, header.entry_size as usize);
                 SectionData::Symbols(symbols)
             }
+            section::Type::Dynamic => {
+                let dyns = Self::read_entries::<Dyn>(data, header.entry_size as usize);
+                SectionData::Dynamic(dyns)
+            }
             _ => SectionData::Raw(data.to_vec()),
         }
     }
@@ -10,11 +10,20 @@ pub struct Rela {
     pub addend: ElfSxword,
 }
 
-#[derive(Eq, PartialEq)]
+#[derive(Eq, PartialEq, Debug)]
 pub enum Type {
     None,
+    // R_X86_64_64
+    Abs64,
     Pc32,
     Plt32,
+    GotPcRel,
+    // R_X86_64_32
+    Abs32,
+    // R_X86_64_32S
+    Abs32S,
+    // R_X86_64_RELATIVE
+    Relative,
     Unknown(u64),
 }
 
@@ -1,11 +1,18 @@
+use std::fmt;
+
 use super::Type;
 
 impl From<u64> for Type {
     fn from(bytes: u64) -> Self {
         match bytes {
             0 => Type::None,
+            1 => Type::Abs64,
             2 => Type::Pc32,
             4 => Type::Plt32,
+            8 => Type::Relative,
+            9 => Type::GotPcRel,
+            10 => Type::Abs32,
+            11 => Type::Abs32S,
             x => Type::Unknown(x),
         }
     }
@@ -15,9 +22,30 @@ impl Into<u64> for Type {
     fn into(self) -> u64 {
         match self {
             Type::None => 0,
+            Type::Abs64 => 1,
             Type::Pc32 => 2,
             Type::Plt32 => 4,
+            Type::Relative => 8,
+            Type::GotPcRel => 9,
+            Type::Abs32 => 10,
+            Type::Abs32S => 11,
             Type::Unknown(x) => x,
         }
     }
 }
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::None => write!(f, "R_X86_64_NONE"),
+            Type::Abs64 => write!(f, "R_X86_64_64"),
+            Type::Pc32 => write!(f, "R_X86_64_PC32"),
+            Type::Plt32 => write!(f, "R_X86_64_PLT32"),
+            Type::GotPcRel => write!(f, "R_X86_64_GOTPCREL"),
+            Type::Abs32 => write!(f, "R_X86_64_32"),
+            Type::Abs32S => write!(f, "R_X86_64_32S"),
+            Type::Relative => write!(f, "R_X86_64_RELATIVE"),
+            Type::Unknown(x) => write!(f, "unknown relocation type {}", x),
+        }
+    }
+}
@@ -4,6 +4,7 @@ pub mod typ;
 
 use std::mem::size_of;
 
+use dynamic::Dyn;
 use rel::Rela;
 use strtab::Strtab;
 use symbol::Symbol;
@@ -78,6 +79,7 @@ pub enum SectionData {
     Strtab(Strtab),
     Symbols(Vec<Symbol>),
     Tse(Vec<Tse>),
+    Dynamic(Vec<Dyn>),
 }
 
 impl SectionData {
@@ -89,6 +91,7 @@ impl SectionData {
             SectionData::Strtab(strtab) => strtab.data.len(),
             SectionData::Symbols(symbols) => size_of::<Symbol>() * symbols.len(),
             SectionData::Tse(tses) => size_of::<Tse>() * tses.len(),
+            SectionData::Dynamic(dyns) => size_of::<Dyn>() * dyns.len(),
         }
     }
 
@@ -112,6 +115,11 @@ impl SectionData {
                     tse.write_to(buf);
                 }
             }
+            SectionData::Dynamic(dyns) => {
+                for dyn_entry in dyns {
+                    dyn_entry.write_to(buf);
+                }
+            }
         }
     }
 
@@ -184,4 +192,11 @@ impl SectionData {
         }
         None
     }
+
+    pub fn as_dynamic(&self) -> Option<&Vec<Dyn>> {
+        if let SectionData::Dynamic(dyns) = self {
+            return Some(dyns);
+        }
+        None
+    }
 }
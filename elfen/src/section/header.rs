@@ -14,6 +14,19 @@ impl SectionHeader {
         self.flags |= bytes;
     }
 
+    pub fn is_nobits(&self) -> bool {
+        self.get_type() == Type::Nobits
+    }
+
+    // NOBITS sections occupy memory but no space in the file
+    pub fn file_size(&self) -> u64 {
+        if self.is_nobits() {
+            0
+        } else {
+            self.size
+        }
+    }
+
     pub fn write_to(&self, buf: &mut Vec<u8>) {
         buf.extend(&self.name.to_le_bytes());
         buf.extend(&self.section_type.to_le_bytes());
@@ -33,6 +33,7 @@ pub enum Type {
     Shlib,
     Phdr,
     Tls,
+    GnuStack,
     Unknown(u32),
 }
 
@@ -11,6 +11,7 @@ impl From<u32> for Type {
             5 => Type::Shlib,
             6 => Type::Phdr,
             7 => Type::Tls,
+            0x6474_e551 => Type::GnuStack,
             x => Type::Unknown(x),
         }
     }
@@ -27,6 +28,7 @@ impl Into<u32> for Type {
             Type::Shlib => 5,
             Type::Phdr => 6,
             Type::Tls => 7,
+            Type::GnuStack => 0x6474_e551,
             Type::Unknown(x) => x,
         }
     }
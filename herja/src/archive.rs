@@ -0,0 +1,92 @@
+// reader of `ar` archives in the System V (GNU) format
+
+const MAGIC: &[u8] = b"!<arch>\n";
+const HEADER_SIZE: usize = 60;
+
+#[derive(Debug)]
+pub struct Archive {
+    pub members: Vec<Member>,
+}
+
+#[derive(Debug)]
+pub struct Member {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+pub fn is_archive(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+impl Archive {
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        if !is_archive(bytes) {
+            return Err("not an archive".into());
+        }
+
+        let mut members = Vec::new();
+        // contents of the `//` member, which holds names longer than 15 bytes
+        let mut long_names: &[u8] = &[];
+
+        let mut offset = MAGIC.len();
+        while offset < bytes.len() {
+            let header = bytes
+                .get(offset..offset + HEADER_SIZE)
+                .ok_or("truncated member header")?;
+            if &header[58..60] != b"`\n" {
+                return Err(format!("broken member header at {:#x}", offset));
+            }
+
+            let size: usize = header_field(&header[48..58])
+                .parse()
+                .map_err(|_| format!("invalid member size at {:#x}", offset))?;
+            let start = offset + HEADER_SIZE;
+            let data = bytes
+                .get(start..start + size)
+                .ok_or("truncated member data")?;
+
+            let name = header_field(&header[0..16]);
+            match name {
+                // symbol table, which is not needed as every member is read
+                "/" | "/SYM64/" => {}
+                "//" => long_names = data,
+                _ => {
+                    let name = if let Some(index) = name.strip_prefix('/') {
+                        let index: usize = index
+                            .parse()
+                            .map_err(|_| format!("invalid member name '{}'", name))?;
+                        long_name(long_names, index)?
+                    } else {
+                        name.trim_end_matches('/').to_string()
+                    };
+
+                    members.push(Member {
+                        name,
+                        data: data.to_vec(),
+                    });
+                }
+            }
+
+            // members are aligned to 2 bytes
+            offset = start + size + size % 2;
+        }
+
+        Ok(Self { members })
+    }
+}
+
+fn header_field(bytes: &[u8]) -> &str {
+    std::str::from_utf8(bytes).unwrap_or("").trim_end()
+}
+
+// long names are separated by "/\n"
+fn long_name(long_names: &[u8], index: usize) -> Result<String, String> {
+    let rest = long_names
+        .get(index..)
+        .ok_or_else(|| format!("invalid long name index {}", index))?;
+    let end = rest
+        .windows(2)
+        .position(|window| window == b"/\n")
+        .unwrap_or(rest.len());
+    Ok(String::from_utf8_lossy(&rest[..end]).into_owned())
+}
@@ -0,0 +1,30 @@
+use elfen::{elf::Elf, section};
+
+// lays out allocated sections from the lowest address like `objcopy -O binary`.
+// gaps between sections are filled with zeros, and trailing NOBITS sections are dropped.
+pub fn from_elf(elf: &Elf) -> Vec<u8> {
+    let mut sections: Vec<_> = elf
+        .sections
+        .iter()
+        .filter(|section| {
+            let header = &section.header;
+            section::Flags::Alloc.contained_in(header.flags)
+                && !header.is_nobits()
+                && header.size != 0
+        })
+        .collect();
+    sections.sort_by_key(|section| section.header.addr);
+
+    let origin = match sections.first() {
+        Some(section) => section.header.addr,
+        None => return Vec::new(),
+    };
+
+    let mut image = Vec::new();
+    for section in sections {
+        let offset = (section.header.addr - origin) as usize;
+        image.resize(offset, 0);
+        image.extend(section.data.as_raw().unwrap());
+    }
+    image
+}
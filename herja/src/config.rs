@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use crate::script::Script;
+
+#[derive(Debug, Clone)]
+pub struct LinkerConfig {
+    // symbol where execution starts
+    pub entry: String,
+    // address of the first segment
+    pub image_base: u64,
+    // addresses given by `--section-start`, which take priority over the script
+    pub section_starts: HashMap<String, u64>,
+    pub script: Option<Script>,
+    pub map_file: Option<String>,
+    pub oformat: OutputFormat,
+    // drop input sections not reachable from the entry
+    pub gc_sections: bool,
+    // report sections dropped by `gc_sections` to stderr
+    pub print_gc_sections: bool,
+    // position-independent executable, relocated by the loader
+    pub pie: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Elf,
+    // raw image of the sections without ELF headers
+    Binary,
+}
+
+impl Default for LinkerConfig {
+    fn default() -> Self {
+        Self {
+            entry: "_start".to_string(),
+            image_base: 0x400000,
+            section_starts: HashMap::new(),
+            script: None,
+            map_file: None,
+            oformat: OutputFormat::Elf,
+            gc_sections: false,
+            print_gc_sections: false,
+            pie: false,
+        }
+    }
+}
+
+impl LinkerConfig {
+    pub fn section_start(&self, name: &str) -> Option<u64> {
+        if let Some(&addr) = self.section_starts.get(name) {
+            return Some(addr);
+        }
+        self.script
+            .as_ref()?
+            .sections
+            .iter()
+            .find(|section| section.name == name)
+            .and_then(|section| section.addr)
+    }
+}
@@ -0,0 +1,112 @@
+use std::{fmt, io};
+
+use elfen::rel;
+
+// a place in an input file, e.g. `main.o:(.text+0x5)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub file: String,
+    pub section: String,
+    pub offset: u64,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:({}+{:#x})", self.file, self.section, self.offset)
+    }
+}
+
+#[derive(Debug)]
+pub enum LinkError {
+    UndefinedSymbol {
+        name: String,
+        location: Location,
+    },
+    DuplicateSymbol {
+        name: String,
+        first_file: String,
+        second_file: String,
+    },
+    RelocationOverflow {
+        name: String,
+        typ: rel::Type,
+        location: Location,
+    },
+    InvalidArchive {
+        file: String,
+        message: String,
+    },
+    NonPicRelocation {
+        name: String,
+        typ: rel::Type,
+        location: Location,
+    },
+    UnsupportedRelocation {
+        name: String,
+        typ: rel::Type,
+        location: Location,
+    },
+    // reading an input or writing an output failed
+    Io {
+        path: String,
+        error: io::Error,
+    },
+}
+
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use LinkError::*;
+        match self {
+            UndefinedSymbol { name, location } => {
+                write!(f, "{}: undefined reference to '{}'", location, name)
+            }
+            DuplicateSymbol {
+                name,
+                first_file,
+                second_file,
+            } => write!(
+                f,
+                "{}: multiple definition of '{}'; first defined in {}",
+                second_file, name, first_file
+            ),
+            RelocationOverflow {
+                name,
+                typ,
+                location,
+            } => write!(
+                f,
+                "{}: relocation truncated to fit: {} against '{}'",
+                location, typ, name
+            ),
+            InvalidArchive { file, message } => write!(f, "{}: invalid archive: {}", file, message),
+            NonPicRelocation {
+                name,
+                typ,
+                location,
+            } => write!(
+                f,
+                "{}: relocation {} against '{}' can not be used when making a PIE",
+                location, typ, name
+            ),
+            UnsupportedRelocation {
+                name,
+                typ: rel::Type::Unknown(typ),
+                location,
+            } => write!(
+                f,
+                "{}: unsupported relocation type {} against '{}'",
+                location, typ, name
+            ),
+            UnsupportedRelocation {
+                name,
+                typ,
+                location,
+            } => write!(
+                f,
+                "{}: unsupported relocation {} against '{}'",
+                location, typ, name
+            ),
+            Io { path, error } => write!(f, "{}: {}", path, error),
+        }
+    }
+}
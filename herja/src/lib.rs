@@ -1 +1,6 @@
+pub mod archive;
+pub mod binary;
+pub mod config;
+pub mod error;
 pub mod linker;
+pub mod script;
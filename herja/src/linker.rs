@@ -1,9 +1,21 @@
+mod dynamic;
+mod gc;
+mod map;
+
 use std::{
     collections::{HashMap, HashSet},
     fs,
     mem::size_of,
 };
 
+use crate::{
+    archive::{self, Archive},
+    binary,
+    config::{LinkerConfig, OutputFormat},
+    error::{LinkError, Location},
+    script::OutputSection,
+};
+
 use elfen::{
     elf::Elf,
     header::{self, Header},
@@ -15,32 +27,210 @@ use elfen::{
     tse::Tse,
 };
 
-pub fn link_to_files(input_files: Vec<String>, output_file: String) -> Result<(), String> {
-    let input_elfs = input_files
-        .into_iter()
-        .map(|path| Elf::read_from_file(&path))
-        .collect();
+use gc::DiscardedSection;
+
+pub fn link_to_files(
+    input_files: Vec<String>,
+    output_file: String,
+    config: &LinkerConfig,
+) -> Result<(), Vec<LinkError>> {
+    let mut objects = Vec::new();
+    let mut archives = Vec::new();
+    for path in input_files {
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(error) => return Err(vec![LinkError::Io { path, error }]),
+        };
+        if !archive::is_archive(&bytes) {
+            objects.push((path, Elf::read_from_bytes(&bytes)));
+            continue;
+        }
+
+        match Archive::parse(&bytes) {
+            Ok(archive) => archives.push((path, archive)),
+            Err(message) => {
+                return Err(vec![LinkError::InvalidArchive {
+                    file: path,
+                    message,
+                }])
+            }
+        }
+    }
 
-    let output_elf = link(input_elfs)?;
+    let (output_elf, map) = link_with_map(objects, archives, config)?;
 
-    let elf_bytes = output_elf.to_bytes();
-    fs::write(output_file, elf_bytes).unwrap();
+    let bytes = match config.oformat {
+        OutputFormat::Elf => output_elf.to_bytes(),
+        OutputFormat::Binary => binary::from_elf(&output_elf),
+    };
+    write_file(&output_file, &bytes)?;
+    if let Some(map_file) = &config.map_file {
+        write_file(map_file, map.as_bytes())?;
+    }
 
     Ok(())
 }
 
-pub fn link(input_elfs: Vec<Elf>) -> Result<Elf, String> {
-    let linker = Linker::new(input_elfs);
-    let output_elf = linker.link()?;
+fn write_file(path: &str, bytes: &[u8]) -> Result<(), Vec<LinkError>> {
+    fs::write(path, bytes).map_err(|error| {
+        vec![LinkError::Io {
+            path: path.to_string(),
+            error,
+        }]
+    })
+}
+
+pub fn link(input_elfs: Vec<Elf>) -> Result<Elf, Vec<LinkError>> {
+    let inputs = input_elfs
+        .into_iter()
+        .enumerate()
+        .map(|(i, elf)| (format!("<input {}>", i), elf))
+        .collect();
+    link_objects(inputs)
+}
+
+// links objects paired with their names, which are used in error messages
+pub fn link_objects(inputs: Vec<(String, Elf)>) -> Result<Elf, Vec<LinkError>> {
+    link_with_archives(inputs, Vec::new())
+}
+
+// links the objects and the archive members which define symbols they need
+pub fn link_with_archives(
+    objects: Vec<(String, Elf)>,
+    archives: Vec<(String, Archive)>,
+) -> Result<Elf, Vec<LinkError>> {
+    let (output_elf, _) = link_with_map(objects, archives, &LinkerConfig::default())?;
     Ok(output_elf)
 }
 
+// links like `link_with_archives` as configured, and also returns the link map
+pub fn link_with_map(
+    mut objects: Vec<(String, Elf)>,
+    archives: Vec<(String, Archive)>,
+    config: &LinkerConfig,
+) -> Result<(Elf, String), Vec<LinkError>> {
+    let (members, archive_members) = select_members(&objects, archives, &config.entry);
+    objects.extend(members);
+
+    let mut linker = Linker::new(objects, config.clone());
+    linker.archive_members = archive_members;
+    linker.link()
+}
+
+// pulls in archive members which define undefined symbols until nothing changes
+fn select_members(
+    objects: &[(String, Elf)],
+    archives: Vec<(String, Archive)>,
+    entry: &str,
+) -> (Vec<(String, Elf)>, Vec<ArchiveMember>) {
+    let mut defined = HashSet::new();
+    // undefined symbol -> file which refers to it
+    let mut undefined = HashMap::new();
+    // the entry point is needed even though nothing refers to it
+    undefined.insert(entry.to_string(), "(entry point)".to_string());
+    for (name, elf) in objects {
+        add_global_symbols(name, elf, &mut defined, &mut undefined);
+    }
+
+    let mut members: Vec<Option<(String, Elf)>> = archives
+        .into_iter()
+        .flat_map(|(path, archive)| {
+            archive
+                .members
+                .into_iter()
+                .filter(|member| member.data.starts_with(ELF_MAGIC))
+                .map(move |member| {
+                    let name = format!("{}({})", path, member.name);
+                    Some((name, Elf::read_from_bytes(&member.data)))
+                })
+        })
+        .collect();
+
+    let mut selected = Vec::new();
+    let mut archive_members = Vec::new();
+    loop {
+        let mut changed = false;
+        for member in members.iter_mut() {
+            let needed_symbol = member.as_ref().and_then(|(_, elf)| {
+                global_symbols(elf)
+                    .into_iter()
+                    .find(|(name, is_defined)| *is_defined && undefined.contains_key(name))
+            });
+            let symbol = match needed_symbol {
+                Some((symbol, _)) => symbol,
+                None => continue,
+            };
+
+            let (name, elf) = member.take().unwrap();
+            archive_members.push(ArchiveMember {
+                name: name.clone(),
+                referrer: undefined.get(&symbol).unwrap().clone(),
+                symbol,
+            });
+            add_global_symbols(&name, &elf, &mut defined, &mut undefined);
+            selected.push((name, elf));
+            changed = true;
+        }
+
+        if !changed {
+            break;
+        }
+    }
+    (selected, archive_members)
+}
+
+fn add_global_symbols(
+    file: &str,
+    elf: &Elf,
+    defined: &mut HashSet<String>,
+    undefined: &mut HashMap<String, String>,
+) {
+    for (name, is_defined) in global_symbols(elf) {
+        if is_defined {
+            undefined.remove(&name);
+            defined.insert(name);
+        } else if !defined.contains(&name) {
+            undefined.entry(name).or_insert_with(|| file.to_string());
+        }
+    }
+}
+
+// lists global symbols with whether they are defined
+fn global_symbols(elf: &Elf) -> Vec<(String, bool)> {
+    let (symtab_section, strtab_section) =
+        match (elf.get_section(".symtab"), elf.get_section(".strtab")) {
+            (Some(symtab), Some(strtab)) => (symtab, strtab),
+            _ => return Vec::new(),
+        };
+    let symbols = symtab_section.data.as_symbols().unwrap();
+    let strtab = strtab_section.data.as_strtab().unwrap();
+
+    symbols
+        .iter()
+        .filter(|symbol| match symbol.get_binding() {
+            symbol::Binding::Global => true,
+            // weak references do not pull in archive members
+            symbol::Binding::Weak => symbol.get_index_type() != symbol::IndexType::Undef,
+            _ => false,
+        })
+        .map(|symbol| {
+            let name = strtab.get(symbol.name as usize);
+            (name, symbol.get_index_type() != symbol::IndexType::Undef)
+        })
+        .collect()
+}
+
 struct Linker {
+    config: LinkerConfig,
     input_elfs: Vec<Elf>,
+    input_names: Vec<String>,
     output_elf: Elf,
 
     global_symbols: HashMap<String, SymbolSignature>,
-    symbol_map: HashMap<SectionPlace, Vec<String>>,
+    // local symbols of every input including section symbols
+    local_symbols: Vec<SymbolSignature>,
+    // symbols defined in each section
+    symbol_map: HashMap<SectionPlace, Vec<SymbolRef>>,
 
     relas: Vec<RelaSignature>,
     rela_map: HashMap<SectionPlace, Vec<usize>>,
@@ -49,10 +239,24 @@ struct Linker {
     symbol_indices: HashMap<String, usize>,
 
     section_offsets: HashMap<usize, u64>,
+    // symbols which have an entry in .got, in order
+    got_symbols: Vec<SymbolRef>,
+
+    errors: Vec<LinkError>,
+
+    // for the map file
+    archive_members: Vec<ArchiveMember>,
+    contributions: Vec<Contribution>,
+    applied_relas: Vec<AppliedRela>,
+    discarded_sections: Vec<DiscardedSection>,
+
+    // R_X86_64_RELATIVE relocations for the loader of a PIE
+    relative_relas: Vec<Rela>,
 }
 
-const BASE_ADDRESS: u64 = 0x400000;
 const PAGE_SIZE: u64 = 0x1000;
+const GOT_SYMBOL: &str = "_GLOBAL_OFFSET_TABLE_";
+const ELF_MAGIC: &[u8] = b"\x7fELF";
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 struct SectionPlace {
@@ -61,16 +265,29 @@ struct SectionPlace {
     section_index: usize,
 }
 
+// a symbol referred by relocations, as local symbols may share names
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+enum SymbolRef {
+    Global(String),
+    // index in local_symbols
+    Local(usize),
+}
+
 #[derive(Debug)]
 struct SymbolSignature {
     name: String,
     symbol: Symbol,
+    // index of the defining file in input_elfs
+    input_index: usize,
 }
 
 #[derive(Debug)]
 struct RelaSignature {
     symbol_name: String,
+    symbol: SymbolRef,
     rela: Rela,
+    // where the relocation is in the input file
+    location: Location,
 }
 
 #[derive(Debug)]
@@ -79,31 +296,88 @@ struct TseSignature {
     tse: Tse,
 }
 
+// archive member linked to define `symbol` referred by `referrer`
+#[derive(Debug)]
+struct ArchiveMember {
+    name: String,
+    referrer: String,
+    symbol: String,
+}
+
+// part of an output section which comes from an input file
+#[derive(Debug)]
+struct Contribution {
+    section_index: usize,
+    // name of the input section
+    name: String,
+    // None for sections generated by the linker
+    input_index: Option<usize>,
+    offset: u64,
+    size: u64,
+}
+
+#[derive(Debug)]
+struct AppliedRela {
+    rela_index: usize,
+    addr: u64,
+    value: i64,
+}
+
 impl Linker {
-    fn new(input_elfs: Vec<Elf>) -> Self {
+    fn new(inputs: Vec<(String, Elf)>, config: LinkerConfig) -> Self {
+        let (input_names, input_elfs) = inputs.into_iter().unzip();
         Self {
+            config,
             input_elfs,
+            input_names,
             output_elf: Elf::default(),
             global_symbols: HashMap::new(),
+            local_symbols: Vec::new(),
             symbol_map: HashMap::new(),
             relas: Vec::new(),
             rela_map: HashMap::new(),
             tses: Vec::new(),
             symbol_indices: HashMap::new(),
             section_offsets: HashMap::new(),
+            got_symbols: Vec::new(),
+            errors: Vec::new(),
+            archive_members: Vec::new(),
+            contributions: Vec::new(),
+            applied_relas: Vec::new(),
+            discarded_sections: Vec::new(),
+            relative_relas: Vec::new(),
         }
     }
 
-    fn link(mut self) -> Result<Elf, String> {
+    fn link(mut self) -> Result<(Elf, String), Vec<LinkError>> {
         self.init_elf();
 
         self.load_tses();
-        self.load_relas();
-        self.load_symbols();
+        let symbol_refs = self.load_symbols();
+        self.load_relas(&symbol_refs);
+        self.allocate_common_symbols();
+        if self.config.gc_sections {
+            self.gc_sections();
+        }
 
         self.link_sections();
+        self.gen_got();
+        if self.config.pie {
+            self.gen_dynamic();
+        }
+        self.check_undefined_symbols();
+        if !self.errors.is_empty() {
+            return Err(self.errors);
+        }
+
         self.layout();
         self.resolve_relas();
+        if !self.errors.is_empty() {
+            return Err(self.errors);
+        }
+        if self.config.pie {
+            self.fill_dynamic();
+        }
 
         self.gen_symtab_strtab();
         self.gen_tse_info();
@@ -112,7 +386,8 @@ impl Linker {
         self.layout();
         self.finalize_elf();
 
-        Ok(self.output_elf)
+        let map = self.gen_map();
+        Ok((self.output_elf, map))
     }
 
     fn init_elf(&mut self) {
@@ -120,7 +395,12 @@ impl Linker {
         header.set_class(header::Class::Class64);
         header.set_data(header::Data::Data2LSB);
         header.set_osabi(header::OSABI::OSABISysV);
-        header.set_filetype(header::Type::Exec);
+        // a PIE is a shared object which can be loaded anywhere
+        if self.config.pie {
+            header.set_filetype(header::Type::Dyn);
+        } else {
+            header.set_filetype(header::Type::Exec);
+        }
         header.set_machine(header::Machine::X86_64);
 
         self.output_elf
@@ -149,7 +429,8 @@ impl Linker {
         }
     }
 
-    fn load_relas(&mut self) {
+    // `symbol_refs` is the symbols in each input by index in its .symtab
+    fn load_relas(&mut self, symbol_refs: &[Vec<SymbolRef>]) {
         for (elf_index, elf) in self.input_elfs.iter_mut().enumerate() {
             let rela_sections: Vec<(usize, Vec<Rela>)> = elf
                 .sections
@@ -161,19 +442,28 @@ impl Linker {
                     (section_index, relas)
                 })
                 .collect();
-
-            let symtab_section = elf.get_section(".symtab").unwrap();
-            let symbols = symtab_section.data.as_symbols().unwrap();
-
-            let strtab_section = elf.get_section(".strtab").unwrap();
-            let strtab = strtab_section.data.as_strtab().unwrap();
+            let input_name = &self.input_names[elf_index];
 
             for (section_index, relas) in rela_sections {
+                let section_name = &elf.sections[section_index].name;
                 for rela in relas {
-                    let symbol = symbols.get(rela.get_symbol() as usize).unwrap();
-                    let symbol_name = strtab.get(symbol.name as usize);
+                    let symbol = symbol_refs[elf_index][rela.get_symbol() as usize].clone();
+                    let symbol_name = match &symbol {
+                        SymbolRef::Global(name) => name.clone(),
+                        SymbolRef::Local(index) => self.local_symbols[*index].name.clone(),
+                    };
+                    let location = Location {
+                        file: input_name.clone(),
+                        section: section_name.clone(),
+                        offset: rela.offset,
+                    };
 
-                    self.relas.push(RelaSignature { symbol_name, rela });
+                    self.relas.push(RelaSignature {
+                        symbol_name,
+                        symbol,
+                        rela,
+                        location,
+                    });
                     let place = SectionPlace {
                         elf_index: elf_index + 1,
                         section_index,
@@ -187,211 +477,746 @@ impl Linker {
         }
     }
 
-    fn load_symbols(&mut self) {
-        for (elf_index, elf) in self.input_elfs.iter_mut().enumerate() {
-            let symtab_section = elf.get_section_mut(".symtab").unwrap();
-            let symtab_data = symtab_section.data.as_symbols_mut().unwrap();
-            let symbols = std::mem::replace(symtab_data, Vec::new());
+    // returns the symbols in each input by index in its .symtab
+    fn load_symbols(&mut self) -> Vec<Vec<SymbolRef>> {
+        let mut symbol_refs = Vec::new();
+        for elf_index in 0..self.input_elfs.len() {
+            let symbols = Self::named_symbols(&self.input_elfs[elf_index]);
+            let refs = symbols
+                .into_iter()
+                .map(|(name, symbol)| self.load_symbol(elf_index, name, symbol))
+                .collect();
+            symbol_refs.push(refs);
+        }
+        symbol_refs
+    }
 
-            let strtab_section = elf.get_section_mut(".strtab").unwrap();
-            let strtab_data = strtab_section.data.as_strtab_mut().unwrap();
-            let strtab = std::mem::replace(strtab_data, Strtab::default());
+    // section symbols are named after their sections
+    fn named_symbols(elf: &Elf) -> Vec<(String, Symbol)> {
+        let symtab_section = elf.get_section(".symtab").unwrap();
+        let symbols = symtab_section.data.as_symbols().unwrap();
 
-            for symbol in symbols {
-                if symbol.get_binding() != symbol::Binding::Global {
-                    continue;
-                }
-                let symbol_name = strtab.get(symbol.name as usize);
+        let strtab_section = elf.get_section(".strtab").unwrap();
+        let strtab = strtab_section.data.as_strtab().unwrap();
 
-                if let Some(symbol_sig) = self.global_symbols.get(&symbol_name) {
-                    if symbol.get_index_type() == symbol::IndexType::Undef {
-                        continue;
-                    }
-                    if symbol_sig.symbol.get_index_type() != symbol::IndexType::Undef {
-                        panic!("duplicate symbol: {}", symbol_name);
-                    }
+        symbols
+            .iter()
+            .map(|symbol| {
+                let name = if symbol.get_type() == symbol::Type::Section {
+                    elf.sections[symbol.section_index as usize].name.clone()
+                } else {
+                    strtab.get(symbol.name as usize)
+                };
+                (name, *symbol)
+            })
+            .collect()
+    }
+
+    fn load_symbol(&mut self, elf_index: usize, name: String, symbol: Symbol) -> SymbolRef {
+        let place = SectionPlace {
+            elf_index: elf_index + 1,
+            section_index: symbol.section_index as usize,
+        };
+        let mut symbol_sig = SymbolSignature {
+            name: name.clone(),
+            symbol,
+            input_index: elf_index,
+        };
+
+        // local symbols are only visible in their file
+        if symbol.get_binding() == symbol::Binding::Local {
+            self.local_symbols.push(symbol_sig);
+            let symbol_ref = SymbolRef::Local(self.local_symbols.len() - 1);
+            self.symbol_map
+                .entry(place)
+                .or_default()
+                .push(symbol_ref.clone());
+            return symbol_ref;
+        }
+
+        let symbol_ref = SymbolRef::Global(name.clone());
+        if let Some(existing) = self.global_symbols.get_mut(&name) {
+            let is_undefined = symbol.get_index_type() == symbol::IndexType::Undef;
+            let is_weak = symbol.get_binding() == symbol::Binding::Weak;
+            let existing_is_undefined =
+                existing.symbol.get_index_type() == symbol::IndexType::Undef;
+            let existing_is_weak = existing.symbol.get_binding() == symbol::Binding::Weak;
+
+            if is_undefined {
+                // a weak reference becomes a strong one if any other is
+                if existing_is_undefined && !is_weak {
+                    existing.symbol.set_binding(symbol::Binding::Global);
+                }
+                return symbol_ref;
+            }
+            let is_common = symbol.get_index_type() == symbol::IndexType::Common;
+            let existing_is_common = existing.symbol.get_index_type() == symbol::IndexType::Common;
+            if is_common && !existing_is_undefined {
+                // a COMMON symbol gives way to a definition, and the largest
+                // of COMMON symbols is kept, as strictly aligned as any of them
+                if !existing_is_common {
+                    return symbol_ref;
+                }
+                let align = existing.symbol.value.max(symbol.value);
+                existing.symbol.value = align;
+                if symbol.size <= existing.symbol.size {
+                    return symbol_ref;
+                }
+                symbol_sig.symbol.value = align;
+            } else if !existing_is_undefined && !existing_is_common {
+                if !is_weak && !existing_is_weak {
+                    self.errors.push(LinkError::DuplicateSymbol {
+                        name: name.clone(),
+                        first_file: self.input_names[existing.input_index].clone(),
+                        second_file: self.input_names[elf_index].clone(),
+                    });
+                }
+                // a weak definition is overridden by a strong one
+                if is_weak || !existing_is_weak {
+                    return symbol_ref;
                 }
+            }
 
-                self.global_symbols.insert(
-                    symbol_name.clone(),
-                    SymbolSignature {
-                        name: symbol_name.clone(),
-                        symbol,
-                    },
-                );
+            let existing_place = SectionPlace {
+                elf_index: existing.input_index + 1,
+                section_index: existing.symbol.section_index as usize,
+            };
+            if let Some(symbol_refs) = self.symbol_map.get_mut(&existing_place) {
+                symbol_refs.retain(|other| *other != symbol_ref);
+            }
+        }
 
-                let place = SectionPlace {
-                    elf_index: elf_index + 1,
-                    section_index: symbol.section_index as usize,
-                };
-                self.symbol_map
-                    .entry(place)
-                    .or_insert(Vec::new())
-                    .push(symbol_name);
+        self.global_symbols.insert(name, symbol_sig);
+        self.symbol_map
+            .entry(place)
+            .or_default()
+            .push(symbol_ref.clone());
+        symbol_ref
+    }
+
+    // gives each COMMON symbol, whose value is its alignment, a .bss section of
+    // its own in the defining file, so that it is laid out like other .bss
+    fn allocate_common_symbols(&mut self) {
+        let mut names: Vec<String> = self
+            .global_symbols
+            .values()
+            .filter(|sig| sig.symbol.get_index_type() == symbol::IndexType::Common)
+            .map(|sig| sig.name.clone())
+            .collect();
+        names.sort();
+
+        for name in names {
+            let symbol_sig = self.global_symbols.get_mut(&name).unwrap();
+            let elf = &mut self.input_elfs[symbol_sig.input_index];
+            let mut header = SectionHeader::default();
+            header.set_type(section::Type::Nobits);
+            header.set_flags(section::Flags::Alloc);
+            header.set_flags(section::Flags::Write);
+            header.size = symbol_sig.symbol.size;
+            header.alignment = symbol_sig.symbol.value.max(1);
+            let section_index = elf.sections.len();
+            elf.add_section(".bss", header, SectionData::None);
+
+            let old_place = SectionPlace {
+                elf_index: symbol_sig.input_index + 1,
+                section_index: symbol_sig.symbol.section_index as usize,
+            };
+            let place = SectionPlace {
+                elf_index: symbol_sig.input_index + 1,
+                section_index,
+            };
+            symbol_sig.symbol.section_index = section_index as u16;
+            symbol_sig.symbol.value = 0;
+
+            let symbol_ref = SymbolRef::Global(name);
+            if let Some(symbol_refs) = self.symbol_map.get_mut(&old_place) {
+                symbol_refs.retain(|other| *other != symbol_ref);
             }
+            self.symbol_map.entry(place).or_default().push(symbol_ref);
         }
     }
 
     fn link_sections(&mut self) {
-        for section_name in self.list_sections_to_alloc() {
+        // input sections already linked, as (elf_index, section_index)
+        let mut linked_sections = HashSet::new();
+        for output_section in self.plan_output_sections() {
             let new_section_index = self.output_elf.sections.len();
-            let mut section_header = None;
+            let inputs: Vec<(usize, usize)> = self
+                .input_elfs
+                .iter()
+                .enumerate()
+                .flat_map(|(elf_index, elf)| {
+                    elf.sections
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, section)| {
+                            section::Flags::Alloc.contained_in(section.header.flags)
+                                && output_section.matches(&section.name)
+                        })
+                        .map(move |(section_index, _)| (elf_index, section_index))
+                })
+                .collect();
+            let inputs: Vec<(usize, usize)> = inputs
+                .into_iter()
+                .filter(|&(elf_index, section_index)| !self.is_discarded(elf_index, section_index))
+                .filter(|input| linked_sections.insert(*input))
+                .collect();
+            if inputs.is_empty() {
+                continue;
+            }
+
+            // .bss-like inputs are filled with zeros unless every input is NOBITS
+            let nobits = inputs.iter().all(|&(elf_index, section_index)| {
+                self.input_elfs[elf_index].sections[section_index]
+                    .header
+                    .is_nobits()
+            });
+            let (first_elf_index, first_section_index) = inputs[0];
+            let mut header = self.input_elfs[first_elf_index].sections[first_section_index].header;
+            let mut size = 0;
             let mut linked_data: Vec<u8> = Vec::new();
-            for (elf_index, elf) in self.input_elfs.iter_mut().enumerate() {
-                let section_index = if let Some(index) = elf.find_section(&section_name) {
-                    index
+            for (elf_index, section_index) in inputs {
+                let section = &self.input_elfs[elf_index].sections[section_index];
+                // the output section is aligned as strictly as any of its inputs
+                header.alignment = header.alignment.max(section.header.alignment);
+                let offset = Self::align(size, section.header.alignment.max(1));
+                let input_size = if section.header.is_nobits() {
+                    section.header.size
                 } else {
-                    continue;
+                    section.data.len() as u64
                 };
-
-                let section = elf.sections.get(section_index).unwrap();
-                section_header = Some(section.header);
-                let offset = linked_data.len() as u64;
-                let section_data = section.data.as_raw().unwrap();
-                linked_data.extend(section_data);
-
-                let place = SectionPlace {
-                    elf_index: elf_index + 1,
-                    section_index,
-                };
-                let new_place = SectionPlace {
-                    elf_index: 0,
-                    section_index: new_section_index,
-                };
-
-                // offset symbols
-                if let Some(symbol_names) = self.symbol_map.remove(&place) {
-                    for symbol_name in &symbol_names {
-                        let symbol_sig = self.global_symbols.get_mut(symbol_name).unwrap();
-                        symbol_sig.symbol.section_index = new_section_index as u16;
-                        symbol_sig.symbol.value += offset;
+                size = offset + input_size;
+                if !nobits {
+                    linked_data.resize(offset as usize, 0);
+                    match section.data.as_raw() {
+                        Some(data) if !section.header.is_nobits() => linked_data.extend(data),
+                        _ => linked_data.resize(size as usize, 0),
                     }
-                    self.symbol_map
-                        .entry(new_place.clone())
-                        .or_insert(Vec::new())
-                        .extend(symbol_names);
                 }
 
-                // offset relas
-                if let Some(rela_indices) = self.rela_map.remove(&place) {
-                    for rela_index in &rela_indices {
-                        let rela_sig = self.relas.get_mut(*rela_index).unwrap();
-                        rela_sig.rela.offset += offset;
-                    }
-
-                    self.rela_map
-                        .entry(new_place)
-                        .or_insert(Vec::new())
-                        .extend(rela_indices);
+                if input_size != 0 {
+                    self.contributions.push(Contribution {
+                        section_index: new_section_index,
+                        name: section.name.clone(),
+                        input_index: Some(elf_index),
+                        offset,
+                        size: input_size,
+                    });
                 }
+                self.move_input_section(elf_index, section_index, new_section_index, offset);
             }
 
-            if linked_data.len() == 0 {
+            // an empty section is kept only for the symbols in it to have an address
+            if size == 0 && !self.has_symbols_in_use(new_section_index) {
+                self.symbol_map.remove(&SectionPlace {
+                    elf_index: 0,
+                    section_index: new_section_index,
+                });
                 continue;
             }
 
+            if nobits {
+                header.size = size;
+            } else {
+                header.set_type(section::Type::Progbits);
+            }
             self.output_elf.add_section(
-                &section_name,
-                section_header.unwrap(),
+                &output_section.name,
+                header,
                 SectionData::Raw(linked_data),
             );
         }
     }
 
-    fn list_sections_to_alloc(&self) -> Vec<String> {
-        let mut section_names = HashSet::new();
+    // whether an output section defines a symbol other than the section one,
+    // or a symbol in it is referred to by a relocation
+    fn has_symbols_in_use(&self, section_index: usize) -> bool {
+        let place = SectionPlace {
+            elf_index: 0,
+            section_index,
+        };
+        let symbol_refs = match self.symbol_map.get(&place) {
+            Some(symbol_refs) => symbol_refs,
+            None => return false,
+        };
+        symbol_refs.iter().any(|symbol_ref| {
+            self.symbol_sig(symbol_ref)
+                .is_some_and(|sig| sig.symbol.get_type() != symbol::Type::Section)
+                || self
+                    .relas
+                    .iter()
+                    .any(|rela_sig| rela_sig.symbol == *symbol_ref)
+        })
+    }
+
+    // moves symbols and relocations in an input section to the output section
+    fn move_input_section(
+        &mut self,
+        elf_index: usize,
+        section_index: usize,
+        new_section_index: usize,
+        offset: u64,
+    ) {
+        let place = SectionPlace {
+            elf_index: elf_index + 1,
+            section_index,
+        };
+        let new_place = SectionPlace {
+            elf_index: 0,
+            section_index: new_section_index,
+        };
+
+        // offset symbols
+        if let Some(symbol_refs) = self.symbol_map.remove(&place) {
+            for symbol_ref in &symbol_refs {
+                let symbol_sig = self.symbol_sig_mut(symbol_ref).unwrap();
+                symbol_sig.symbol.section_index = new_section_index as u16;
+                symbol_sig.symbol.value += offset;
+            }
+            self.symbol_map
+                .entry(new_place.clone())
+                .or_default()
+                .extend(symbol_refs);
+        }
+
+        // offset relas
+        if let Some(rela_indices) = self.rela_map.remove(&place) {
+            for rela_index in &rela_indices {
+                let rela_sig = self.relas.get_mut(*rela_index).unwrap();
+                rela_sig.rela.offset += offset;
+            }
+
+            self.rela_map
+                .entry(new_place)
+                .or_default()
+                .extend(rela_indices);
+        }
+    }
+
+    // output sections in the script, followed by the other allocated sections
+    fn plan_output_sections(&self) -> Vec<OutputSection> {
+        let mut output_sections = match &self.config.script {
+            Some(script) => script.sections.clone(),
+            None => Vec::new(),
+        };
+
+        let mut orphan_headers = HashMap::new();
         for elf in &self.input_elfs {
             for section in &elf.sections {
-                if section::Flags::Alloc.contained_in(section.header.flags) {
-                    section_names.insert(section.name.clone());
+                let is_orphan = !output_sections
+                    .iter()
+                    .any(|output_section| output_section.matches(&section.name));
+                if section::Flags::Alloc.contained_in(section.header.flags) && is_orphan {
+                    orphan_headers
+                        .entry(Self::orphan_output_name(&section.name, &output_sections))
+                        .or_insert(section.header);
                 }
             }
         }
-        let mut symbol_names: Vec<String> = section_names.into_iter().collect();
-        symbol_names.sort();
-        symbol_names
+        let mut orphan_names: Vec<&String> = orphan_headers.keys().collect();
+        orphan_names.sort_by_key(|name| Self::section_order(name, &orphan_headers[*name]));
+
+        output_sections.extend(orphan_names.into_iter().map(|name| {
+            let mut output_section = OutputSection::new(name);
+            if Self::standard_section_name(name).is_some() {
+                output_section.patterns.push(format!("{}.*", name));
+            }
+            output_section
+        }));
+        output_sections
+    }
+
+    // e.g. `.text.main` goes to `.text` unless the script defines `.text`
+    fn orphan_output_name(name: &str, script_sections: &[OutputSection]) -> String {
+        match Self::standard_section_name(name) {
+            Some(base)
+                if !script_sections
+                    .iter()
+                    .any(|output_section| output_section.name == base) =>
+            {
+                base.to_string()
+            }
+            _ => name.to_string(),
+        }
+    }
+
+    // `.text` for `.text` and `.text.*`, and so on
+    fn standard_section_name(name: &str) -> Option<&'static str> {
+        [".text", ".rodata", ".data", ".bss"]
+            .iter()
+            .copied()
+            .find(|base| {
+                name.strip_prefix(base)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+            })
+    }
+
+    // .text, .rodata, .data and then .bss
+    fn section_order(name: &str, shdr: &SectionHeader) -> (u8, bool, String) {
+        let flags = Self::gen_segment(shdr).flags;
+        (
+            Self::segment_rank(flags),
+            shdr.is_nobits(),
+            name.to_string(),
+        )
     }
 
     fn layout(&mut self) {
         self.output_elf.segments.clear();
 
-        let mut cur_offset = size_of::<Header>() as u64;
+        let groups = self.group_alloc_sections();
+        let dynamic_index = self.output_elf.find_section(".dynamic");
+        // a PT_LOAD for each group, PT_DYNAMIC if any and PT_GNU_STACK
+        let num_segments = groups.len() + dynamic_index.iter().count() + 1;
+
+        let mut cur_offset =
+            (size_of::<Header>() + size_of::<ProgramHeader>() * num_segments) as u64;
+        let mut cur_addr = self.config.image_base;
+        for section_indices in groups {
+            let first_header = &self.output_elf.sections[section_indices[0]].header;
+            let mut phdr = Self::gen_segment(first_header);
+            // each segment starts on a new page, at an address congruent
+            // to its file offset modulo the page size
+            let first_name = &self.output_elf.sections[section_indices[0]].name;
+            let addr = match self.config.section_start(first_name) {
+                Some(addr) => addr,
+                // flat images have no page protection, so sections are packed
+                None if self.config.oformat == OutputFormat::Binary => cur_addr,
+                None => Self::align(cur_addr, PAGE_SIZE) + cur_offset % PAGE_SIZE,
+            };
+            phdr.offset = cur_offset + (addr.wrapping_sub(cur_offset) & (PAGE_SIZE - 1));
+            phdr.virt_addr = addr;
+            phdr.phys_addr = phdr.virt_addr;
+            cur_offset = phdr.offset;
+            cur_addr = phdr.virt_addr;
+
+            for section_index in section_indices {
+                let section = &mut self.output_elf.sections[section_index];
+                let shdr = &mut section.header;
+                if !shdr.is_nobits() {
+                    shdr.size = section.data.len() as u64;
+                }
+
+                shdr.addr = Self::align(cur_addr, shdr.alignment.max(1));
+                // NOBITS sections take up addresses but no space in the file
+                if shdr.is_nobits() {
+                    shdr.offset = cur_offset;
+                } else {
+                    shdr.offset = cur_offset + (shdr.addr - cur_addr);
+                    cur_offset = shdr.offset + shdr.size;
+                }
+                cur_addr = shdr.addr + shdr.size;
+
+                self.section_offsets.insert(section_index, shdr.addr);
+            }
+
+            phdr.file_size = cur_offset - phdr.offset;
+            phdr.memory_size = cur_addr - phdr.virt_addr;
+            self.output_elf.segments.push(phdr);
+        }
+        if let Some(dynamic_index) = dynamic_index {
+            let shdr = &self.output_elf.sections[dynamic_index].header;
+            let phdr = Self::gen_dynamic_segment(shdr);
+            self.output_elf.segments.push(phdr);
+        }
+        self.output_elf.segments.push(Self::gen_stack_segment());
+
         for (section_index, section) in self.output_elf.sections.iter_mut().enumerate() {
-            // skip null section
-            if section_index == 0 {
+            let shdr = &mut section.header;
+            // skip null section and allocated sections laid out above
+            if section_index == 0 || section::Flags::Alloc.contained_in(shdr.flags) {
                 continue;
             }
-            let shdr = &mut section.header;
 
             shdr.size = section.data.len() as u64;
+            shdr.offset = Self::align(cur_offset, shdr.alignment.max(1));
+            cur_offset = shdr.offset + shdr.size;
 
-            if section::Flags::Alloc.contained_in(shdr.flags) {
-                let mut phdr = Self::gen_segment(&shdr);
-                shdr.offset = Self::align(cur_offset, phdr.alignment);
-                phdr.offset = shdr.offset;
-
-                shdr.addr = BASE_ADDRESS + shdr.offset;
-                phdr.virt_addr = shdr.addr;
-                phdr.phys_addr = shdr.addr;
+            self.section_offsets.insert(section_index, shdr.offset);
+        }
+    }
 
-                self.output_elf.segments.push(phdr);
-            } else {
-                shdr.offset = Self::align(cur_offset, shdr.alignment);
+    // groups allocated sections by permission into RX, R and RW segments
+    fn group_alloc_sections(&self) -> Vec<Vec<usize>> {
+        let sections = &self.output_elf.sections;
+        let mut section_indices: Vec<usize> = (1..sections.len())
+            .filter(|&index| section::Flags::Alloc.contained_in(sections[index].header.flags))
+            .collect();
+        // in the order of the script if any
+        section_indices.sort_by_key(|&index| {
+            let section = &sections[index];
+            (
+                self.script_position(&section.name),
+                Self::section_order(&section.name, &section.header),
+            )
+        });
+
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for index in section_indices {
+            let section = &sections[index];
+            let flags = Self::gen_segment(&section.header).flags;
+            // a section with a fixed address or after NOBITS one starts a new segment
+            let starts_segment = self.config.section_start(&section.name).is_some();
+            match groups.last_mut() {
+                Some(group) if !starts_segment => {
+                    let last_header = &sections[*group.last().unwrap()].header;
+                    if Self::gen_segment(last_header).flags == flags && !last_header.is_nobits() {
+                        group.push(index);
+                    } else {
+                        groups.push(vec![index]);
+                    }
+                }
+                _ => groups.push(vec![index]),
             }
-            cur_offset = shdr.offset + shdr.size;
+        }
+        groups
+    }
 
-            let offset = if shdr.addr != 0 {
-                shdr.addr
-            } else {
-                shdr.offset
-            };
-            self.section_offsets.insert(section_index, offset);
+    // sections not in the script come after the ones in it
+    fn script_position(&self, name: &str) -> usize {
+        self.config
+            .script
+            .as_ref()
+            .and_then(|script| {
+                script
+                    .sections
+                    .iter()
+                    .position(|section| section.name == name)
+            })
+            .unwrap_or(usize::MAX)
+    }
+
+    fn segment_rank(flags: u32) -> u8 {
+        let executable = segment::Flags::X.contained_in(flags);
+        let writable = segment::Flags::W.contained_in(flags);
+        match (executable, writable) {
+            (true, false) => 0,
+            (false, false) => 1,
+            (false, true) => 2,
+            (true, true) => 3,
+        }
+    }
+
+    // reports every relocation in linked sections against an undefined symbol
+    fn check_undefined_symbols(&mut self) {
+        let mut rela_indices: Vec<usize> = self
+            .rela_map
+            .iter()
+            .filter(|(place, _)| place.elf_index == 0)
+            .flat_map(|(_, rela_indices)| rela_indices.iter().copied())
+            .collect();
+        rela_indices.sort_unstable();
+
+        for rela_index in rela_indices {
+            let rela_sig = &self.relas[rela_index];
+            // weak undefined symbols are resolved to 0
+            let is_resolved = self.symbol_sig(&rela_sig.symbol).is_some_and(|symbol_sig| {
+                symbol_sig.symbol.get_index_type() != symbol::IndexType::Undef
+                    || symbol_sig.symbol.get_binding() == symbol::Binding::Weak
+            });
+            if !is_resolved {
+                self.errors.push(LinkError::UndefinedSymbol {
+                    name: rela_sig.symbol_name.clone(),
+                    location: rela_sig.location.clone(),
+                });
+            }
         }
     }
 
     fn resolve_relas(&mut self) {
-        for (section_index, section) in self.output_elf.sections.iter_mut().enumerate() {
-            let place = SectionPlace {
-                elf_index: 0,
-                section_index,
-            };
-            let rela_indices = if let Some(indices) = self.rela_map.get(&place) {
-                indices
-            } else {
+        self.fill_got();
+
+        let mut patches = Vec::new();
+        let mut applied_relas = Vec::new();
+        let mut relative_relas = Vec::new();
+        let mut errors = Vec::new();
+        // in a fixed order to report errors deterministically
+        let mut places: Vec<(&SectionPlace, &Vec<usize>)> = self.rela_map.iter().collect();
+        places.sort_by_key(|(place, _)| place.section_index);
+        for (place, rela_indices) in places {
+            // relocations in sections which are not linked
+            if place.elf_index != 0 {
                 continue;
-            };
+            }
 
             for rela_index in rela_indices {
-                let rela_sig = self.relas.get_mut(*rela_index).unwrap();
-                let target_symbol = self
-                    .global_symbols
-                    .get(&rela_sig.symbol_name)
-                    .unwrap()
-                    .symbol;
-
-                let addr_from = rela_sig.rela.offset as i32;
-                let addr_to = target_symbol.value as i32;
-
-                let mut diff = match rela_sig.rela.get_type() {
-                    rel::Type::Pc32 => {
-                        let offset_from = *self.section_offsets.get(&section_index).unwrap() as i32;
-                        let sym_idx: u16 = target_symbol.get_index_type().into();
-                        let offset_to = *self.section_offsets.get(&sym_idx.into()).unwrap() as i32;
-
-                        (addr_to + offset_to) - (addr_from + offset_from)
+                let rela_sig = self.relas.get(*rela_index).unwrap();
+                let rela = &rela_sig.rela;
+
+                // S: address of the symbol, A: addend, P: address of the place
+                let s = match self.symbol_addr(&rela_sig.symbol) {
+                    Some(addr) => addr as i64,
+                    None => {
+                        errors.push(LinkError::UndefinedSymbol {
+                            name: rela_sig.symbol_name.clone(),
+                            location: rela_sig.location.clone(),
+                        });
+                        continue;
+                    }
+                };
+                let a = rela.addend;
+                let p =
+                    (self.section_offsets.get(&place.section_index).unwrap() + rela.offset) as i64;
+
+                let value = match rela.get_type() {
+                    rel::Type::Pc32 | rel::Type::Plt32 => s + a - p,
+                    rel::Type::GotPcRel => {
+                        let g = self.find_got_entry(&rela_sig.symbol) as i64;
+                        g + a - p
+                    }
+                    rel::Type::Abs64 | rel::Type::Abs32 | rel::Type::Abs32S => s + a,
+                    typ => {
+                        errors.push(LinkError::UnsupportedRelocation {
+                            name: rela_sig.symbol_name.clone(),
+                            typ,
+                            location: rela_sig.location.clone(),
+                        });
+                        continue;
                     }
-                    rel::Type::Plt32 => addr_to - addr_from,
-                    _ => panic!(),
                 };
-                diff += rela_sig.rela.addend as i32;
 
-                let code_index = addr_from as usize;
-                let section_data = section.data.as_raw_mut().unwrap();
-                for (i, value) in diff.to_le_bytes().iter().enumerate() {
-                    section_data[(code_index + i)] = *value;
+                // the loader of a PIE can only relocate 64-bit addresses
+                let moves = self.config.pie && self.moves_with_base(&rela_sig.symbol);
+                let bytes = match rela.get_type() {
+                    rel::Type::Abs64 => {
+                        if moves {
+                            relative_relas.push(Self::relative_rela(p as u64, value));
+                        }
+                        Ok(value.to_le_bytes().to_vec())
+                    }
+                    rel::Type::Abs32 | rel::Type::Abs32S if moves => {
+                        Err(LinkError::NonPicRelocation {
+                            name: rela_sig.symbol_name.clone(),
+                            typ: rela.get_type(),
+                            location: rela_sig.location.clone(),
+                        })
+                    }
+                    rel::Type::Abs32 => {
+                        if (0..=u32::MAX as i64).contains(&value) {
+                            Ok((value as u32).to_le_bytes().to_vec())
+                        } else {
+                            Err(Self::overflow(rela_sig))
+                        }
+                    }
+                    _ => Self::fit_i32(value, rela_sig).map(|value| value.to_le_bytes().to_vec()),
+                };
+                match bytes {
+                    Ok(bytes) => {
+                        patches.push((place.section_index, rela.offset as usize, bytes));
+                        applied_relas.push(AppliedRela {
+                            rela_index: *rela_index,
+                            addr: p as u64,
+                            value,
+                        });
+                    }
+                    Err(err) => errors.push(err),
                 }
             }
         }
+
+        for (section_index, offset, bytes) in patches {
+            let section = self.output_elf.sections.get_mut(section_index).unwrap();
+            let section_data = section.data.as_raw_mut().unwrap();
+            section_data[offset..offset + bytes.len()].copy_from_slice(&bytes);
+        }
+
+        self.applied_relas = applied_relas;
+        self.relative_relas = relative_relas;
+        self.errors.extend(errors);
+    }
+
+    fn fit_i32(value: i64, rela_sig: &RelaSignature) -> Result<i32, LinkError> {
+        if (i32::MIN as i64..=i32::MAX as i64).contains(&value) {
+            Ok(value as i32)
+        } else {
+            Err(Self::overflow(rela_sig))
+        }
+    }
+
+    fn overflow(rela_sig: &RelaSignature) -> LinkError {
+        LinkError::RelocationOverflow {
+            name: rela_sig.symbol_name.clone(),
+            typ: rela_sig.rela.get_type(),
+            location: rela_sig.location.clone(),
+        }
+    }
+
+    // adds .got with an entry for every symbol referred by R_X86_64_GOTPCREL
+    fn gen_got(&mut self) {
+        let mut got_symbols: Vec<SymbolRef> = self
+            .rela_map
+            .iter()
+            .filter(|(place, _)| place.elf_index == 0)
+            .flat_map(|(_, rela_indices)| rela_indices.iter().map(|&index| &self.relas[index]))
+            .filter(|rela_sig| rela_sig.rela.get_type() == rel::Type::GotPcRel)
+            .map(|rela_sig| rela_sig.symbol.clone())
+            .collect();
+        got_symbols.sort();
+        got_symbols.dedup();
+
+        let got_referred = self.global_symbols.contains_key(GOT_SYMBOL);
+        if got_symbols.is_empty() && !got_referred {
+            return;
+        }
+
+        let mut header = SectionHeader::default();
+        header.set_type(section::Type::Progbits);
+        header.set_flags(section::Flags::Alloc);
+        header.set_flags(section::Flags::Write);
+        header.alignment = 8;
+        header.entry_size = 8;
+
+        let got_index = self.output_elf.sections.len();
+        self.contributions.push(Contribution {
+            section_index: got_index,
+            name: ".got".to_string(),
+            input_index: None,
+            offset: 0,
+            size: got_symbols.len() as u64 * 8,
+        });
+        let data = SectionData::Raw(vec![0; got_symbols.len() * 8]);
+        self.output_elf.add_section(".got", header, data);
+        self.got_symbols = got_symbols;
+
+        // _GLOBAL_OFFSET_TABLE_ points to the head of .got
+        if let Some(symbol_sig) = self.global_symbols.get_mut(GOT_SYMBOL) {
+            symbol_sig.symbol.section_index = got_index as u16;
+            symbol_sig.symbol.value = 0;
+            let place = SectionPlace {
+                elf_index: 0,
+                section_index: got_index,
+            };
+            self.symbol_map
+                .entry(place)
+                .or_default()
+                .push(SymbolRef::Global(GOT_SYMBOL.to_string()));
+        }
+    }
+
+    fn fill_got(&mut self) {
+        let addrs: Vec<u64> = self
+            .got_symbols
+            .iter()
+            .map(|symbol_ref| self.symbol_addr(symbol_ref).unwrap())
+            .collect();
+
+        if let Some(section) = self.output_elf.get_section_mut(".got") {
+            let got_data = section.data.as_raw_mut().unwrap();
+            for (i, addr) in addrs.into_iter().enumerate() {
+                got_data[i * 8..(i + 1) * 8].copy_from_slice(&addr.to_le_bytes());
+            }
+        }
+    }
+
+    fn find_got_entry(&self, symbol_ref: &SymbolRef) -> u64 {
+        let got_index = self.output_elf.find_section(".got").unwrap();
+        let got_addr = self.section_offsets.get(&got_index).unwrap();
+        let entry_index = self
+            .got_symbols
+            .iter()
+            .position(|got_symbol| got_symbol == symbol_ref)
+            .unwrap();
+        got_addr + entry_index as u64 * 8
     }
 
     fn gen_segment(shdr: &SectionHeader) -> ProgramHeader {
@@ -400,9 +1225,6 @@ impl Linker {
         phdr.set_flags(segment::Flags::R);
         phdr.alignment = PAGE_SIZE;
 
-        phdr.file_size = shdr.size;
-        phdr.memory_size = shdr.size;
-
         if section::Flags::Execinstr.contained_in(shdr.flags) {
             phdr.set_flags(segment::Flags::X);
         }
@@ -413,6 +1235,16 @@ impl Linker {
         phdr
     }
 
+    // marks the stack as non-executable
+    fn gen_stack_segment() -> ProgramHeader {
+        let mut phdr = ProgramHeader::default();
+        phdr.set_type(segment::Type::GnuStack);
+        phdr.set_flags(segment::Flags::R);
+        phdr.set_flags(segment::Flags::W);
+        phdr.alignment = 16;
+        phdr
+    }
+
     fn align(x: u64, align: u64) -> u64 {
         (x + align - 1) & !(align - 1)
     }
@@ -424,19 +1256,54 @@ impl Linker {
         symbols.push(Symbol::default());
         strtab.insert("".into());
 
-        let mut symbol_sigs: Vec<&SymbolSignature> = self.global_symbols.values().collect();
-        symbol_sigs.sort_by_key(|sig| sig.symbol.value);
+        // symbols in sections which are linked
+        let linked: HashSet<&SymbolRef> = self
+            .symbol_map
+            .iter()
+            .filter(|(place, _)| place.elf_index == 0)
+            .flat_map(|(_, symbol_refs)| symbol_refs)
+            .collect();
+        let is_output = |symbol_ref: &SymbolRef, symbol: &Symbol| match symbol.get_index_type() {
+            symbol::IndexType::Abs => true,
+            symbol::IndexType::Undef => symbol.get_binding() == symbol::Binding::Weak,
+            _ => linked.contains(symbol_ref),
+        };
+
+        // local symbols come first as ELF requires, in the order of inputs
+        let mut symbol_sigs: Vec<&SymbolSignature> = self
+            .local_symbols
+            .iter()
+            .enumerate()
+            .filter(|(index, sig)| {
+                sig.symbol.get_type() != symbol::Type::Section
+                    && is_output(&SymbolRef::Local(*index), &sig.symbol)
+            })
+            .map(|(_, sig)| sig)
+            .collect();
+
+        // undefined symbols left here are not referred by any linked section
+        // unless they are weak
+        let mut global_sigs: Vec<&SymbolSignature> = self
+            .global_symbols
+            .values()
+            .filter(|sig| is_output(&SymbolRef::Global(sig.name.clone()), &sig.symbol))
+            .collect();
+        global_sigs.sort_by_key(|sig| (sig.symbol.section_index, sig.symbol.value, &sig.name));
+        symbol_sigs.extend(global_sigs);
 
         for symbol_sig in symbol_sigs {
-            let mut symbol = symbol_sig.symbol.clone();
+            let mut symbol = symbol_sig.symbol;
             let symbol_name = symbol_sig.name.clone();
 
-            let symbol_section_index = symbol.section_index as usize;
-            symbol.value += self.section_offsets.get(&symbol_section_index).unwrap();
+            if let symbol::IndexType::Index(section_index) = symbol.get_index_type() {
+                symbol.value += self.section_offsets.get(&(section_index as usize)).unwrap();
+            }
             symbol.name = strtab.insert(symbol_name.clone()) as u32;
 
             symbols.push(symbol);
-            self.symbol_indices.insert(symbol_name, symbols.len() - 1);
+            if symbol.get_binding() != symbol::Binding::Local {
+                self.symbol_indices.insert(symbol_name, symbols.len() - 1);
+            }
         }
 
         // generate symtab
@@ -511,18 +1378,61 @@ impl Linker {
     fn finalize_elf(&mut self) {
         self.output_elf.update_header();
 
-        let addr_of_text = self.output_elf.get_section(".text").unwrap().header.addr;
-        let entrypoint = self.find_symbol("_start").unwrap_or(addr_of_text);
+        // like GNU ld, an undefined entry symbol falls back to the start of the
+        // first executable section with a warning
+        let entrypoint = match self.find_symbol(&self.config.entry) {
+            Some(addr) => addr,
+            None => {
+                let addr = self
+                    .output_elf
+                    .sections
+                    .iter()
+                    .map(|section| &section.header)
+                    .filter(|shdr| {
+                        section::Flags::Alloc.contained_in(shdr.flags)
+                            && section::Flags::Execinstr.contained_in(shdr.flags)
+                    })
+                    .map(|shdr| shdr.addr)
+                    .min()
+                    .unwrap_or(0);
+                eprintln!(
+                    "herja: warning: cannot find entry symbol '{}'; defaulting to {:#x}",
+                    self.config.entry, addr
+                );
+                addr
+            }
+        };
         self.output_elf.header.entrypoint = entrypoint;
     }
 
     fn find_symbol(&self, name: &str) -> Option<u64> {
-        let symbol_sig = self.global_symbols.get(name)?;
-        let symbol = symbol_sig.symbol;
+        self.symbol_addr(&SymbolRef::Global(name.to_string()))
+    }
 
-        let symbol_section_index = symbol.section_index as usize;
-        let symbol_offset = self.section_offsets.get(&symbol_section_index).unwrap();
+    fn symbol_addr(&self, symbol_ref: &SymbolRef) -> Option<u64> {
+        let symbol = self.symbol_sig(symbol_ref)?.symbol;
+        match symbol.get_index_type() {
+            symbol::IndexType::Index(section_index) => {
+                let symbol_offset = self.section_offsets.get(&(section_index as usize)).unwrap();
+                Some(symbol.value + symbol_offset)
+            }
+            symbol::IndexType::Abs => Some(symbol.value),
+            symbol::IndexType::Undef if symbol.get_binding() == symbol::Binding::Weak => Some(0),
+            _ => None,
+        }
+    }
 
-        Some(symbol.value + symbol_offset)
+    fn symbol_sig(&self, symbol_ref: &SymbolRef) -> Option<&SymbolSignature> {
+        match symbol_ref {
+            SymbolRef::Global(name) => self.global_symbols.get(name),
+            SymbolRef::Local(index) => self.local_symbols.get(*index),
+        }
+    }
+
+    fn symbol_sig_mut(&mut self, symbol_ref: &SymbolRef) -> Option<&mut SymbolSignature> {
+        match symbol_ref {
+            SymbolRef::Global(name) => self.global_symbols.get_mut(name),
+            SymbolRef::Local(index) => self.local_symbols.get_mut(*index),
+        }
     }
 }
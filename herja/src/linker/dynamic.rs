@@ -0,0 +1,159 @@
+use std::mem::size_of;
+
+use elfen::{
+    dynamic::{self, Dyn},
+    rel::{self, Rela},
+    section::{self, SectionData, SectionHeader},
+    segment::{self, ProgramHeader},
+    strtab::Strtab,
+    symbol,
+};
+
+use super::{Contribution, Linker, SymbolRef};
+
+impl Linker {
+    // adds .rela.dyn, .dynstr and .dynamic, which are filled in `fill_dynamic` once
+    // addresses are known
+    pub(super) fn gen_dynamic(&mut self) {
+        let num_relas = self.count_relative_relas();
+        if num_relas != 0 {
+            let mut header = SectionHeader::default();
+            header.set_type(section::Type::Rela);
+            header.set_flags(section::Flags::Alloc);
+            header.alignment = 8;
+            header.entry_size = size_of::<Rela>() as u64;
+            let data = SectionData::Rela(vec![Rela::default(); num_relas]);
+            self.add_generated_section(".rela.dyn", header, data);
+        }
+
+        let mut dyns = Vec::new();
+        if num_relas != 0 {
+            let relas_size = (num_relas * size_of::<Rela>()) as u64;
+            dyns.push(Dyn::new(dynamic::Tag::Rela, 0));
+            dyns.push(Dyn::new(dynamic::Tag::RelaSz, relas_size));
+            dyns.push(Dyn::new(dynamic::Tag::RelaEnt, size_of::<Rela>() as u64));
+            dyns.push(Dyn::new(dynamic::Tag::RelaCount, num_relas as u64));
+        }
+        dyns.push(Dyn::new(dynamic::Tag::Flags1, dynamic::FLAGS1_PIE));
+        dyns.push(Dyn::new(dynamic::Tag::Null, 0));
+
+        // no symbols are exported, but .dynamic refers to a string table
+        let mut header = SectionHeader::default();
+        header.set_type(section::Type::Strtab);
+        header.set_flags(section::Flags::Alloc);
+        header.alignment = 1;
+        let mut dynstr = Strtab::default();
+        dynstr.insert("".into());
+        let dynstr_index = self.output_elf.sections.len();
+        self.add_generated_section(".dynstr", header, SectionData::Strtab(dynstr));
+
+        let mut header = SectionHeader::default();
+        header.set_type(section::Type::Dynamic);
+        header.link = dynstr_index as u32;
+        header.set_flags(section::Flags::Alloc);
+        header.set_flags(section::Flags::Write);
+        header.alignment = 8;
+        header.entry_size = size_of::<Dyn>() as u64;
+        self.add_generated_section(".dynamic", header, SectionData::Dynamic(dyns));
+    }
+
+    // writes R_X86_64_RELATIVE relocations for absolute addresses in data and
+    // .got, which the loader adds the load address to
+    pub(super) fn fill_dynamic(&mut self) {
+        let mut relas = std::mem::take(&mut self.relative_relas);
+        if let Some(got_index) = self.output_elf.find_section(".got") {
+            let got_addr = self.section_offsets[&got_index];
+            for (i, symbol_ref) in self.got_symbols.iter().enumerate() {
+                if self.moves_with_base(symbol_ref) {
+                    let addr = self.symbol_addr(symbol_ref).unwrap();
+                    relas.push(Self::relative_rela(got_addr + i as u64 * 8, addr as i64));
+                }
+            }
+        }
+        relas.sort_by_key(|rela| rela.offset);
+
+        let relas_addr = match self.output_elf.find_section(".rela.dyn") {
+            Some(index) => {
+                let relas_addr = self.section_offsets[&index];
+                self.output_elf.sections[index].data = SectionData::Rela(relas);
+                relas_addr
+            }
+            None => return,
+        };
+
+        let dynamic = self.output_elf.get_section_mut(".dynamic").unwrap();
+        if let SectionData::Dynamic(dyns) = &mut dynamic.data {
+            for dyn_entry in dyns.iter_mut() {
+                if dyn_entry.get_tag() == dynamic::Tag::Rela {
+                    dyn_entry.val = relas_addr;
+                }
+            }
+        }
+    }
+
+    pub(super) fn relative_rela(addr: u64, value: i64) -> Rela {
+        let mut rela = Rela {
+            offset: addr,
+            addend: value,
+            ..Rela::default()
+        };
+        rela.set_info(0, rel::Type::Relative);
+        rela
+    }
+
+    // whether the address of the symbol depends on where the PIE is loaded
+    pub(super) fn moves_with_base(&self, symbol_ref: &SymbolRef) -> bool {
+        self.symbol_sig(symbol_ref).is_some_and(|symbol_sig| {
+            matches!(
+                symbol_sig.symbol.get_index_type(),
+                symbol::IndexType::Index(_)
+            )
+        })
+    }
+
+    pub(super) fn gen_dynamic_segment(shdr: &SectionHeader) -> ProgramHeader {
+        let mut phdr = ProgramHeader::default();
+        phdr.set_type(segment::Type::Dynamic);
+        phdr.set_flags(segment::Flags::R);
+        phdr.set_flags(segment::Flags::W);
+        phdr.offset = shdr.offset;
+        phdr.virt_addr = shdr.addr;
+        phdr.phys_addr = shdr.addr;
+        phdr.file_size = shdr.size;
+        phdr.memory_size = shdr.size;
+        phdr.alignment = shdr.alignment;
+        phdr
+    }
+
+    // a relocation for each 64-bit absolute address and .got entry
+    fn count_relative_relas(&self) -> usize {
+        let num_data_relas = self
+            .rela_map
+            .iter()
+            .filter(|(place, _)| place.elf_index == 0)
+            .flat_map(|(_, rela_indices)| rela_indices)
+            .map(|&rela_index| &self.relas[rela_index])
+            .filter(|rela_sig| {
+                rela_sig.rela.get_type() == rel::Type::Abs64
+                    && self.moves_with_base(&rela_sig.symbol)
+            })
+            .count();
+        let num_got_relas = self
+            .got_symbols
+            .iter()
+            .filter(|symbol_ref| self.moves_with_base(symbol_ref))
+            .count();
+        num_data_relas + num_got_relas
+    }
+
+    fn add_generated_section(&mut self, name: &str, header: SectionHeader, data: SectionData) {
+        self.contributions.push(Contribution {
+            section_index: self.output_elf.sections.len(),
+            name: name.to_string(),
+            input_index: None,
+            offset: 0,
+            size: data.len() as u64,
+        });
+        self.output_elf.add_section(name, header, data);
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashSet;
+
+use elfen::{section, symbol};
+
+use super::{Linker, SectionPlace, SymbolRef};
+
+// input section dropped by `--gc-sections`
+#[derive(Debug)]
+pub(super) struct DiscardedSection {
+    pub(super) input_index: usize,
+    pub(super) section_index: usize,
+    pub(super) name: String,
+    pub(super) size: u64,
+}
+
+impl Linker {
+    // discards allocated input sections which are not reachable from the entry
+    // symbol through relocations
+    pub(super) fn gc_sections(&mut self) {
+        // nothing is known to be used without the entry
+        let entry = SymbolRef::Global(self.config.entry.clone());
+        let entry_place = match self.defining_place(&entry) {
+            Some(place) => place,
+            None => return,
+        };
+
+        let mut live: HashSet<SectionPlace> = HashSet::new();
+        let mut worklist = self.gc_roots();
+        worklist.push(entry_place);
+        while let Some(place) = worklist.pop() {
+            if !live.insert(place.clone()) {
+                continue;
+            }
+
+            let rela_indices = match self.rela_map.get(&place) {
+                Some(rela_indices) => rela_indices,
+                None => continue,
+            };
+            for rela_index in rela_indices {
+                let symbol_ref = &self.relas[*rela_index].symbol;
+                if let Some(target) = self.defining_place(symbol_ref) {
+                    worklist.push(target);
+                }
+            }
+        }
+
+        let mut discarded_sections = Vec::new();
+        for (elf_index, elf) in self.input_elfs.iter().enumerate() {
+            for (section_index, section) in elf.sections.iter().enumerate() {
+                let place = SectionPlace {
+                    elf_index: elf_index + 1,
+                    section_index,
+                };
+                // empty sections are not worth reporting
+                if section::Flags::Alloc.contained_in(section.header.flags)
+                    && section.header.size != 0
+                    && !live.contains(&place)
+                {
+                    discarded_sections.push(DiscardedSection {
+                        input_index: elf_index,
+                        section_index,
+                        name: section.name.clone(),
+                        size: section.header.size,
+                    });
+                }
+            }
+        }
+
+        for discarded in discarded_sections {
+            if self.config.print_gc_sections {
+                eprintln!(
+                    "herja: removing unused section '{}' in file '{}'",
+                    discarded.name, self.input_names[discarded.input_index]
+                );
+            }
+            self.discard(SectionPlace {
+                elf_index: discarded.input_index + 1,
+                section_index: discarded.section_index,
+            });
+            self.discarded_sections.push(discarded);
+        }
+    }
+
+    pub(super) fn is_discarded(&self, elf_index: usize, section_index: usize) -> bool {
+        self.discarded_sections.iter().any(|discarded| {
+            discarded.input_index == elf_index && discarded.section_index == section_index
+        })
+    }
+
+    // sections whose use the linker cannot know, such as .init_array
+    fn gc_roots(&self) -> Vec<SectionPlace> {
+        let mut roots = Vec::new();
+        for (elf_index, elf) in self.input_elfs.iter().enumerate() {
+            for (section_index, section) in elf.sections.iter().enumerate() {
+                if section::Flags::Alloc.contained_in(section.header.flags)
+                    && Self::standard_section_name(&section.name).is_none()
+                {
+                    roots.push(SectionPlace {
+                        elf_index: elf_index + 1,
+                        section_index,
+                    });
+                }
+            }
+        }
+        roots
+    }
+
+    fn defining_place(&self, symbol_ref: &SymbolRef) -> Option<SectionPlace> {
+        let symbol_sig = self.symbol_sig(symbol_ref)?;
+        match symbol_sig.symbol.get_index_type() {
+            symbol::IndexType::Index(section_index) => Some(SectionPlace {
+                elf_index: symbol_sig.input_index + 1,
+                section_index: section_index as usize,
+            }),
+            _ => None,
+        }
+    }
+
+    // forgets the symbols and relocations in the section
+    fn discard(&mut self, place: SectionPlace) {
+        if let Some(symbol_refs) = self.symbol_map.remove(&place) {
+            for symbol_ref in symbol_refs {
+                if let SymbolRef::Global(name) = symbol_ref {
+                    self.global_symbols.remove(&name);
+                }
+            }
+        }
+        self.rela_map.remove(&place);
+    }
+}
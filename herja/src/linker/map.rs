@@ -0,0 +1,169 @@
+use std::fmt::Write;
+
+use elfen::{rel, section};
+
+use super::{Contribution, Linker, SymbolSignature};
+
+impl Linker {
+    // generates a map in the style of `ld -Map`
+    pub(super) fn gen_map(&self) -> String {
+        let mut map = String::new();
+        self.write_archive_members(&mut map);
+        self.write_discarded_sections(&mut map);
+        self.write_memory_map(&mut map);
+        self.write_relocations(&mut map);
+        map
+    }
+
+    fn write_archive_members(&self, map: &mut String) {
+        if self.archive_members.is_empty() {
+            return;
+        }
+
+        writeln!(
+            map,
+            "Archive member included to satisfy reference by file (symbol)\n"
+        )
+        .unwrap();
+        for member in &self.archive_members {
+            writeln!(map, "{}", member.name).unwrap();
+            writeln!(map, "{:30}{} ({})", "", member.referrer, member.symbol).unwrap();
+        }
+        writeln!(map).unwrap();
+    }
+
+    fn write_discarded_sections(&self, map: &mut String) {
+        if self.discarded_sections.is_empty() {
+            return;
+        }
+
+        writeln!(map, "Discarded input sections\n").unwrap();
+        for discarded in &self.discarded_sections {
+            writeln!(
+                map,
+                " {:<15}{:#018x} {:>10} {}",
+                discarded.name,
+                0,
+                format!("{:#x}", discarded.size),
+                self.input_names[discarded.input_index]
+            )
+            .unwrap();
+        }
+        writeln!(map).unwrap();
+    }
+
+    fn write_memory_map(&self, map: &mut String) {
+        writeln!(map, "Memory map\n").unwrap();
+        let mut section_indices: Vec<usize> = (1..self.output_elf.sections.len())
+            .filter(|&index| {
+                let flags = self.output_elf.sections[index].header.flags;
+                section::Flags::Alloc.contained_in(flags)
+            })
+            .collect();
+        section_indices.sort_by_key(|&index| self.output_elf.sections[index].header.addr);
+
+        for section_index in section_indices {
+            let section = &self.output_elf.sections[section_index];
+            let header = &section.header;
+
+            writeln!(
+                map,
+                "{:<16}{:#018x} {:>10}",
+                section.name,
+                header.addr,
+                format!("{:#x}", header.size)
+            )
+            .unwrap();
+
+            for contribution in &self.contributions {
+                if contribution.section_index == section_index {
+                    self.write_contribution(map, header.addr, contribution);
+                }
+            }
+            writeln!(map).unwrap();
+        }
+    }
+
+    fn write_contribution(&self, map: &mut String, section_addr: u64, contribution: &Contribution) {
+        let input_name = match contribution.input_index {
+            Some(index) => self.input_names[index].as_str(),
+            None => "*(linker generated)",
+        };
+        writeln!(
+            map,
+            " {:<15}{:#018x} {:>10} {}",
+            contribution.name,
+            section_addr + contribution.offset,
+            format!("{:#x}", contribution.size),
+            input_name
+        )
+        .unwrap();
+
+        let mut symbols: Vec<(u64, &str)> = self
+            .global_symbols
+            .values()
+            .filter(|sig| sig.symbol.section_index as usize == contribution.section_index)
+            .filter(|sig| self.owns_symbol(contribution, sig))
+            .map(|sig| (section_addr + sig.symbol.value, sig.name.as_str()))
+            .collect();
+        symbols.sort();
+
+        for (addr, name) in symbols {
+            writeln!(map, "{:16}{:#018x}{:16}{}", "", addr, "", name).unwrap();
+        }
+    }
+
+    // whether the symbol is in the contribution, which is the last one
+    // starting at or before the symbol among those from the same input
+    fn owns_symbol(&self, contribution: &Contribution, sig: &SymbolSignature) -> bool {
+        let input_index = match contribution.input_index {
+            Some(input_index) => input_index,
+            None => return true,
+        };
+        let value = sig.symbol.value;
+        input_index == sig.input_index
+            && contribution.offset <= value
+            && !self.contributions.iter().any(|other| {
+                other.section_index == contribution.section_index
+                    && other.input_index == contribution.input_index
+                    && contribution.offset < other.offset
+                    && other.offset <= value
+            })
+    }
+
+    fn write_relocations(&self, map: &mut String) {
+        if self.applied_relas.is_empty() {
+            return;
+        }
+
+        writeln!(map, "Relocations applied\n").unwrap();
+
+        let mut applied_relas: Vec<_> = self.applied_relas.iter().collect();
+        applied_relas.sort_by_key(|applied| applied.addr);
+        for applied in applied_relas {
+            let rela_sig = &self.relas[applied.rela_index];
+            let typ = rela_sig.rela.get_type();
+
+            let target = match rela_sig.rela.addend {
+                0 => rela_sig.symbol_name.clone(),
+                addend => format!("{}{:+}", rela_sig.symbol_name, addend),
+            };
+            // the value as written to the field
+            let value = match typ {
+                rel::Type::Abs64 => applied.value as u64,
+                _ => applied.value as u32 as u64,
+            };
+
+            writeln!(
+                map,
+                " {:#018x} {:<18} {:<24} {:#010x} {}",
+                applied.addr,
+                typ.to_string(),
+                target,
+                value,
+                rela_sig.location
+            )
+            .unwrap();
+        }
+    }
+}
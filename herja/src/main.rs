@@ -1,20 +1,105 @@
-use std::env;
+use std::{env, fs};
 
-use herja::linker;
+use herja::{
+    config::{LinkerConfig, OutputFormat},
+    linker, script,
+};
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 3 {
-        println!("usage: herja <input_file>... <output_file>");
-        std::process::exit(0);
+    let mut files = Vec::new();
+    let mut config = LinkerConfig::default();
+    let mut entry = None;
+    let mut image_base = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--map" => config.map_file = Some(next_arg(&mut args)),
+            "--gc-sections" => config.gc_sections = true,
+            "--print-gc-sections" => config.print_gc_sections = true,
+            "-e" | "--entry" => entry = Some(next_arg(&mut args)),
+            "--oformat" => {
+                config.oformat = match next_arg(&mut args).as_str() {
+                    "elf64-x86-64" => OutputFormat::Elf,
+                    "binary" => OutputFormat::Binary,
+                    format => exit_with_error(&format!("unknown output format '{}'", format)),
+                }
+            }
+            "--image-base" => {
+                let value = next_arg(&mut args);
+                image_base = Some(parse_number(&value));
+            }
+            "-pie" | "--pie" => config.pie = true,
+            "--section-start" => {
+                // e.g. `.text=0x7c00`
+                let value = next_arg(&mut args);
+                let (name, addr) = value.split_once('=').unwrap_or_else(|| show_usage());
+                config
+                    .section_starts
+                    .insert(name.to_string(), parse_number(addr));
+            }
+            "-T" | "--script" => {
+                let path = next_arg(&mut args);
+                let source = fs::read_to_string(&path).unwrap_or_else(|err| {
+                    exit_with_error(&format!("{}: {}", path, err));
+                });
+                match script::parse(&source) {
+                    Ok(script) => config.script = Some(script),
+                    Err(message) => exit_with_error(&format!("{}: {}", path, message)),
+                }
+            }
+            _ => files.push(arg),
+        }
+    }
+    if files.len() < 2 {
+        show_usage();
     }
 
-    let args_num = args.len() - 1;
-    let input_files = args[1..args_num].to_vec();
-    let output_file = args[args_num].clone();
+    // `-e` takes priority over `ENTRY` in the script
+    let script_entry = config
+        .script
+        .as_ref()
+        .and_then(|script| script.entry.clone());
+    if let Some(entry) = entry.or(script_entry) {
+        config.entry = entry;
+    }
 
-    if let Err(err) = linker::link_to_files(input_files, output_file) {
-        println!("Failed to link: {}", err);
+    // a PIE is linked at 0 and moved by the loader
+    if let Some(image_base) = image_base {
+        config.image_base = image_base;
+    } else if config.pie {
+        config.image_base = 0;
+    }
+
+    let output_file = files.pop().unwrap();
+    let input_files = files;
+
+    if let Err(errs) = linker::link_to_files(input_files, output_file, &config) {
+        for err in errs {
+            eprintln!("herja: {}", err);
+        }
         std::process::exit(1);
     }
 }
+
+fn next_arg(args: &mut impl Iterator<Item = String>) -> String {
+    args.next().unwrap_or_else(|| show_usage())
+}
+
+fn parse_number(s: &str) -> u64 {
+    script::parse_number(s).unwrap_or_else(|| exit_with_error(&format!("invalid number '{}'", s)))
+}
+
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("herja: {}", message);
+    std::process::exit(1);
+}
+
+fn show_usage() -> ! {
+    println!(
+        "usage: herja [--map <map_file>] [-e <symbol>] [--image-base <addr>] \
+         [--section-start <section>=<addr>] [-T <script>] [--oformat elf64-x86-64|binary] \
+         [--gc-sections] [--print-gc-sections] [-pie] <input_file>... <output_file>"
+    );
+    std::process::exit(0);
+}
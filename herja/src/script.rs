@@ -0,0 +1,194 @@
+// a small subset of the GNU ld linker script:
+//
+//   ENTRY(_start)
+//   SECTIONS {
+//       . = 0x7c00;
+//       .text : { *(.text .text.*) }
+//       .data : { *(.data) *(.rodata) }
+//   }
+
+#[derive(Debug, Clone, Default)]
+pub struct Script {
+    pub entry: Option<String>,
+    pub sections: Vec<OutputSection>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OutputSection {
+    pub name: String,
+    // set by `. = ADDR;` right before the section
+    pub addr: Option<u64>,
+    // names of input sections, which may end with `*`
+    pub patterns: Vec<String>,
+}
+
+impl OutputSection {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            addr: None,
+            patterns: vec![name.to_string()],
+        }
+    }
+
+    pub fn matches(&self, section_name: &str) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => section_name.starts_with(prefix),
+                None => section_name == pattern,
+            })
+    }
+}
+
+// parses numbers such as `4096` and `0x7c00`
+pub fn parse_number(s: &str) -> Option<u64> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+pub fn parse(source: &str) -> Result<Script, String> {
+    Parser::new(tokenize(source)?).parse()
+}
+
+fn tokenize(source: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '/' {
+            chars.next();
+            if chars.next() != Some('*') {
+                return Err("unexpected '/'".into());
+            }
+            // skip until `*/`
+            let mut prev = ' ';
+            loop {
+                match chars.next() {
+                    Some('/') if prev == '*' => break,
+                    Some(c) => prev = c,
+                    None => return Err("unterminated comment".into()),
+                }
+            }
+        } else if "{}():;=".contains(c) {
+            tokens.push(c.to_string());
+            chars.next();
+        } else if is_name_char(c) {
+            let mut name = String::new();
+            while let Some(&c) = chars.peek() {
+                if !is_name_char(c) {
+                    break;
+                }
+                name.push(c);
+                chars.next();
+            }
+            tokens.push(name);
+        } else {
+            return Err(format!("unexpected '{}'", c));
+        }
+    }
+    Ok(tokens)
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "._*$-".contains(c)
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(tokens: Vec<String>) -> Self {
+        Self { tokens, pos: 0 }
+    }
+
+    fn parse(mut self) -> Result<Script, String> {
+        let mut script = Script::default();
+        while let Some(token) = self.next() {
+            match token.as_str() {
+                "ENTRY" => {
+                    self.expect("(")?;
+                    script.entry = Some(self.expect_token()?);
+                    self.expect(")")?;
+                }
+                "SECTIONS" => self.parse_sections(&mut script)?,
+                _ => return Err(format!("unexpected '{}'", token)),
+            }
+        }
+        Ok(script)
+    }
+
+    fn parse_sections(&mut self, script: &mut Script) -> Result<(), String> {
+        self.expect("{")?;
+        let mut addr = None;
+        loop {
+            let name = self.expect_token()?;
+            match name.as_str() {
+                "}" => return Ok(()),
+                "." => {
+                    self.expect("=")?;
+                    let value = self.expect_token()?;
+                    let value = parse_number(&value)
+                        .ok_or_else(|| format!("invalid address '{}'", value))?;
+                    addr = Some(value);
+                    self.expect(";")?;
+                }
+                _ => {
+                    self.expect(":")?;
+                    let patterns = self.parse_input_sections()?;
+                    script.sections.push(OutputSection {
+                        name,
+                        addr: addr.take(),
+                        patterns,
+                    });
+                }
+            }
+        }
+    }
+
+    // e.g. `{ *(.text) *(.text.*) }`
+    fn parse_input_sections(&mut self) -> Result<Vec<String>, String> {
+        self.expect("{")?;
+        let mut patterns = Vec::new();
+        loop {
+            match self.expect_token()?.as_str() {
+                "}" => return Ok(patterns),
+                "*" => {
+                    self.expect("(")?;
+                    loop {
+                        let pattern = self.expect_token()?;
+                        if pattern == ")" {
+                            break;
+                        }
+                        patterns.push(pattern);
+                    }
+                }
+                token => return Err(format!("unexpected '{}'", token)),
+            }
+        }
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect_token(&mut self) -> Result<String, String> {
+        self.next()
+            .ok_or_else(|| "unexpected end of script".to_string())
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!("expected '{}', found '{}'", expected, token)),
+            None => Err(format!("expected '{}', found end of script", expected)),
+        }
+    }
+}
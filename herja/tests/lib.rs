@@ -1,7 +1,21 @@
 extern crate herja;
 
-use elfen::elf::Elf;
-use herja::linker;
+use eir::emulator::{exit::ExitReason, io::CapturedIo, Emulator};
+use elfen::{
+    dynamic::{self, Dyn},
+    elf::Elf,
+    header, rel, segment,
+    symbol::{self, Symbol},
+};
+use herja::{
+    archive::Archive,
+    binary,
+    config::{LinkerConfig, OutputFormat},
+    error::LinkError,
+    linker, script,
+};
+use rota::{assembler, frontend::lexer::SourceFile};
+use std::convert::TryInto;
 use std::fs;
 
 #[test]
@@ -16,3 +30,661 @@ fn link() {
     let expected_bytes = fs::read("tests/testcases/file").unwrap();
     assert_eq!(actual_bytes, expected_bytes);
 }
+
+#[test]
+fn data_relocations() {
+    let input_elfs = vec![Elf::read_from_file("tests/testcases/data_rela.o")];
+    let output_elf = linker::link(input_elfs).unwrap();
+
+    let table = find_symbol(&output_elf, "table");
+    let table_ptr = find_symbol(&output_elf, "table_ptr");
+    let values = find_symbol(&output_elf, "values");
+
+    let data = read_at(&output_elf, table, 28);
+    assert_eq!(u64_at(&data, 0), values);
+    assert_eq!(u64_at(&data, 8), values + 8);
+    assert_eq!(u64_at(&data, 16), table);
+    assert_eq!(u32_at(&data, 24) as u64, values + 4);
+
+    let start = find_symbol(&output_elf, "_start");
+    let text = read_at(&output_elf, start, 20);
+    let got_entry = (start as i64 + 7 + u32_at(&text, 3) as i32 as i64) as u64;
+    assert_eq!(u64_at(&read_at(&output_elf, got_entry, 8), 0), table_ptr);
+    assert_eq!(u32_at(&text, 8) as u64, values);
+    assert_eq!(u32_at(&text, 16) as u64, values);
+}
+
+#[test]
+fn bss() {
+    let output_elf = link_files(vec![
+        "tests/testcases/bss1.o".to_string(),
+        "tests/testcases/bss2.o".to_string(),
+    ])
+    .unwrap();
+
+    let bss = output_elf.get_section(".bss").unwrap();
+    assert!(bss.header.is_nobits());
+    assert_eq!(bss.header.size, 0x10008);
+
+    let buf = find_symbol(&output_elf, "buf");
+    let counter = find_symbol(&output_elf, "counter");
+    assert_eq!(buf, bss.header.addr);
+    assert_eq!(counter, buf + 0x10000);
+
+    // .bss is at the tail of the RW segment and is not in the file
+    let segment = output_elf
+        .segments
+        .iter()
+        .find(|segment| segment.virt_addr <= buf && buf < segment.virt_addr + segment.memory_size)
+        .unwrap();
+    assert!(segment.get_type() == segment::Type::Load);
+    assert!(segment.virt_addr + segment.file_size <= buf);
+    assert_eq!(segment.virt_addr + segment.memory_size, buf + 0x10008);
+
+    let flag = find_symbol(&output_elf, "flag");
+    assert_eq!(flag, segment.virt_addr);
+    assert!(output_elf.to_bytes().len() < 0x10000);
+}
+
+#[test]
+fn empty_section() {
+    let output_elf = link_files(vec!["tests/testcases/empty_section.o".to_string()]).unwrap();
+
+    // .data has nothing in it but the symbols, which are still at its address
+    let data = output_elf.get_section(".data").unwrap();
+    assert_eq!(data.header.size, 0);
+    let data_end = find_symbol(&output_elf, "data_end");
+    assert_eq!(data_end, data.header.addr);
+
+    let start = find_symbol(&output_elf, "_start");
+    let text = read_at(&output_elf, start, 14);
+    let rax = (start as i64 + 7 + u32_at(&text, 3) as i32 as i64) as u64;
+    let rcx = (start as i64 + 14 + u32_at(&text, 10) as i32 as i64) as u64;
+    assert_eq!(rax, data_end);
+    assert_eq!(rcx, data_end);
+}
+
+#[test]
+fn common_symbols() {
+    let output_elf = link_files(vec![
+        "tests/testcases/common1.o".to_string(),
+        "tests/testcases/common2.o".to_string(),
+    ])
+    .unwrap();
+
+    let bss = output_elf.get_section(".bss").unwrap();
+    assert!(bss.header.is_nobits());
+    let buf = find_symbol(&output_elf, "buf");
+    let shared = find_symbol(&output_elf, "shared");
+    let end = bss.header.addr + bss.header.size;
+    assert!(bss.header.addr <= buf && buf + 64 <= end);
+    // the larger of the two `shared` is kept, with its alignment
+    assert_eq!(shared % 32, 0);
+    assert!(bss.header.addr <= shared && shared + 16 <= end);
+    assert_eq!(find_symbols(&output_elf, "shared")[0].size, 16);
+
+    // `lea rax, [rip + buf]` and `lea rcx, [rip + shared]` refer to them
+    let start = find_symbol(&output_elf, "_start");
+    let text = read_at(&output_elf, start, 14);
+    assert_eq!(start + 7 + u32_at(&text, 3) as u64, buf);
+    assert_eq!(start + 14 + u32_at(&text, 10) as u64, shared);
+}
+
+#[test]
+fn segments() {
+    let output_elf = link_files(vec![
+        "tests/testcases/bss1.o".to_string(),
+        "tests/testcases/bss2.o".to_string(),
+    ])
+    .unwrap();
+
+    let section_names: Vec<&str> = output_elf
+        .sections
+        .iter()
+        .filter(|section| section.header.addr != 0)
+        .map(|section| section.name.as_str())
+        .collect();
+    assert_eq!(section_names, vec![".text", ".rodata", ".data", ".bss"]);
+
+    let segments = &output_elf.segments;
+    assert_eq!(segments.len(), 4);
+    let flags: Vec<u32> = segments.iter().map(|segment| segment.flags).collect();
+    // RX, R, RW and then the non-executable stack
+    assert_eq!(flags, vec![0b101, 0b100, 0b110, 0b110]);
+    assert!(segments[3].get_type() == segment::Type::GnuStack);
+
+    for segment in &segments[..3] {
+        assert!(segment.get_type() == segment::Type::Load);
+        assert_eq!(segment.offset % 0x1000, segment.virt_addr % 0x1000);
+    }
+    // segments with different permissions never share a page
+    for pair in segments[..3].windows(2) {
+        let end = pair[0].virt_addr + pair[0].memory_size;
+        assert!(end <= pair[1].virt_addr & !0xfff);
+    }
+}
+
+#[test]
+fn linker_script() {
+    let source = "
+        /* a flat image */
+        ENTRY(message)
+        SECTIONS {
+            . = 0x7c00;
+            .text : { *(.text) *(.rodata) }
+            . = 0x8000;
+            .data : { *(.data) *(.bss) }
+        }
+    ";
+    let script = script::parse(source).unwrap();
+    let config = LinkerConfig {
+        entry: script.entry.clone().unwrap(),
+        script: Some(script),
+        ..LinkerConfig::default()
+    };
+    let output_elf = link_files_with_config(
+        vec!["tests/testcases/bss1.o", "tests/testcases/bss2.o"],
+        &config,
+    );
+
+    let section_names: Vec<&str> = output_elf
+        .sections
+        .iter()
+        .filter(|section| section.header.addr != 0)
+        .map(|section| section.name.as_str())
+        .collect();
+    assert_eq!(section_names, vec![".text", ".data"]);
+
+    assert_eq!(find_symbol(&output_elf, "_start"), 0x7c00);
+    // .rodata follows .text, which is 15 bytes
+    assert_eq!(find_symbol(&output_elf, "message"), 0x7c0f);
+    assert_eq!(output_elf.header.entrypoint, 0x7c0f);
+    assert_eq!(find_symbol(&output_elf, "flag"), 0x8000);
+    // .bss is filled with zeros in .data
+    assert_eq!(find_symbol(&output_elf, "buf"), 0x8001);
+    assert_eq!(read_at(&output_elf, 0x8001, 4), vec![0; 4]);
+}
+
+#[test]
+fn undefined_entry() {
+    // there is no .text, and the entry falls back to the executable .code
+    let script = script::parse("SECTIONS { .code : { *(.text) } }").unwrap();
+    let config = LinkerConfig {
+        entry: "missing".to_string(),
+        script: Some(script),
+        ..LinkerConfig::default()
+    };
+    let output_elf = link_files_with_config(
+        vec!["tests/testcases/bss1.o", "tests/testcases/bss2.o"],
+        &config,
+    );
+    assert!(output_elf.get_section(".text").is_none());
+    let code = output_elf.get_section(".code").unwrap();
+    assert_eq!(output_elf.header.entrypoint, code.header.addr);
+}
+
+#[test]
+fn section_start() {
+    let mut config = LinkerConfig {
+        entry: "message".to_string(),
+        image_base: 0x10000000,
+        ..LinkerConfig::default()
+    };
+    config
+        .section_starts
+        .insert(".data".to_string(), 0x20000000);
+    let output_elf = link_files_with_config(
+        vec!["tests/testcases/bss1.o", "tests/testcases/bss2.o"],
+        &config,
+    );
+
+    let text = output_elf.get_section(".text").unwrap();
+    assert_eq!(text.header.addr & !0xfff, 0x10000000);
+    assert_eq!(find_symbol(&output_elf, "flag"), 0x20000000);
+    assert_eq!(
+        output_elf.header.entrypoint,
+        find_symbol(&output_elf, "message")
+    );
+    for segment in &output_elf.segments {
+        assert_eq!(segment.offset % 0x1000, segment.virt_addr % 0x1000);
+    }
+}
+
+#[test]
+fn binary_output() {
+    let config = LinkerConfig {
+        image_base: 0x7c00,
+        oformat: OutputFormat::Binary,
+        ..LinkerConfig::default()
+    };
+    let output_elf = link_files_with_config(
+        vec!["tests/testcases/bss1.o", "tests/testcases/bss2.o"],
+        &config,
+    );
+
+    // sections are packed from the origin
+    assert_eq!(find_symbol(&output_elf, "_start"), 0x7c00);
+    assert_eq!(find_symbol(&output_elf, "message"), 0x7c0f);
+    assert_eq!(find_symbol(&output_elf, "flag"), 0x7c11);
+
+    // .text, .rodata and .data without the trailing .bss
+    let image = binary::from_elf(&output_elf);
+    assert_eq!(image.len(), 0x12);
+    assert_eq!(&image[0x0f..], b"hi\x01");
+    // `movq $buf, %rax` refers to .bss after the image, aligned to 8 bytes
+    let buf = find_symbol(&output_elf, "buf");
+    assert_eq!(buf, 0x7c18);
+    assert_eq!(u32_at(&image, 3) as u64, buf);
+}
+
+#[test]
+fn gc_sections() {
+    let objects = vec![(
+        "gc.o".to_string(),
+        Elf::read_from_file("tests/testcases/gc.o"),
+    )];
+    let config = LinkerConfig {
+        gc_sections: true,
+        ..LinkerConfig::default()
+    };
+    let (output_elf, map) = linker::link_with_map(objects, Vec::new(), &config).unwrap();
+
+    // `.text.*` and `.data.*` are merged into `.text` and `.data`
+    assert!(output_elf.get_section(".text.used").is_none());
+    assert_eq!(output_elf.get_section(".text").unwrap().header.size, 0xe);
+    assert_eq!(output_elf.get_section(".data").unwrap().header.size, 8);
+    let mut names = symbol_names(&output_elf);
+    names.sort();
+    assert_eq!(names, vec!["_start", "used", "value"]);
+
+    let expected_lines = vec![
+        "Discarded input sections",
+        " .text.unused   0x0000000000000000        0xd gc.o",
+        " .data.dead     0x0000000000000000        0x8 gc.o",
+    ];
+    for line in expected_lines {
+        assert!(map.lines().any(|l| l == line), "missing line: {}", line);
+    }
+
+    // the unused section refers to an undefined symbol
+    let errs = link_files(vec!["tests/testcases/gc.o".to_string()])
+        .err()
+        .unwrap();
+    assert_eq!(
+        errs[0].to_string(),
+        "tests/testcases/gc.o:(.text.unused+0x1): undefined reference to 'missing'"
+    );
+}
+
+#[test]
+fn local_and_weak_symbols() {
+    let input_files = vec![
+        "tests/testcases/local1.o".to_string(),
+        "tests/testcases/local2.o".to_string(),
+    ];
+    let output_elf = link_files(input_files).unwrap();
+
+    // each file has its own `helper`, which come first in .symtab
+    let helpers = find_symbols(&output_elf, "helper");
+    assert_eq!(helpers.len(), 2);
+    for helper in &helpers {
+        assert!(helper.get_binding() == symbol::Binding::Local);
+        assert!(helper.get_type() == symbol::Type::Func);
+    }
+    assert_eq!(helpers[0].size, 6);
+    assert_eq!(helpers[1].size, 1);
+    let symtab = output_elf.get_section(".symtab").unwrap();
+    assert_eq!(symtab.header.info, 4);
+
+    // the weak `weak_fn` in local1.o is overridden by local2.o
+    let weak_fns = find_symbols(&output_elf, "weak_fn");
+    assert_eq!(weak_fns.len(), 1);
+    assert!(weak_fns[0].get_binding() == symbol::Binding::Global);
+    assert_eq!(weak_fns[0].size, 6);
+    assert_eq!(weak_fns[0].value, helpers[1].value - 6);
+
+    let start = find_symbol(&output_elf, "_start");
+    let code = read_at(&output_elf, start, 0x19);
+    let call_target = (start + 0xa).wrapping_add(u32_at(&code, 6) as i32 as u64);
+    assert_eq!(call_target, weak_fns[0].value);
+    // `movq $table, %rcx` refers to the local `table` through `.data`
+    let table = find_symbols(&output_elf, "table")[0];
+    assert!(table.get_type() == symbol::Type::Object);
+    assert_eq!(u32_at(&code, 0xd) as u64, table.value);
+    assert_eq!(read_at(&output_elf, table.value, 1), vec![7]);
+    // the undefined weak `weak_ref` is 0
+    assert_eq!(u32_at(&code, 0x14), 0);
+    let weak_ref = find_symbols(&output_elf, "weak_ref")[0];
+    assert!(weak_ref.get_binding() == symbol::Binding::Weak);
+    assert!(weak_ref.get_index_type() == symbol::IndexType::Undef);
+
+    // the weak definition is used when nothing overrides it
+    let output_elf = link_files(vec!["tests/testcases/local1.o".to_string()]).unwrap();
+    let weak_fn = find_symbols(&output_elf, "weak_fn")[0];
+    assert!(weak_fn.get_binding() == symbol::Binding::Weak);
+    assert_eq!(weak_fn.value, find_symbol(&output_elf, "_start") + 0x1f);
+}
+
+#[test]
+fn pie() {
+    let config = LinkerConfig {
+        image_base: 0,
+        pie: true,
+        ..LinkerConfig::default()
+    };
+    let output_elf = link_files_with_config(vec!["tests/testcases/pie.o"], &config);
+    assert!(output_elf.header.get_filetype() == header::Type::Dyn);
+
+    let dynamic = output_elf.get_section(".dynamic").unwrap();
+    let dynamic_segment = output_elf
+        .segments
+        .iter()
+        .find(|segment| segment.get_type() == segment::Type::Dynamic)
+        .unwrap();
+    assert_eq!(dynamic_segment.virt_addr, dynamic.header.addr);
+    assert_eq!(dynamic_segment.file_size, dynamic.header.size);
+
+    // `.quad message` and the .got entry for `counter` are moved by the loader
+    let rela_dyn = output_elf.get_section(".rela.dyn").unwrap();
+    let relas: Vec<(rel::Type, u64, i64)> = rela_dyn
+        .data
+        .as_rela()
+        .unwrap()
+        .iter()
+        .map(|rela| (rela.get_type(), rela.offset, rela.addend))
+        .collect();
+    let message = output_elf.get_section(".rodata").unwrap().header.addr;
+    let got = output_elf.get_section(".got").unwrap().header.addr;
+    let counter = find_symbol(&output_elf, "counter");
+    assert_eq!(
+        relas,
+        vec![
+            (
+                rel::Type::Relative,
+                find_symbol(&output_elf, "pointer"),
+                message as i64
+            ),
+            (rel::Type::Relative, got, counter as i64),
+        ]
+    );
+
+    let dyns = dynamic.data.as_dynamic().unwrap();
+    assert!(dyns.contains(&Dyn::new(dynamic::Tag::Rela, rela_dyn.header.addr)));
+    assert!(dyns.contains(&Dyn::new(dynamic::Tag::RelaSz, 48)));
+    assert!(dyns.contains(&Dyn::new(dynamic::Tag::Flags1, dynamic::FLAGS1_PIE)));
+    assert_eq!(dyns.last(), Some(&Dyn::new(dynamic::Tag::Null, 0)));
+
+    // 32-bit absolute addresses can not be relocated
+    let objects = vec![
+        "tests/testcases/bss1.o".to_string(),
+        "tests/testcases/bss2.o".to_string(),
+    ]
+    .into_iter()
+    .map(|path| {
+        let elf = Elf::read_from_file(&path);
+        (path, elf)
+    })
+    .collect();
+    let errs = linker::link_with_map(objects, Vec::new(), &config)
+        .err()
+        .unwrap();
+    assert_eq!(
+        errs[0].to_string(),
+        "tests/testcases/bss1.o:(.text+0x3): relocation R_X86_64_32S against 'buf' \
+         can not be used when making a PIE"
+    );
+}
+
+#[test]
+fn invalid_linker_script() {
+    let err = script::parse("SECTIONS { .text { *(.text) } }")
+        .err()
+        .unwrap();
+    assert_eq!(err, "expected ':', found '{'");
+}
+
+#[test]
+fn undefined_symbols() {
+    let input_files = vec!["tests/testcases/undefined.o".to_string()];
+    let errs = link_files(input_files).err().unwrap();
+    let messages: Vec<String> = errs.iter().map(|err| err.to_string()).collect();
+
+    assert_eq!(
+        messages,
+        vec![
+            "tests/testcases/undefined.o:(.text+0x1): undefined reference to 'foo'",
+            "tests/testcases/undefined.o:(.text+0x8): undefined reference to 'bar'",
+            "tests/testcases/undefined.o:(.text+0xd): undefined reference to 'foo'",
+        ]
+    );
+}
+
+#[test]
+fn unsupported_relocation() {
+    let input_files = vec!["tests/testcases/unsupported_rela.o".to_string()];
+    let errs = link_files(input_files).err().unwrap();
+    assert_eq!(
+        errs[0].to_string(),
+        "tests/testcases/unsupported_rela.o:(.data+0x0): unsupported relocation type 12 \
+         against '_start'"
+    );
+}
+
+#[test]
+fn io_errors() {
+    let config = LinkerConfig::default();
+    let errs = linker::link_to_files(
+        vec!["tests/testcases/missing.o".to_string()],
+        "tests/testcases/missing".to_string(),
+        &config,
+    )
+    .err()
+    .unwrap();
+    assert!(
+        matches!(&errs[..], [LinkError::Io { path, .. }] if path == "tests/testcases/missing.o")
+    );
+
+    // the output can not be written into a directory which does not exist
+    let errs = linker::link_to_files(
+        vec![
+            "tests/testcases/bss1.o".to_string(),
+            "tests/testcases/bss2.o".to_string(),
+        ],
+        "tests/testcases/missing/file".to_string(),
+        &config,
+    )
+    .err()
+    .unwrap();
+    assert!(errs[0]
+        .to_string()
+        .starts_with("tests/testcases/missing/file: "));
+}
+
+#[test]
+fn duplicate_symbols() {
+    let input_files = vec![
+        "tests/testcases/duplicate1.o".to_string(),
+        "tests/testcases/duplicate2.o".to_string(),
+    ];
+    let errs = link_files(input_files).err().unwrap();
+
+    assert_eq!(errs.len(), 1);
+    match &errs[0] {
+        LinkError::DuplicateSymbol {
+            name,
+            first_file,
+            second_file,
+        } => {
+            assert_eq!(name, "helper");
+            assert_eq!(first_file, "tests/testcases/duplicate1.o");
+            assert_eq!(second_file, "tests/testcases/duplicate2.o");
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn archive() {
+    let bytes = fs::read("tests/testcases/libarchive.a").unwrap();
+    let archive = Archive::parse(&bytes).unwrap();
+    let member_names: Vec<&str> = archive
+        .members
+        .iter()
+        .map(|member| member.name.as_str())
+        .collect();
+    assert_eq!(
+        member_names,
+        vec![
+            "archive_unused.o",
+            "archive_leaf.o",
+            "archive_helper.o",
+            "archive_start.o"
+        ]
+    );
+
+    let main_file = "tests/testcases/archive_main.o";
+    let objects = vec![(main_file.to_string(), Elf::read_from_file(main_file))];
+    let archives = vec![("libarchive.a".to_string(), archive)];
+    let output_elf = linker::link_with_archives(objects, archives).unwrap();
+
+    // `unused` refers to an undefined symbol, so it must not be pulled in
+    let mut symbol_names = symbol_names(&output_elf);
+    symbol_names.sort();
+    assert_eq!(symbol_names, vec!["_start", "helper", "leaf", "main"]);
+    assert_eq!(
+        output_elf.header.entrypoint,
+        find_symbol(&output_elf, "_start")
+    );
+}
+
+#[test]
+fn map() {
+    let bytes = fs::read("tests/testcases/libarchive.a").unwrap();
+    let archive = Archive::parse(&bytes).unwrap();
+
+    let main_file = "archive_main.o";
+    let objects = vec![(
+        main_file.to_string(),
+        Elf::read_from_file("tests/testcases/archive_main.o"),
+    )];
+    let archives = vec![("libarchive.a".to_string(), archive)];
+    let (_, map) = linker::link_with_map(objects, archives, &LinkerConfig::default()).unwrap();
+
+    let expected_lines = vec![
+        "libarchive.a(archive_helper.o)",
+        "                              archive_main.o (helper)",
+        "                              libarchive.a(archive_helper.o) (leaf)",
+        ".text           0x00000000004000b0       0x13",
+        " .text          0x00000000004000b0        0x6 archive_main.o",
+        "                0x00000000004000b0                main",
+        " .text          0x00000000004000b6        0x6 libarchive.a(archive_helper.o)",
+        "                0x00000000004000b6                helper",
+        " 0x00000000004000b1 R_X86_64_PLT32     helper-4                 0x00000001 archive_main.o:(.text+0x1)",
+    ];
+    for line in expected_lines {
+        assert!(map.lines().any(|l| l == line), "missing line: {}", line);
+    }
+}
+
+#[test]
+fn assemble_link_and_run() {
+    // rota assembles hello.s, herja links it and eir runs it
+    let source = SourceFile {
+        filename: "hello.s".to_string(),
+        content: fs::read_to_string("tests/testcases/hello.s").unwrap(),
+    };
+    let object = assembler::assemble(source).unwrap();
+    let output_elf =
+        linker::link_objects(vec![("hello.o".to_string(), Elf::read_from_bytes(&object))]).unwrap();
+
+    let io = CapturedIo::new(b"");
+    let mut emu = Emulator::new(0x7c00, 0x7c00);
+    emu.io = Box::new(io.clone());
+    emu.load_elf_bytes(&output_elf.to_bytes()).unwrap();
+    assert_eq!(emu.run(), ExitReason::Exited(42));
+    assert_eq!(io.stdout(), b"hello\n");
+}
+
+fn link_files(input_files: Vec<String>) -> Result<Elf, Vec<LinkError>> {
+    let inputs = input_files
+        .into_iter()
+        .map(|path| {
+            let elf = Elf::read_from_file(&path);
+            (path, elf)
+        })
+        .collect();
+    linker::link_objects(inputs)
+}
+
+fn link_files_with_config(input_files: Vec<&str>, config: &LinkerConfig) -> Elf {
+    let objects = input_files
+        .into_iter()
+        .map(|path| (path.to_string(), Elf::read_from_file(path)))
+        .collect();
+    let (output_elf, _) = linker::link_with_map(objects, Vec::new(), config).unwrap();
+    output_elf
+}
+
+fn find_symbol(elf: &Elf, name: &str) -> u64 {
+    find_symbols(elf, name)[0].value
+}
+
+fn find_symbols(elf: &Elf, name: &str) -> Vec<Symbol> {
+    let strtab = elf
+        .get_section(".strtab")
+        .unwrap()
+        .data
+        .as_strtab()
+        .unwrap();
+    let symbols = elf
+        .get_section(".symtab")
+        .unwrap()
+        .data
+        .as_symbols()
+        .unwrap();
+    symbols
+        .iter()
+        .filter(|symbol| strtab.get(symbol.name as usize) == name)
+        .copied()
+        .collect()
+}
+
+fn symbol_names(elf: &Elf) -> Vec<String> {
+    let strtab = elf
+        .get_section(".strtab")
+        .unwrap()
+        .data
+        .as_strtab()
+        .unwrap();
+    let symbols = elf
+        .get_section(".symtab")
+        .unwrap()
+        .data
+        .as_symbols()
+        .unwrap();
+    symbols
+        .iter()
+        .skip(1)
+        .map(|symbol| strtab.get(symbol.name as usize))
+        .collect()
+}
+
+fn read_at(elf: &Elf, addr: u64, len: usize) -> Vec<u8> {
+    let section = elf
+        .sections
+        .iter()
+        .find(|section| {
+            section.header.addr <= addr && addr < section.header.addr + section.header.size
+        })
+        .unwrap();
+    let offset = (addr - section.header.addr) as usize;
+    section.data.as_raw().unwrap()[offset..offset + len].to_vec()
+}
+
+fn u64_at(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
@@ -0,0 +1,4 @@
+.globl helper
+helper:
+  call leaf
+  ret
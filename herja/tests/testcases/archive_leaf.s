@@ -0,0 +1,3 @@
+.globl leaf
+leaf:
+  ret
@@ -0,0 +1,4 @@
+.globl main
+main:
+  call helper
+  ret
@@ -0,0 +1,4 @@
+.globl unused
+unused:
+  call missing
+  ret
@@ -0,0 +1,20 @@
+.globl _start
+_start:
+  movq $buf, %rax
+  movq $counter, %rcx
+  ret
+
+.section .rodata
+.globl message
+message:
+  .byte 104, 105
+
+.data
+.globl flag
+flag:
+  .byte 1
+
+.bss
+.globl buf
+buf:
+  .zero 65536
@@ -0,0 +1,5 @@
+.bss
+.balign 8
+.globl counter
+counter:
+  .zero 8
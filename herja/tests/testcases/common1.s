@@ -0,0 +1,9 @@
+.intel_syntax noprefix
+.global _start
+_start:
+    lea rax, [rip + buf]
+    lea rcx, [rip + shared]
+    ret
+
+.comm buf, 64, 8
+.comm shared, 4, 4
@@ -0,0 +1,3 @@
+.intel_syntax noprefix
+# a larger and more aligned `shared` than common1.s
+.comm shared, 16, 32
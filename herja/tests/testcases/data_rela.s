@@ -0,0 +1,17 @@
+.globl _start
+_start:
+  movq table_ptr@GOTPCREL(%rip), %rax
+  movl $values, %ecx
+  movq values(,%rcx,8), %rdx
+  ret
+
+.data
+.globl table
+table:
+  .quad values, values+8
+.globl table_ptr
+table_ptr:
+  .quad table
+.globl values
+values:
+  .long values+4
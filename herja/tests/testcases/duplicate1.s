@@ -0,0 +1,7 @@
+.globl _start
+_start:
+  call helper
+  ret
+.globl helper
+helper:
+  ret
@@ -0,0 +1,10 @@
+.globl _start
+_start:
+  lea data_end(%rip), %rax
+  lea .Lmark(%rip), %rcx
+  ret
+
+.data
+.Lmark:
+.globl data_end
+data_end:
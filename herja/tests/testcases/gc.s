@@ -0,0 +1,29 @@
+    .section .text._start,"ax",@progbits
+    .global _start
+_start:
+    call used
+    movq $value, %rax
+    ret
+
+    .section .text.used,"ax",@progbits
+    .global used
+used:
+    ret
+
+    # refers to an undefined symbol, which is fine once discarded
+    .section .text.unused,"ax",@progbits
+    .global unused
+unused:
+    call missing
+    movq $dead, %rax
+    ret
+
+    .section .data.value,"aw",@progbits
+    .global value
+value:
+    .quad 1
+
+    .section .data.dead,"aw",@progbits
+    .global dead
+dead:
+    .quad 2
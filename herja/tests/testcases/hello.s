@@ -0,0 +1,15 @@
+.intel_syntax noprefix
+.global _start
+_start:
+    mov rax, 1
+    mov rdi, 1
+    lea rsi, [rip + message]
+    mov rdx, 6
+    syscall
+    mov rax, 60
+    mov rdi, 42
+    syscall
+
+.section .rodata
+message:
+    .ascii "hello\n"
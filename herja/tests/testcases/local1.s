@@ -0,0 +1,30 @@
+    .text
+    .global _start
+    .type _start, @function
+_start:
+    call helper
+    call weak_fn
+    movq $table, %rcx
+    movq $weak_ref, %rdx
+    ret
+    .size _start, .-_start
+
+    .type helper, @function
+helper:
+    movl $1, %eax
+    ret
+    .size helper, .-helper
+
+    .weak weak_fn
+    .type weak_fn, @function
+weak_fn:
+    ret
+    .size weak_fn, .-weak_fn
+
+    .weak weak_ref
+
+    .data
+    .type table, @object
+table:
+    .quad 7
+    .size table, 8
@@ -0,0 +1,13 @@
+    .text
+    .global weak_fn
+    .type weak_fn, @function
+weak_fn:
+    movl $2, %eax
+    ret
+    .size weak_fn, .-weak_fn
+
+    # shares the name with the one in local1.s
+    .type helper, @function
+helper:
+    ret
+    .size helper, .-helper
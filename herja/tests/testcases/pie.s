@@ -0,0 +1,19 @@
+    .text
+    .global _start
+_start:
+    leaq message(%rip), %rsi
+    movq pointer(%rip), %rdi
+    movq counter@GOTPCREL(%rip), %rax
+    ret
+
+    .section .rodata
+message:
+    .ascii "hi"
+
+    .data
+    .global pointer
+pointer:
+    .quad message
+    .global counter
+counter:
+    .quad 0
@@ -0,0 +1,5 @@
+.globl _start
+_start:
+  call foo
+  movq bar(%rip), %rax
+  call foo
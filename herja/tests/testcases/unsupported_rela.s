@@ -0,0 +1,7 @@
+.global _start
+_start:
+    ret
+
+.data
+# R_X86_64_16, which herja does not support
+    .word _start
@@ -2,25 +2,30 @@ use std::{error, fs};
 
 use crate::{
     backend::{
+        function_sections,
         gen_code::{self, SectionName},
-        gen_elf,
+        gen_elf, listing,
     },
-    common::error::Error,
+    common::error::Errors,
     frontend::{
         lexer::{self, SourceFile},
-        parser,
+        parser::{self, node::Program, Syntax},
     },
 };
 
 pub fn assemble_to_file(
     input_file: String,
     output_file: String,
+    syntax: Syntax,
+    split_functions: bool,
 ) -> Result<(), Box<dyn error::Error>> {
     let source = SourceFile {
         filename: input_file.clone(),
         content: fs::read_to_string(input_file)?,
     };
-    let output = assemble(source)?;
+    let content = source.content.clone();
+    let output = assemble_with_options(source, syntax, split_functions)
+        .map_err(|errs| errs.render(&content))?;
     fs::write(output_file, output)?;
     Ok(())
 }
@@ -28,28 +33,53 @@ pub fn assemble_to_file(
 pub fn assemble_raw_to_file(
     input_file: String,
     output_file: String,
+    syntax: Syntax,
 ) -> Result<(), Box<dyn error::Error>> {
     let source = SourceFile {
         filename: input_file.clone(),
         content: fs::read_to_string(input_file)?,
     };
-    let output = assemble_raw(source)?;
+    let content = source.content.clone();
+    let output = assemble_raw(source, syntax).map_err(|errs| errs.render(&content))?;
     fs::write(output_file, output)?;
     Ok(())
 }
 
-pub fn assemble(source: SourceFile) -> Result<Vec<u8>, Error> {
-    lexer::tokenize(source)
-        .and_then(parser::parse)
+pub fn list_file(
+    input_file: String,
+    syntax: Syntax,
+    split_functions: bool,
+) -> Result<String, Box<dyn error::Error>> {
+    let source = SourceFile {
+        filename: input_file.clone(),
+        content: fs::read_to_string(input_file)?,
+    };
+    let content = source.content.clone();
+    Ok(list(source, syntax, split_functions).map_err(|errs| errs.render(&content))?)
+}
+
+pub fn assemble(source: SourceFile) -> Result<Vec<u8>, Errors> {
+    assemble_with_syntax(source, Syntax::Intel)
+}
+
+pub fn assemble_with_syntax(source: SourceFile, syntax: Syntax) -> Result<Vec<u8>, Errors> {
+    assemble_with_options(source, syntax, false)
+}
+
+// `split_functions` puts each function into its own `.text.<name>` section
+pub fn assemble_with_options(
+    source: SourceFile,
+    syntax: Syntax,
+    split_functions: bool,
+) -> Result<Vec<u8>, Errors> {
+    parse_with_options(source, syntax, split_functions)
         .and_then(gen_code::generate)
         .and_then(gen_elf::generate)
         .map(|elf| elf.to_bytes())
 }
 
-pub fn assemble_raw(source: SourceFile) -> Result<Vec<u8>, Error> {
-    let obj = lexer::tokenize(source)
-        .and_then(parser::parse)
-        .and_then(gen_code::generate)?;
+pub fn assemble_raw(source: SourceFile, syntax: Syntax) -> Result<Vec<u8>, Errors> {
+    let obj = parse(source, syntax).and_then(gen_code::generate)?;
 
     let text_section = obj
         .sections
@@ -59,3 +89,37 @@ pub fn assemble_raw(source: SourceFile) -> Result<Vec<u8>, Error> {
 
     Ok(text_section.data)
 }
+
+pub fn list(source: SourceFile, syntax: Syntax, split_functions: bool) -> Result<String, Errors> {
+    let content = source.content.clone();
+    let obj = parse_with_options(source, syntax, split_functions).and_then(gen_code::generate)?;
+
+    Ok(listing::generate(&content, &obj))
+}
+
+fn parse_with_options(
+    source: SourceFile,
+    syntax: Syntax,
+    split_functions: bool,
+) -> Result<Program, Errors> {
+    parse(source, syntax).map(|program| {
+        if split_functions {
+            function_sections::split(program)
+        } else {
+            program
+        }
+    })
+}
+
+// reports errors from both the lexer and the parser
+fn parse(source: SourceFile, syntax: Syntax) -> Result<Program, Errors> {
+    let (tokens, mut errors) = lexer::tokenize_recovering(source);
+    match parser::parse_with_syntax(tokens, syntax) {
+        Ok(program) if errors.is_empty() => return Ok(program),
+        Ok(_) => {}
+        Err(Errors(parse_errors)) => errors.extend(parse_errors),
+    }
+
+    errors.sort_by_key(|err| (err.pos.line, err.pos.column));
+    Err(Errors(errors))
+}
@@ -0,0 +1,55 @@
+use std::collections::HashSet;
+
+use crate::{
+    backend::gen_code::SectionName,
+    frontend::parser::node::{InstructionNode, Program, PseudoOp, PseudoOpArg},
+};
+
+// puts each function in `.text` into its own `.text.<name>` section, like
+// `gcc -ffunction-sections`, so that the linker can drop unused ones.
+// a function is a label declared with `.global` or `.type name, @function`
+pub fn split(program: Program) -> Program {
+    let functions = collect_functions(&program);
+
+    let mut insts = Vec::new();
+    let mut positions = Vec::new();
+    let mut in_text = true;
+    for (inst, pos) in program.insts.into_iter().zip(program.positions) {
+        match &inst {
+            InstructionNode::PseudoOp(op, args) => {
+                if let Some(section) = SectionName::switched_by(op, args) {
+                    in_text = section == SectionName::Text;
+                }
+            }
+            InstructionNode::Label(name) if in_text && functions.contains(name) => {
+                insts.push(InstructionNode::PseudoOp(
+                    PseudoOp::Section,
+                    vec![PseudoOpArg::String(format!(".text.{}", name))],
+                ));
+                positions.push(pos.clone());
+            }
+            _ => {}
+        }
+        insts.push(inst);
+        positions.push(pos);
+    }
+
+    Program { insts, positions }
+}
+
+fn collect_functions(program: &Program) -> HashSet<String> {
+    program
+        .insts
+        .iter()
+        .filter_map(|inst| match inst {
+            InstructionNode::PseudoOp(PseudoOp::Global, args) => Some(args[0].as_string()),
+            InstructionNode::PseudoOp(PseudoOp::Type, args)
+                if args[1].as_string() == "function" =>
+            {
+                Some(args[0].as_string())
+            }
+            _ => None,
+        })
+        .cloned()
+        .collect()
+}
@@ -2,7 +2,7 @@ pub mod collect_symbol;
 pub mod generate_code;
 pub mod resolve_symbol;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use x86asm::{encode, instruction::Instruction};
 
@@ -10,22 +10,22 @@ use crate::{
     backend::gen_code::{
         collect_symbol::SymbolCollector,
         generate_code::CodeGen,
-        resolve_symbol::{list_global_symbols, resolve_symbol},
+        resolve_symbol::{list_symbols, resolve_symbol},
     },
-    common::error::Error,
-    frontend::parser::node::Program,
+    common::error::Errors,
+    frontend::parser::node::{Program, PseudoOp, PseudoOpArg},
 };
 
-pub fn generate(program: Program) -> Result<Object, Error> {
+pub fn generate(program: Program) -> Result<Object, Errors> {
     let collector = SymbolCollector::new();
     let (symbols, tses) = collector.collect_symbols(&program);
 
     let generator = CodeGen::new();
-    let mut codes = generator.gen_program(program);
+    let mut codes = generator.gen_program(program)?;
 
     Ok(Object {
         sections: gen_sections(&symbols, &mut codes),
-        global_symbols: list_global_symbols(symbols, &codes),
+        symbols: list_symbols(symbols, &codes),
         tses,
     })
 }
@@ -33,18 +33,35 @@ pub fn generate(program: Program) -> Result<Object, Error> {
 fn gen_sections(symbols: &Symbols, codes: &mut Codes) -> Vec<Section> {
     let mut sections = Vec::new();
     for (section_name, code) in codes.iter_mut() {
-        let section_relas = resolve_symbol(&symbols, code);
+        let section_relas = resolve_symbol(symbols, section_name, code);
 
-        let section_data: Vec<u8> = code
-            .items
-            .iter()
-            .flat_map(|item| encode_item(&item))
-            .collect();
+        let mut section_data: Vec<u8> = Vec::new();
+        let mut section_lines: Vec<LineRange> = Vec::new();
+        for (item, line) in code.items.iter().zip(&code.lines) {
+            let item_data = encode_item(item);
+            let offset = section_data.len();
+            section_data.extend(item_data);
+
+            // merge items from the same line, e.g. `.lcomm`
+            match section_lines.last_mut() {
+                Some(range) if range.line == *line => {
+                    range.size = section_data.len() - range.offset
+                }
+                _ => section_lines.push(LineRange {
+                    line: *line,
+                    offset,
+                    size: section_data.len() - offset,
+                }),
+            }
+        }
 
         sections.push(Section {
             name: section_name.clone(),
+            attr: code.attr,
+            align: code.align,
             data: section_data,
             relas: section_relas,
+            lines: section_lines,
         })
     }
     sections
@@ -58,20 +75,31 @@ fn encode_item(item: &CodeItem) -> Vec<u8> {
 }
 
 type Symbols = HashMap<String, Symbol>;
-type Codes = HashMap<SectionName, Code>;
+type Codes = BTreeMap<SectionName, Code>;
 
 #[derive(Debug)]
 pub struct Object {
     pub sections: Vec<Section>,
-    pub global_symbols: Vec<Symbol>,
+    pub symbols: Vec<Symbol>,
     pub tses: Vec<Tse>,
 }
 
 #[derive(Debug)]
 pub struct Section {
     pub name: SectionName,
+    pub attr: SectionAttr,
+    pub align: u64,
     pub data: Vec<u8>,
     pub relas: Vec<Rela>,
+    pub lines: Vec<LineRange>,
+}
+
+// bytes in a section generated from a source line
+#[derive(Debug)]
+pub struct LineRange {
+    pub line: usize,
+    pub offset: usize,
+    pub size: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -80,6 +108,19 @@ pub struct Symbol {
     pub addr: Option<usize>,
     pub is_global: bool,
     pub section: SectionName,
+    pub kind: SymbolKind,
+    pub size: usize,
+    // alignment of a `.comm` symbol
+    pub common_align: Option<u64>,
+    // item index given by `.size sym, .-sym`
+    size_until: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    NoType,
+    Func,
+    Object,
 }
 
 #[derive(Debug)]
@@ -87,35 +128,136 @@ pub struct Rela {
     pub name: String,
     pub typ: RelaType,
     pub offset: u32,
+    pub addend: i64,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum RelaType {
     Pc32,
     Plt32,
+    GotPcRel,
+    Abs64,
+    Abs32,
+    Abs32S,
+}
+
+impl RelaType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            RelaType::Pc32 => "R_X86_64_PC32",
+            RelaType::Plt32 => "R_X86_64_PLT32",
+            RelaType::GotPcRel => "R_X86_64_GOTPCREL",
+            RelaType::Abs64 => "R_X86_64_64",
+            RelaType::Abs32 => "R_X86_64_32",
+            RelaType::Abs32S => "R_X86_64_32S",
+        }
+    }
 }
 
-#[derive(Hash, Eq, PartialEq, Clone, Debug)]
+#[derive(Hash, Eq, PartialEq, Ord, PartialOrd, Clone, Debug)]
 pub enum SectionName {
-    Data,
     Text,
+    Data,
+    Rodata,
+    Bss,
+    Other(String),
 }
 
 impl SectionName {
+    pub fn new(name: &str) -> Self {
+        match name {
+            ".text" => SectionName::Text,
+            ".data" => SectionName::Data,
+            ".rodata" => SectionName::Rodata,
+            ".bss" => SectionName::Bss,
+            x => SectionName::Other(x.to_string()),
+        }
+    }
+
     pub fn as_str(&self) -> &str {
         match self {
-            SectionName::Data => ".data",
             SectionName::Text => ".text",
+            SectionName::Data => ".data",
+            SectionName::Rodata => ".rodata",
+            SectionName::Bss => ".bss",
+            SectionName::Other(name) => name,
+        }
+    }
+
+    // returns the section to switch to if `op` is a section directive
+    pub fn switched_by(op: &PseudoOp, args: &[PseudoOpArg]) -> Option<Self> {
+        match op {
+            PseudoOp::Text => Some(SectionName::Text),
+            PseudoOp::Data => Some(SectionName::Data),
+            PseudoOp::Rodata => Some(SectionName::Rodata),
+            PseudoOp::Bss => Some(SectionName::Bss),
+            PseudoOp::Section => Some(SectionName::new(args[0].as_string())),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SectionAttr {
+    pub alloc: bool,
+    pub write: bool,
+    pub exec: bool,
+    pub nobits: bool,
+}
+
+impl SectionAttr {
+    pub fn new(name: &SectionName) -> Self {
+        let name = name.as_str();
+        let is = |prefix: &str| name == prefix || name.starts_with(&format!("{}.", prefix));
+
+        if is(".text") {
+            Self::parse("ax", None)
+        } else if is(".data") {
+            Self::parse("aw", None)
+        } else if is(".rodata") {
+            Self::parse("a", None)
+        } else if is(".bss") {
+            Self::parse("aw", Some("nobits"))
+        } else {
+            Self::default()
+        }
+    }
+
+    // parses the flags and type of `.section name, "flags", @type`
+    pub fn parse(flags: &str, typ: Option<&str>) -> Self {
+        Self {
+            alloc: flags.contains('a'),
+            write: flags.contains('w'),
+            exec: flags.contains('x'),
+            nobits: typ == Some("nobits"),
         }
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Debug)]
 pub struct Code {
+    attr: SectionAttr,
+    align: u64,
+    size: usize,
     items: Vec<CodeItem>,
+    // source line of each item
+    lines: Vec<usize>,
     unresolved_symbols: Vec<UnresolvedSymbol>,
 }
 
+impl Code {
+    fn new(name: &SectionName) -> Self {
+        Self {
+            attr: SectionAttr::new(name),
+            align: 1,
+            size: 0,
+            items: Vec::new(),
+            lines: Vec::new(),
+            unresolved_symbols: Vec::new(),
+        }
+    }
+}
+
 #[derive(Debug)]
 enum CodeItem {
     Raw(Vec<u8>),
@@ -127,12 +269,22 @@ struct UnresolvedSymbol {
     symbol_name: String,
     typ: SymbolType,
     item_index: usize,
+    addend: i64,
 }
 
 #[derive(Debug)]
 enum SymbolType {
+    // rip-relative displacement
     Addr,
+    // rip-relative displacement to the GOT entry
+    GotPcRel,
+    // absolute displacement
+    AbsDisp,
+    // absolute immediate
+    AbsImm,
     Jump,
+    // absolute address of `size` bytes at `offset` in a data item
+    Data { offset: usize, size: usize },
 }
 
 #[derive(Debug)]
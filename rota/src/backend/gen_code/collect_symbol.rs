@@ -1,8 +1,10 @@
 use std::collections::HashMap;
 
 use crate::{
-    backend::gen_code::{SectionName, Symbol, Symbols, Tse},
-    frontend::parser::node::{DispNode, InstructionNode, OperandNode, Program, PseudoOp},
+    backend::gen_code::{SectionName, Symbol, SymbolKind, Symbols, Tse},
+    frontend::parser::node::{
+        DispNode, InstructionNode, MemoryNode, OperandNode, Program, PseudoOp, PseudoOpArg,
+    },
 };
 
 pub struct SymbolCollector {
@@ -20,6 +22,11 @@ impl Symbol {
         self
     }
 
+    fn set_local(&mut self) -> &mut Self {
+        self.is_global = false;
+        self
+    }
+
     fn set_addr(&mut self, addr: usize) -> &mut Self {
         self.addr = Some(addr);
         self
@@ -29,6 +36,11 @@ impl Symbol {
         self.section = section;
         self
     }
+
+    fn set_kind(&mut self, kind: SymbolKind) -> &mut Self {
+        self.kind = kind;
+        self
+    }
 }
 
 impl SymbolCollector {
@@ -46,11 +58,9 @@ impl SymbolCollector {
         for inst in &program.insts {
             self.collect_symbols_in(inst);
 
-            if let InstructionNode::PseudoOp(op, _) = inst {
-                match op {
-                    PseudoOp::Data => self.current_section = SectionName::Data,
-                    PseudoOp::Text => self.current_section = SectionName::Text,
-                    _ => {}
+            if let InstructionNode::PseudoOp(op, args) = inst {
+                if let Some(section) = SectionName::switched_by(op, args) {
+                    self.current_section = section;
                 }
             }
 
@@ -69,14 +79,62 @@ impl SymbolCollector {
                 self.add_symbol(name).set_global();
                 self.current_symbol = name.clone();
             }
+            InstructionNode::PseudoOp(PseudoOp::Local, args) => {
+                for arg in args {
+                    self.add_symbol(arg.as_string()).set_local();
+                }
+            }
             InstructionNode::PseudoOp(PseudoOp::Tse, args) => {
                 self.tses.push(Tse {
                     symbol_name: self.current_symbol.clone(),
-                    offset: *args[0].as_integer() as i64,
+                    offset: *args[0].as_integer(),
                     size: *args[1].as_integer() as u64,
                     align: *args[2].as_integer() as u64,
                 });
             }
+            InstructionNode::PseudoOp(PseudoOp::Type, args) => {
+                let kind = match args[1].as_string().as_str() {
+                    "function" => SymbolKind::Func,
+                    "object" => SymbolKind::Object,
+                    _ => SymbolKind::NoType,
+                };
+                self.add_symbol(args[0].as_string()).set_kind(kind);
+            }
+            InstructionNode::PseudoOp(PseudoOp::Size, args) => {
+                let addr = *self.cur_addr();
+                let symbol = self.add_symbol(args[0].as_string());
+                match args[1] {
+                    PseudoOpArg::Integer(size) => symbol.size = size as usize,
+                    _ => symbol.size_until = Some(addr),
+                }
+            }
+            InstructionNode::PseudoOp(PseudoOp::Comm, args) => {
+                let symbol = self.add_symbol(args[0].as_string());
+                symbol.set_global();
+                symbol.size = *args[1].as_integer() as usize;
+                symbol.common_align = Some(args.get(2).map_or(1, |arg| *arg.as_integer() as u64));
+            }
+            InstructionNode::PseudoOp(PseudoOp::Lcomm, args) => {
+                // `.lcomm` emits an alignment item and a zero-filled item into .bss
+                let prev_section = std::mem::replace(&mut self.current_section, SectionName::Bss);
+                self.inc_addr();
+                let addr = *self.cur_addr();
+                self.inc_addr();
+                self.add_symbol(args[0].as_string())
+                    .set_addr(addr)
+                    .set_section(SectionName::Bss)
+                    .set_kind(SymbolKind::Object)
+                    .size = *args[1].as_integer() as usize;
+                self.current_section = prev_section;
+            }
+            InstructionNode::PseudoOp(PseudoOp::Long, args)
+            | InstructionNode::PseudoOp(PseudoOp::Quad, args) => {
+                for arg in args {
+                    if let PseudoOpArg::Symbol(symbol) = arg {
+                        self.add_symbol(&symbol.name);
+                    }
+                }
+            }
             InstructionNode::Label(name) => {
                 let addr = *self.cur_addr();
                 let cur_section = self.current_section.clone();
@@ -88,22 +146,42 @@ impl SymbolCollector {
             InstructionNode::UnaryOp(_, OperandNode::Label(name)) => {
                 self.add_symbol(name);
             }
-            InstructionNode::BinaryOp(_, _, OperandNode::Memory(mem)) => {
-                if let Some(DispNode::Label(ref name)) = mem.disp {
-                    self.add_symbol(name);
-                }
+            InstructionNode::UnaryOp(_, opr1) => self.collect_symbols_in_operand(opr1),
+            InstructionNode::BinaryOp(_, opr1, opr2) => {
+                self.collect_symbols_in_operand(opr1);
+                self.collect_symbols_in_operand(opr2);
+            }
+            _ => {}
+        }
+    }
+
+    fn collect_symbols_in_operand(&mut self, opr: &OperandNode) {
+        match opr {
+            OperandNode::Memory(MemoryNode {
+                disp: Some(DispNode::Label(symbol)),
+                ..
+            })
+            | OperandNode::SymbolImmediate(symbol) => {
+                self.add_symbol(&symbol.name);
             }
             _ => {}
         }
     }
 
     fn add_symbol(&mut self, name: &str) -> &mut Symbol {
-        self.symbols.entry(name.to_string()).or_insert(Symbol {
-            name: name.to_string(),
-            addr: None,
-            is_global: false,
-            section: self.current_section.clone(),
-        })
+        let section = self.current_section.clone();
+        self.symbols
+            .entry(name.to_string())
+            .or_insert_with(|| Symbol {
+                name: name.to_string(),
+                addr: None,
+                is_global: false,
+                section,
+                kind: SymbolKind::NoType,
+                size: 0,
+                common_align: None,
+                size_until: None,
+            })
     }
 
     fn inc_addr(&mut self) {
@@ -124,5 +202,12 @@ fn is_inst(inst: &InstructionNode) -> bool {
             | InstructionNode::BinaryOp(_, _, _)
             | InstructionNode::PseudoOp(PseudoOp::Zero, _)
             | InstructionNode::PseudoOp(PseudoOp::Ascii, _)
+            | InstructionNode::PseudoOp(PseudoOp::Asciz, _)
+            | InstructionNode::PseudoOp(PseudoOp::Byte, _)
+            | InstructionNode::PseudoOp(PseudoOp::Word, _)
+            | InstructionNode::PseudoOp(PseudoOp::Long, _)
+            | InstructionNode::PseudoOp(PseudoOp::Quad, _)
+            | InstructionNode::PseudoOp(PseudoOp::Align, _)
+            | InstructionNode::PseudoOp(PseudoOp::P2align, _)
     )
 }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use x86asm::instruction::{
     mnemonic::Mnemonic,
@@ -6,35 +6,52 @@ use x86asm::instruction::{
         immediate::Immediate,
         memory::{Displacement, Memory},
         offset::Offset,
+        register::Register,
         Operand,
     },
     Instruction,
 };
 
 use crate::{
-    backend::gen_code::{Code, CodeItem, Codes, SectionName, SymbolType, UnresolvedSymbol},
-    frontend::parser::node::{DispNode, InstructionNode, OperandNode, Program, PseudoOp},
+    backend::gen_code::{
+        encode_item, Code, CodeItem, Codes, SectionAttr, SectionName, SymbolType, UnresolvedSymbol,
+    },
+    common::{
+        error::{Error, ErrorKind, Errors},
+        pos::Pos,
+    },
+    frontend::parser::node::{
+        DispNode, InstructionNode, OperandNode, Program, PseudoOp, PseudoOpArg,
+    },
 };
 
 pub struct CodeGen {
     codes: Codes,
     current_section: SectionName,
+    current_pos: Pos,
+    errors: Vec<Error>,
 }
 
 impl CodeGen {
     pub fn new() -> Self {
         Self {
-            codes: HashMap::new(),
+            codes: BTreeMap::new(),
             current_section: SectionName::Text,
+            current_pos: Pos::default(),
+            errors: Vec::new(),
         }
     }
 
-    pub fn gen_program(mut self, program: Program) -> Codes {
-        for inst in program.insts {
+    pub fn gen_program(mut self, program: Program) -> Result<Codes, Errors> {
+        for (inst, pos) in program.insts.into_iter().zip(program.positions) {
+            self.current_pos = pos;
             self.gen_inst(inst);
         }
 
-        self.codes
+        if !self.errors.is_empty() {
+            return Err(Errors(self.errors));
+        }
+        Ok(self.codes)
     }
 
     fn gen_inst(&mut self, inst: InstructionNode) {
@@ -53,22 +70,50 @@ impl CodeGen {
                 }
 
                 let opr1 = self.opr2opr(opr1);
-                let opr2 = self.opr2opr(opr2);
+                let mut opr2 = self.opr2opr(opr2);
+                // the count of a shift is always an 8-bit immediate
+                if let (
+                    Mnemonic::Sar | Mnemonic::Shl | Mnemonic::Shr,
+                    Operand::Immediate(Immediate::Imm32(value)),
+                ) = (&op, &opr2)
+                {
+                    opr2 = Operand::Immediate(Immediate::Imm8(*value as i8));
+                }
                 self.add_item(CodeItem::Inst(Instruction::new_binary(op, opr1, opr2)));
             }
             InstructionNode::PseudoOp(op, args) => match op {
-                PseudoOp::Data => self.current_section = SectionName::Data,
-                PseudoOp::Text => self.current_section = SectionName::Text,
+                PseudoOp::Data | PseudoOp::Text | PseudoOp::Rodata | PseudoOp::Bss => {
+                    self.current_section = SectionName::switched_by(&op, &args).unwrap();
+                    // the section exists even if nothing is put in it, for labels
+                    self.cur_section();
+                }
+                PseudoOp::Section => self.gen_section(&args),
                 PseudoOp::Zero => self.gen_zero(*args[0].as_integer()),
-                PseudoOp::Ascii => self.gen_ascii(args[0].as_string()),
-                PseudoOp::Byte => self.gen_byte(*args[0].as_integer() as i8),
-                PseudoOp::Long => self.gen_long(*args[0].as_integer()),
+                PseudoOp::Ascii => self.gen_ascii(&args, false),
+                PseudoOp::Asciz => self.gen_ascii(&args, true),
+                PseudoOp::Byte => self.gen_integers(&args, 1),
+                PseudoOp::Word => self.gen_integers(&args, 2),
+                PseudoOp::Long => self.gen_integers(&args, 4),
+                PseudoOp::Quad => self.gen_integers(&args, 8),
+                PseudoOp::Align => self.gen_align(&args, *args[0].as_integer() as u64),
+                PseudoOp::P2align => self.gen_align(&args, 1 << *args[0].as_integer()),
+                PseudoOp::Lcomm => self.gen_lcomm(&args),
                 _ => {}
             },
             _ => {}
         }
     }
 
+    fn gen_section(&mut self, args: &[PseudoOpArg]) {
+        self.current_section = SectionName::new(args[0].as_string());
+        self.cur_section();
+
+        if let Some(flags) = args.get(1) {
+            let typ = args.get(2).map(|typ| typ.as_string().as_str());
+            self.cur_section().attr = SectionAttr::parse(flags.as_string(), typ);
+        }
+    }
+
     fn gen_mov(&mut self, opr1: OperandNode, opr2: OperandNode) {
         let opr1 = self.opr2opr(opr1);
         let mut opr2 = self.opr2opr(opr2);
@@ -84,82 +129,165 @@ impl CodeGen {
         )));
     }
 
-    fn gen_zero(&mut self, arg: i32) {
-        if arg.is_negative() {
-            return;
+    fn gen_zero(&mut self, arg: i64) {
+        self.add_item(CodeItem::Raw(vec![0; arg.max(0) as usize]));
+    }
+
+    fn gen_ascii(&mut self, args: &[PseudoOpArg], zero_terminated: bool) {
+        let mut data = Vec::new();
+        for arg in args {
+            let arg_content = arg
+                .as_string()
+                .trim_start_matches('"')
+                .trim_end_matches('"');
+            data.extend(arg_content.as_bytes());
+            if zero_terminated {
+                data.push(0);
+            }
         }
 
-        self.add_item(CodeItem::Raw(vec![0; arg as usize]));
+        self.add_item(CodeItem::Raw(data));
     }
 
-    fn gen_ascii(&mut self, arg: &str) {
-        let arg_content = arg.trim_start_matches('"').trim_end_matches('"');
+    fn gen_integers(&mut self, args: &[PseudoOpArg], size: usize) {
+        let mut data = Vec::new();
+        for arg in args {
+            let value = match arg {
+                PseudoOpArg::Symbol(symbol) => {
+                    let typ = SymbolType::Data {
+                        offset: data.len(),
+                        size,
+                    };
+                    self.add_unresolved_symbol(symbol.name.clone(), typ, symbol.addend);
+                    0
+                }
+                arg => *arg.as_integer(),
+            };
+            data.extend(&value.to_le_bytes()[..size]);
+        }
 
-        self.add_item(CodeItem::Raw(arg_content.as_bytes().to_vec()));
+        self.add_item(CodeItem::Raw(data));
     }
 
-    fn gen_byte(&mut self, value: i8) {
-        self.add_item(CodeItem::Raw(vec![value as u8]));
+    fn gen_align(&mut self, args: &[PseudoOpArg], align: u64) {
+        let code = self.cur_section();
+        let default_fill = if code.attr.exec { 0x90 } else { 0 };
+        let fill = args
+            .get(1)
+            .map_or(default_fill, |arg| *arg.as_integer() as u8);
+
+        let align = align.max(1) as usize;
+        let padding = (align - code.size % align) % align;
+        code.align = code.align.max(align as u64);
+
+        self.add_item(CodeItem::Raw(vec![fill; padding]));
     }
 
-    fn gen_long(&mut self, value: i32) {
-        self.add_item(CodeItem::Raw(value.to_le_bytes().to_vec()));
+    fn gen_lcomm(&mut self, args: &[PseudoOpArg]) {
+        let prev_section = std::mem::replace(&mut self.current_section, SectionName::Bss);
+
+        let size = *args[1].as_integer();
+        let align = args.get(2).map_or(1, |arg| *arg.as_integer() as u64);
+        self.gen_align(&[], align);
+        self.gen_zero(size);
+
+        self.current_section = prev_section;
     }
 
     fn opr2opr(&mut self, opr: OperandNode) -> Operand {
         match opr {
             OperandNode::Immidiate(value) => {
-                if value < 0x80 {
+                if (-0x80..0x80).contains(&value) {
                     Operand::Immediate(Immediate::Imm8(value as i8))
                 } else {
                     Operand::Immediate(Immediate::Imm32(value as i32))
                 }
             }
             OperandNode::Register(reg) => Operand::Register(reg),
-            OperandNode::Memory(mem) => Operand::Memory(Memory::new(
-                mem.base,
-                mem.disp.map(|disp| match disp {
-                    DispNode::Immediate(disp) => {
-                        if disp >= -0x80 && disp < 0x80 {
-                            Displacement::Disp8(disp as i8)
-                        } else {
-                            Displacement::Disp32(disp as i32)
+            OperandNode::Memory(mem) => {
+                let is_rip_relative = mem.base == Some(Register::Rip);
+                Operand::Memory(Memory {
+                    base: mem.base,
+                    index: mem.index,
+                    disp: mem.disp.map(|disp| match disp {
+                        DispNode::Immediate(disp) => {
+                            if disp >= -0x80 && disp < 0x80 {
+                                Displacement::Disp8(disp as i8)
+                            } else {
+                                Displacement::Disp32(disp as i32)
+                            }
                         }
-                    }
-                    DispNode::Label(symbol_name) => {
-                        let cur_section = self.cur_section();
-                        let item_index = cur_section.items.len();
-                        cur_section.unresolved_symbols.push(UnresolvedSymbol {
-                            symbol_name,
-                            typ: SymbolType::Addr,
-                            item_index,
-                        });
-
-                        Displacement::Disp32(0)
-                    }
-                }),
-            )),
+                        DispNode::Label(symbol) => {
+                            let typ = if symbol.gotpcrel {
+                                SymbolType::GotPcRel
+                            } else if is_rip_relative {
+                                SymbolType::Addr
+                            } else {
+                                SymbolType::AbsDisp
+                            };
+                            self.add_unresolved_symbol(symbol.name, typ, symbol.addend);
+
+                            Displacement::Disp32(0)
+                        }
+                    }),
+                })
+            }
+            OperandNode::SymbolImmediate(symbol) => {
+                self.add_unresolved_symbol(symbol.name, SymbolType::AbsImm, symbol.addend);
+
+                Operand::Immediate(Immediate::Imm32(0))
+            }
             OperandNode::Label(symbol_name) => {
-                let cur_section = self.cur_section();
-                let item_index = cur_section.items.len();
-                cur_section.unresolved_symbols.push(UnresolvedSymbol {
-                    symbol_name,
-                    typ: SymbolType::Jump,
-                    item_index,
-                });
+                self.add_unresolved_symbol(symbol_name, SymbolType::Jump, 0);
 
                 Operand::Offset(Offset::Off32(0))
             }
         }
     }
 
+    // the symbol is referred by the item to be added next
+    fn add_unresolved_symbol(&mut self, symbol_name: String, typ: SymbolType, addend: i64) {
+        let cur_section = self.cur_section();
+        let item_index = cur_section.items.len();
+        cur_section.unresolved_symbols.push(UnresolvedSymbol {
+            symbol_name,
+            typ,
+            item_index,
+            addend,
+        });
+    }
+
     fn add_item(&mut self, item: CodeItem) {
-        self.cur_section().items.push(item);
+        let line = self.current_pos.line;
+        let section = self.current_section.as_str().to_string();
+        let code = self.cur_section();
+
+        // a nobits section has no contents in the file, so it can only be zero
+        // filled as by `.zero` and `.lcomm`
+        if code.attr.nobits {
+            let is_zero = match &item {
+                CodeItem::Raw(data) => data.iter().all(|byte| *byte == 0),
+                CodeItem::Inst(_) => false,
+            };
+            let has_symbol = code
+                .unresolved_symbols
+                .last()
+                .is_some_and(|symbol| symbol.item_index == code.items.len());
+            if !is_zero || has_symbol {
+                let kind = ErrorKind::NonZeroInNobits { section };
+                self.errors.push(Error::new(self.current_pos.clone(), kind));
+                return;
+            }
+        }
+
+        let code = self.cur_section();
+        code.size += encode_item(&item).len();
+        code.items.push(item);
+        code.lines.push(line);
     }
 
     fn cur_section(&mut self) -> &mut Code {
-        self.codes
-            .entry(self.current_section.clone())
-            .or_insert_with(Code::default)
+        let name = self.current_section.clone();
+        self.codes.entry(name).or_insert_with_key(Code::new)
     }
 }
@@ -1,26 +1,79 @@
-use x86asm::instruction::operand::{offset::Offset, Operand};
+use x86asm::instruction::{
+    operand::{immediate::Immediate, offset::Offset, register, Operand},
+    Instruction,
+};
 
 use crate::backend::gen_code::{
-    encode_item, Code, CodeItem, Codes, Rela, RelaType, Symbol, SymbolType, Symbols,
+    encode_item, Code, CodeItem, Codes, Rela, RelaType, SectionName, Symbol, SymbolType, Symbols,
 };
 
-pub fn resolve_symbol(symbols: &Symbols, code: &mut Code) -> Vec<Rela> {
+pub fn resolve_symbol(symbols: &Symbols, section_name: &SectionName, code: &mut Code) -> Vec<Rela> {
     let mut relas = Vec::new();
     for unresolved_symbol in &code.unresolved_symbols {
         let symbol = symbols.get(&unresolved_symbol.symbol_name).unwrap();
+        let item_offset = calc_offset(&code.items, 0, unresolved_symbol.item_index) as u32;
+        let item = code.items.get(unresolved_symbol.item_index).unwrap();
+        let item_len = encode_item(item).len() as u32;
+        let addend = unresolved_symbol.addend;
+
         match unresolved_symbol.typ {
-            SymbolType::Addr => {
-                let item_index = unresolved_symbol.item_index + 1;
-                let rela_offset = calc_offset(&code.items, 0, item_index) as u32 - 4;
+            SymbolType::Addr | SymbolType::GotPcRel => {
+                // rip points to the end of the instruction, where an immediate may follow
+                let field_offset = item_len - 4 - imm_len(item);
+                let typ = if matches!(unresolved_symbol.typ, SymbolType::Addr) {
+                    RelaType::Pc32
+                } else {
+                    RelaType::GotPcRel
+                };
+
+                relas.push(Rela {
+                    name: symbol.name.to_string(),
+                    typ,
+                    offset: item_offset + field_offset,
+                    addend: addend - (item_len - field_offset) as i64,
+                });
+            }
+            SymbolType::AbsDisp => {
+                relas.push(Rela {
+                    name: symbol.name.to_string(),
+                    typ: RelaType::Abs32S,
+                    offset: item_offset + item_len - 4 - imm_len(item),
+                    addend,
+                });
+            }
+            SymbolType::AbsImm => {
+                // an immediate is zero-extended only when moved into a 32-bit register
+                let typ = match item {
+                    CodeItem::Inst(Instruction {
+                        operand1: Some(Operand::Register(reg)),
+                        ..
+                    }) if reg.size() == register::Size::DWord => RelaType::Abs32,
+                    _ => RelaType::Abs32S,
+                };
 
                 relas.push(Rela {
                     name: symbol.name.to_string(),
-                    typ: RelaType::Pc32,
-                    offset: rela_offset,
+                    typ,
+                    offset: item_offset + item_len - 4,
+                    addend,
+                });
+            }
+            SymbolType::Data { offset, size } => {
+                let typ = match size {
+                    8 => RelaType::Abs64,
+                    _ => RelaType::Abs32,
+                };
+
+                relas.push(Rela {
+                    name: symbol.name.to_string(),
+                    typ,
+                    offset: item_offset + offset as u32,
+                    addend,
                 });
             }
             SymbolType::Jump => {
-                let is_global = symbol.is_global || symbol.addr.is_none();
+                let is_global =
+                    symbol.is_global || symbol.addr.is_none() || symbol.section != *section_name;
 
                 let offset = if is_global {
                     0
@@ -38,13 +91,11 @@ pub fn resolve_symbol(symbols: &Symbols, code: &mut Code) -> Vec<Rela> {
                     _ => panic!(),
                 }
                 if is_global {
-                    let item_index = unresolved_symbol.item_index + 1;
-                    let rela_offset = calc_offset(&code.items, 0, item_index) as u32 - 4;
-
                     relas.push(Rela {
                         name: symbol.name.to_string(),
                         typ: RelaType::Plt32,
-                        offset: rela_offset,
+                        offset: item_offset + item_len - 4,
+                        addend: -4,
                     });
                 }
             }
@@ -53,25 +104,54 @@ pub fn resolve_symbol(symbols: &Symbols, code: &mut Code) -> Vec<Rela> {
     relas
 }
 
-pub fn list_global_symbols(symbols: Symbols, codes: &Codes) -> Vec<Symbol> {
-    let mut global_symbols: Vec<Symbol> = symbols
-        .into_iter()
-        .map(|(_, v)| v)
-        .filter(|symbol| symbol.is_global | symbol.addr.is_none())
-        .map(|symbol| relocate_symbol(symbol, &codes))
+// size of the immediate which follows the displacement
+fn imm_len(item: &CodeItem) -> u32 {
+    match item {
+        CodeItem::Inst(Instruction {
+            operand2: Some(Operand::Immediate(imm)),
+            ..
+        }) => match imm {
+            Immediate::Imm8(_) => 1,
+            Immediate::Imm32(_) => 4,
+        },
+        _ => 0,
+    }
+}
+
+// lists symbols to be written to the symbol table, local symbols first
+pub fn list_symbols(symbols: Symbols, codes: &Codes) -> Vec<Symbol> {
+    let mut symbols: Vec<Symbol> = symbols
+        .into_values()
+        .map(|symbol| relocate_symbol(symbol, codes))
         .collect();
 
-    global_symbols.sort_by_key(|symbol| symbol.addr);
+    symbols.sort_by(|a, b| (a.is_global, a.addr, &a.name).cmp(&(b.is_global, b.addr, &b.name)));
 
-    global_symbols
+    symbols
 }
 
 fn relocate_symbol(mut symbol: Symbol, codes: &Codes) -> Symbol {
-    let code = codes.get(&symbol.section).unwrap();
+    let addr = match symbol.addr {
+        Some(addr) => addr,
+        None => {
+            // undefined symbols are resolved by the linker
+            symbol.is_global = true;
+            return symbol;
+        }
+    };
+    // a label in a section with no contents is at its start
+    let code = match codes.get(&symbol.section) {
+        Some(code) => code,
+        None => {
+            symbol.addr = Some(0);
+            return symbol;
+        }
+    };
 
-    symbol.addr = symbol
-        .addr
-        .map(|addr| calc_offset(&code.items, 0, addr) as usize);
+    if let Some(until) = symbol.size_until {
+        symbol.size = calc_offset(&code.items, addr, until) as usize;
+    }
+    symbol.addr = Some(calc_offset(&code.items, 0, addr) as usize);
 
     symbol
 }
@@ -11,8 +11,8 @@ use elfen::{
 };
 
 use crate::{
-    backend::gen_code::{Object, RelaType, Section, SectionName},
-    common::error::Error,
+    backend::gen_code::{Object, RelaType, Section, SymbolKind},
+    common::error::Errors,
 };
 
 struct ElfGen {
@@ -22,7 +22,7 @@ struct ElfGen {
     symbols: HashMap<String, usize>,
 }
 
-pub fn generate(obj: Object) -> Result<Elf, Error> {
+pub fn generate(obj: Object) -> Result<Elf, Errors> {
     let elfgen = ElfGen::new(obj);
     elfgen.generate()
 }
@@ -36,7 +36,7 @@ impl ElfGen {
         }
     }
 
-    fn generate(mut self) -> Result<Elf, Error> {
+    fn generate(mut self) -> Result<Elf, Errors> {
         self.gen_header();
         self.gen_sections();
         self.elf.update_section_headers();
@@ -67,14 +67,16 @@ impl ElfGen {
 
     fn gen_alloc_sections(&mut self) {
         for section in self.obj.sections.iter_mut() {
-            let header = match section.name {
-                SectionName::Data => gen_data_header(),
-                SectionName::Text => gen_text_header(),
+            let mut header = gen_section_header(section);
+
+            let data_raw = std::mem::take(&mut section.data);
+            let data = if section.attr.nobits {
+                header.size = data_raw.len() as u64;
+                SectionData::Raw(Vec::new())
+            } else {
+                SectionData::Raw(data_raw)
             };
 
-            let data_raw = std::mem::replace(&mut section.data, Vec::new());
-            let data = SectionData::Raw(data_raw);
-
             self.elf.add_section(section.name.as_str(), header, data);
         }
     }
@@ -109,18 +111,32 @@ impl ElfGen {
         }
 
         // add symbols
-        for symbol_data in &self.obj.global_symbols {
+        for symbol_data in &self.obj.symbols {
             let mut symbol = Symbol::default();
             symbol.name = strtab.insert(symbol_data.name.clone()) as u32;
-            symbol.set_binding(symbol::Binding::Global);
-            match symbol_data.addr {
-                Some(addr) => {
+            symbol.set_binding(if symbol_data.is_global {
+                symbol::Binding::Global
+            } else {
+                symbol::Binding::Local
+            });
+            symbol.set_type(match symbol_data.kind {
+                SymbolKind::NoType => symbol::Type::Notype,
+                SymbolKind::Func => symbol::Type::Func,
+                SymbolKind::Object => symbol::Type::Object,
+            });
+            symbol.size = symbol_data.size as u64;
+            match (symbol_data.addr, symbol_data.common_align) {
+                (Some(addr), _) => {
                     let section_name = symbol_data.section.as_str();
                     let section_index = self.elf.find_section(section_name).unwrap();
                     symbol.set_index_type(symbol::IndexType::Index(section_index as u16));
                     symbol.value = addr as u64;
                 }
-                None => symbol.set_index_type(symbol::IndexType::Undef),
+                (None, Some(align)) => {
+                    symbol.set_index_type(symbol::IndexType::Common);
+                    symbol.value = align;
+                }
+                (None, None) => symbol.set_index_type(symbol::IndexType::Undef),
             }
             symbols.push(symbol);
 
@@ -170,11 +186,16 @@ impl ElfGen {
                 .symbols
                 .get(&rela_data.name)
                 .expect(&format!("cannot find symbol '{}'", rela_data.name));
-            match rela_data.typ {
-                RelaType::Pc32 => rela.set_info(*symbol_index as u64, rel::Type::Pc32),
-                RelaType::Plt32 => rela.set_info(*symbol_index as u64, rel::Type::Plt32),
-            }
-            rela.addend = -4;
+            let typ = match rela_data.typ {
+                RelaType::Pc32 => rel::Type::Pc32,
+                RelaType::Plt32 => rel::Type::Plt32,
+                RelaType::GotPcRel => rel::Type::GotPcRel,
+                RelaType::Abs64 => rel::Type::Abs64,
+                RelaType::Abs32 => rel::Type::Abs32,
+                RelaType::Abs32S => rel::Type::Abs32S,
+            };
+            rela.set_info(*symbol_index as u64, typ);
+            rela.addend = rela_data.addend;
             relas.push(rela);
         }
 
@@ -228,20 +249,22 @@ impl ElfGen {
     }
 }
 
-fn gen_data_header() -> SectionHeader {
+fn gen_section_header(section: &Section) -> SectionHeader {
     let mut header = SectionHeader::default();
-    header.set_type(section::Type::Progbits);
-    header.set_flags(section::Flags::Alloc);
-    header.set_flags(section::Flags::Write);
-    header.alignment = 1;
-    header
-}
-
-fn gen_text_header() -> SectionHeader {
-    let mut header = SectionHeader::default();
-    header.set_type(section::Type::Progbits);
-    header.set_flags(section::Flags::Alloc);
-    header.set_flags(section::Flags::Execinstr);
-    header.alignment = 1;
+    if section.attr.nobits {
+        header.set_type(section::Type::Nobits);
+    } else {
+        header.set_type(section::Type::Progbits);
+    }
+    if section.attr.alloc {
+        header.set_flags(section::Flags::Alloc);
+    }
+    if section.attr.write {
+        header.set_flags(section::Flags::Write);
+    }
+    if section.attr.exec {
+        header.set_flags(section::Flags::Execinstr);
+    }
+    header.alignment = section.align;
     header
 }
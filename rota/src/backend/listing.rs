@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use crate::backend::gen_code::{LineRange, Object, Rela, Section};
+
+const BYTES_PER_ROW: usize = 8;
+// long data such as `.zero 4096` is cut off after this many rows
+const MAX_ROWS: usize = 4;
+
+// lists each source line next to its section offset and encoded bytes
+pub fn generate(source: &str, obj: &Object) -> String {
+    let mut line_ranges: HashMap<usize, Vec<(&Section, &LineRange)>> = HashMap::new();
+    for section in &obj.sections {
+        for range in &section.lines {
+            line_ranges
+                .entry(range.line)
+                .or_default()
+                .push((section, range));
+        }
+    }
+
+    let mut listing = String::new();
+    for (index, text) in source.lines().enumerate() {
+        let line = index + 1;
+        let ranges = match line_ranges.get(&line) {
+            Some(ranges) => ranges,
+            None => {
+                push_row(&mut listing, &line.to_string(), "", "", "", text);
+                continue;
+            }
+        };
+
+        for (i, (section, range)) in ranges.iter().enumerate() {
+            let text = if i == 0 { text } else { "" };
+            list_range(&mut listing, line, section, range, text);
+        }
+    }
+    listing
+}
+
+fn list_range(listing: &mut String, line: usize, section: &Section, range: &LineRange, text: &str) {
+    // .bss has no content in the object file
+    let data = if section.attr.nobits {
+        &[]
+    } else {
+        &section.data[range.offset..range.offset + range.size]
+    };
+
+    let relas: Vec<String> = section
+        .relas
+        .iter()
+        .filter(|rela| (range.offset..range.offset + range.size).contains(&(rela.offset as usize)))
+        .map(format_rela)
+        .collect();
+
+    let mut rows: Vec<&[u8]> = data.chunks(BYTES_PER_ROW).take(MAX_ROWS).collect();
+    if rows.is_empty() {
+        rows.push(&[]);
+    }
+
+    for (i, row) in rows.into_iter().enumerate() {
+        let bytes: Vec<String> = row.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let offset = format!("{:04x}", range.offset + i * BYTES_PER_ROW);
+        if i == 0 {
+            let text = format!("{}  {}", text, relas.join(", "));
+            let line = line.to_string();
+            push_row(
+                listing,
+                &line,
+                section.name.as_str(),
+                &offset,
+                &bytes.join(" "),
+                &text,
+            );
+        } else {
+            push_row(listing, "", "", &offset, &bytes.join(" "), "");
+        }
+    }
+}
+
+fn push_row(
+    listing: &mut String,
+    line: &str,
+    section: &str,
+    offset: &str,
+    bytes: &str,
+    text: &str,
+) {
+    let row = format!(
+        "{:>4} {:<8} {:>4} {:<23} {}",
+        line, section, offset, bytes, text
+    );
+    listing.push_str(row.trim_end());
+    listing.push('\n');
+}
+
+// e.g. `R_X86_64_PC32 foo-4`
+fn format_rela(rela: &Rela) -> String {
+    if rela.addend == 0 {
+        format!("{} {}", rela.typ.as_str(), rela.name)
+    } else {
+        format!("{} {}{:+}", rela.typ.as_str(), rela.name, rela.addend)
+    }
+}
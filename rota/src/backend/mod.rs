@@ -1,2 +1,4 @@
+pub mod function_sections;
 pub mod gen_code;
 pub mod gen_elf;
+pub mod listing;
@@ -12,6 +12,9 @@ pub enum ErrorKind {
     UnexpectedChar {
         actual: char,
     },
+    InvalidInteger {
+        actual: String,
+    },
     UnexpectedToken {
         expected: Option<TokenKind>,
         actual: TokenKind,
@@ -28,6 +31,27 @@ pub enum ErrorKind {
     UnknownPseudoOp {
         name: String,
     },
+    UnknownMnemonic {
+        name: String,
+    },
+    InvalidSuffix {
+        mnemonic: String,
+    },
+    InvalidScale {
+        actual: i64,
+    },
+    InvalidAlignment {
+        actual: i64,
+        // whether `actual` is the log2 of the alignment, as for `.p2align`
+        log2: bool,
+    },
+    OperandSizeMismatch {
+        left: Register,
+        right: Register,
+    },
+    NonZeroInNobits {
+        section: String,
+    },
 
     UnexpectedMnemonic {
         actual: Mnemonic,
@@ -49,6 +73,7 @@ impl fmt::Display for ErrorKind {
         use ErrorKind::*;
         match self {
             UnexpectedChar { actual } => write!(f, "unexpected char: '{}'", actual),
+            InvalidInteger { actual } => write!(f, "invalid integer: '{}'", actual),
             UnexpectedToken { expected, actual } => {
                 write!(f, "unexpected {:?}", actual)?;
                 if let Some(expected) = expected {
@@ -60,6 +85,34 @@ impl fmt::Display for ErrorKind {
             ExpectedString { actual } => write!(f, "expected string, but got {:?}", actual),
             ExpectedIdent { actual } => write!(f, "expected identifier, but got {:?}", actual),
             UnknownPseudoOp { name } => write!(f, "unknown pseudo-op: '{}'", name),
+            UnknownMnemonic { name } => write!(f, "unknown mnemonic: '{}'", name),
+            InvalidSuffix { mnemonic } => {
+                write!(
+                    f,
+                    "operand size suffix does not match operands: '{}'",
+                    mnemonic
+                )
+            }
+            InvalidScale { actual } => write!(f, "scale factor must be 1, 2, 4 or 8: {}", actual),
+            InvalidAlignment {
+                actual,
+                log2: false,
+            } => {
+                write!(f, "alignment must be a power of 2 up to 2**31: {}", actual)
+            }
+            InvalidAlignment { actual, log2: true } => {
+                write!(f, "alignment must be 2**0 to 2**31: 2**{}", actual)
+            }
+            OperandSizeMismatch { left, right } => {
+                write!(f, "operand size mismatch: {:?} and {:?}", left, right)
+            }
+            NonZeroInNobits { section } => {
+                write!(
+                    f,
+                    "attempt to store non-zero value in section '{}'",
+                    section
+                )
+            }
 
             UnexpectedMnemonic { actual } => write!(f, "unexpected {:?}", actual),
             UnexpectedOperand { actual } => write!(f, "unexpected {:?}", actual),
@@ -77,10 +130,51 @@ pub struct Error {
     pub kind: ErrorKind,
 }
 
+impl ErrorKind {
+    pub fn hint(&self) -> Option<&str> {
+        use ErrorKind::*;
+        match self {
+            UnknownMnemonic { .. } => Some("not supported by x86asm"),
+            UnknownPseudoOp { .. } => Some("not supported by rota"),
+            InvalidSuffix { .. } => Some("operand size mismatch"),
+            OperandSizeMismatch { .. } => Some("both operands must be the same size"),
+            NonZeroInNobits { .. } => Some("only .zero and .lcomm can fill a nobits section"),
+            _ => None,
+        }
+    }
+}
+
 impl Error {
     pub fn new(pos: Pos, kind: ErrorKind) -> Self {
         Self { pos, kind }
     }
+
+    // renders the error with the source line and a caret at the position
+    pub fn render(&self, source: &str) -> String {
+        let line_number = self.pos.line.to_string();
+        let margin = " ".repeat(line_number.len());
+
+        let mut result = format!("error: {}\n", self.kind);
+        result.push_str(&format!("{}--> {}\n", margin, self.pos));
+
+        if let Some(line) = source.lines().nth(self.pos.line.wrapping_sub(1)) {
+            // keep tabs so that the caret lines up with the source line
+            let indent: String = line
+                .chars()
+                .take(self.pos.column.saturating_sub(1))
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+
+            result.push_str(&format!("{} |\n", margin));
+            result.push_str(&format!("{} | {}\n", line_number, line));
+            result.push_str(&format!("{} | {}^\n", margin, indent));
+        }
+        if let Some(hint) = self.kind.hint() {
+            result.push_str(&format!("{} = hint: {}\n", margin, hint));
+        }
+
+        result
+    }
 }
 
 impl fmt::Display for Error {
@@ -90,3 +184,42 @@ impl fmt::Display for Error {
 }
 
 impl error::Error for Error {}
+
+// all errors found in a stage, in source order
+#[derive(Debug)]
+pub struct Errors(pub Vec<Error>);
+
+impl Errors {
+    pub fn render(&self, source: &str) -> String {
+        let mut result = String::new();
+        for err in &self.0 {
+            result.push_str(&err.render(source));
+            result.push('\n');
+        }
+
+        let count = self.0.len();
+        let plural = if count == 1 { "" } else { "s" };
+        result.push_str(&format!("{} error{} generated\n", count, plural));
+        result
+    }
+}
+
+impl From<Error> for Errors {
+    fn from(err: Error) -> Self {
+        Errors(vec![err])
+    }
+}
+
+impl fmt::Display for Errors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, err) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", err)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for Errors {}
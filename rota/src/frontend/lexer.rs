@@ -4,7 +4,7 @@ use x86asm::instruction::{mnemonic::Mnemonic, operand::register::Register};
 
 use crate::{
     common::{
-        error::{Error, ErrorKind},
+        error::{Error, ErrorKind, Errors},
         pos::Pos,
     },
     frontend::lexer::token::{Symbol, Token, TokenKind},
@@ -24,7 +24,17 @@ pub struct SourceFile {
     pub content: String,
 }
 
-pub fn tokenize(source: SourceFile) -> Result<Vec<Token>, Error> {
+pub fn tokenize(source: SourceFile) -> Result<Vec<Token>, Errors> {
+    let (tokens, errors) = tokenize_recovering(source);
+    if errors.is_empty() {
+        Ok(tokens)
+    } else {
+        Err(Errors(errors))
+    }
+}
+
+// tokenizes all lines, leaving out the lines with errors
+pub fn tokenize_recovering(source: SourceFile) -> (Vec<Token>, Vec<Error>) {
     let mut lexer = Lexer::new(source);
     lexer.tokenize()
 }
@@ -44,12 +54,25 @@ impl Lexer {
         }
     }
 
-    fn tokenize(&mut self) -> Result<Vec<Token>, Error> {
+    fn tokenize(&mut self) -> (Vec<Token>, Vec<Error>) {
         let mut tokens = Vec::new();
+        let mut errors = Vec::new();
 
         while !self.is_eof() {
             self.consume_whitespace();
-            tokens.push(self.next_token()?);
+            match self.next_token() {
+                Ok(token) => tokens.push(token),
+                Err(err) => {
+                    while tokens
+                        .last()
+                        .is_some_and(|token: &Token| token.pos.line == err.pos.line)
+                    {
+                        tokens.pop();
+                    }
+                    errors.push(err);
+                    self.skip_line();
+                }
+            }
         }
 
         tokens.push(Token {
@@ -57,7 +80,7 @@ impl Lexer {
             pos: self.pos.clone(),
         });
 
-        Ok(tokens)
+        (tokens, errors)
     }
 
     fn next_token(&mut self) -> Result<Token, Error> {
@@ -71,7 +94,7 @@ impl Lexer {
         let pos = self.pos.clone();
         let kind = match self.peek_char() {
             '"' => self.consume_string_literal(),
-            x if x.is_digit(10) => self.consume_number(),
+            x if x.is_digit(10) => self.consume_number()?,
             x if is_ident(x) => find_keyword(self.consume_ident()),
             _ => self.consume_symbol()?,
         };
@@ -108,15 +131,28 @@ impl Lexer {
         }
     }
 
-    fn consume_number(&mut self) -> TokenKind {
+    fn consume_number(&mut self) -> Result<TokenKind, Error> {
+        let pos = self.pos.clone();
         let mut result = String::new();
 
-        while !self.is_eof() && self.peek_char().is_digit(10) {
+        while !self.is_eof() && self.peek_char().is_ascii_alphanumeric() {
             result.push(self.consume_char());
         }
 
-        let value = result.parse().unwrap();
-        TokenKind::Integer(value)
+        let value = match result
+            .strip_prefix("0x")
+            .or_else(|| result.strip_prefix("0X"))
+        {
+            Some(hex) => u64::from_str_radix(hex, 16).map(|value| value as i64),
+            None => result.parse(),
+        };
+        match value {
+            Ok(value) => Ok(TokenKind::Integer(value)),
+            Err(_) => Err(Error::new(
+                pos,
+                ErrorKind::InvalidInteger { actual: result },
+            )),
+        }
     }
 
     fn consume_ident(&mut self) -> TokenKind {
@@ -129,6 +165,7 @@ impl Lexer {
     }
 
     fn consume_symbol(&mut self) -> Result<TokenKind, Error> {
+        let pos = self.pos.clone();
         let symbol = match self.consume_char() {
             ',' => Symbol::Comma,
             ':' => Symbol::Colon,
@@ -136,16 +173,18 @@ impl Lexer {
             ']' => Symbol::RBracket,
             '+' => Symbol::Plus,
             '-' => Symbol::Minus,
+            '@' => Symbol::At,
+            '%' => Symbol::Percent,
+            '$' => Symbol::Dollar,
+            '(' => Symbol::LParen,
+            ')' => Symbol::RParen,
+            '*' => Symbol::Star,
             ';' => {
                 self.consume_char();
                 return Ok(self.consume_comment());
             }
-            x => {
-                return Err(Error::new(
-                    self.pos.clone(),
-                    ErrorKind::UnexpectedChar { actual: x },
-                ))
-            }
+            '#' => return Ok(self.consume_comment()),
+            x => return Err(Error::new(pos, ErrorKind::UnexpectedChar { actual: x })),
         };
 
         Ok(TokenKind::Symbol(symbol))
@@ -160,6 +199,13 @@ impl Lexer {
         TokenKind::Comment(content)
     }
 
+    // skips the rest of the line to recover from an error
+    fn skip_line(&mut self) {
+        while !self.is_eof() && self.peek_char() != '\n' {
+            self.consume_char();
+        }
+    }
+
     fn consume_whitespace(&mut self) {
         while !self.is_eof() && self.peek_char().is_whitespace() {
             self.consume_char();
@@ -201,7 +247,7 @@ fn is_ident(c: char) -> bool {
     }
 }
 
-fn find_keyword(ident: TokenKind) -> TokenKind {
+pub(crate) fn find_keyword(ident: TokenKind) -> TokenKind {
     let name = match ident {
         TokenKind::Ident(ref name) => name,
         x => return x,
@@ -227,12 +273,15 @@ fn find_keyword(ident: TokenKind) -> TokenKind {
         "pop" => TokenKind::Mnemonic(Mnemonic::Pop),
         "push" => TokenKind::Mnemonic(Mnemonic::Push),
         "ret" => TokenKind::Mnemonic(Mnemonic::Ret),
+        "sar" => TokenKind::Mnemonic(Mnemonic::Sar),
         "sete" => TokenKind::Mnemonic(Mnemonic::Sete),
         "setg" => TokenKind::Mnemonic(Mnemonic::Setg),
         "setge" => TokenKind::Mnemonic(Mnemonic::Setge),
         "setl" => TokenKind::Mnemonic(Mnemonic::Setl),
         "setle" => TokenKind::Mnemonic(Mnemonic::Setle),
         "setne" => TokenKind::Mnemonic(Mnemonic::Setne),
+        "shl" => TokenKind::Mnemonic(Mnemonic::Shl),
+        "shr" => TokenKind::Mnemonic(Mnemonic::Shr),
         "sub" => TokenKind::Mnemonic(Mnemonic::Sub),
         "syscall" => TokenKind::Mnemonic(Mnemonic::Syscall),
         "xor" => TokenKind::Mnemonic(Mnemonic::Xor),
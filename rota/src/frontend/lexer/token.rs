@@ -16,7 +16,7 @@ impl Token {
 
 #[derive(Eq, PartialEq, Debug, Clone)]
 pub enum TokenKind {
-    Integer(i32),
+    Integer(i64),
     String(String),
     Ident(String),
     Symbol(Symbol),
@@ -35,6 +35,12 @@ pub enum Symbol {
     RBracket,
     Plus,
     Minus,
+    At,
+    Percent,
+    Dollar,
+    LParen,
+    RParen,
+    Star,
 }
 
 #[derive(Eq, PartialEq, Debug, Clone)]
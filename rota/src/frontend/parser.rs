@@ -1,34 +1,63 @@
+mod att;
 pub mod node;
 
-use x86asm::instruction::mnemonic;
+use x86asm::instruction::{
+    mnemonic::{self, Mnemonic},
+    operand::register::Register,
+};
 
 use crate::{
-    common::error::{Error, ErrorKind},
+    common::{
+        error::{Error, ErrorKind, Errors},
+        pos::Pos,
+    },
     frontend::{
         lexer::token::{Keyword, Symbol, Token, TokenKind},
         parser::node::{
             DispNode, InstructionNode, MemoryNode, OperandNode, Program, PseudoOp, PseudoOpArg,
+            SymbolNode,
         },
     },
 };
 
+// the largest alignment is 2**31
+const MAX_ALIGN_LOG2: i64 = 31;
+
 struct Parser {
     pos: usize,
     tokens: Vec<Token>,
+    syntax: Syntax,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Syntax {
+    Intel,
+    Att,
+}
+
+pub fn parse(tokens: Vec<Token>) -> Result<Program, Errors> {
+    parse_with_syntax(tokens, Syntax::Intel)
 }
 
-pub fn parse(tokens: Vec<Token>) -> Result<Program, Error> {
-    let mut parser = Parser::new(tokens);
+// `syntax` is the initial syntax, which can be switched by `.intel_syntax` and `.att_syntax`
+pub fn parse_with_syntax(tokens: Vec<Token>, syntax: Syntax) -> Result<Program, Errors> {
+    let mut parser = Parser::new(tokens, syntax);
     parser.parse()
 }
 
 impl Parser {
-    fn new(tokens: Vec<Token>) -> Self {
-        Self { pos: 0, tokens }
+    fn new(tokens: Vec<Token>, syntax: Syntax) -> Self {
+        Self {
+            pos: 0,
+            tokens,
+            syntax,
+        }
     }
 
-    fn parse(&mut self) -> Result<Program, Error> {
+    fn parse(&mut self) -> Result<Program, Errors> {
         let mut insts = Vec::new();
+        let mut positions = Vec::new();
+        let mut errors = Vec::new();
         loop {
             if self.is_eof() {
                 break;
@@ -39,32 +68,72 @@ impl Parser {
                 continue;
             }
 
-            if !matches!(self.peek().kind, TokenKind::Ident(_)) {
-                insts.push(self.parse_inst()?);
-                continue;
+            let pos = self.peek().pos.clone();
+            let inst = self
+                .parse_statement()
+                .and_then(|inst| check_operand_size(&pos, inst));
+            match inst {
+                Ok(inst) => {
+                    insts.push(inst);
+                    positions.push(pos);
+                }
+                Err(err) => {
+                    errors.push(err);
+                    self.skip_line(pos.line);
+                }
             }
+        }
+
+        if errors.is_empty() {
+            Ok(Program { insts, positions })
+        } else {
+            Err(Errors(errors))
+        }
+    }
 
-            let ident_token = self.peek().clone();
-            let ident = self.consume_ident()?;
+    // skips the tokens up to the end of the line where parsing failed
+    fn skip_line(&mut self, start_line: usize) {
+        let last_line = self.tokens[self.pos.saturating_sub(1)].pos.line;
+        let line = start_line.max(last_line);
+        while !self.is_eof() && self.peek().pos.line <= line {
+            self.consume();
+        }
+    }
 
-            if self.peek().kind == TokenKind::Symbol(Symbol::Colon) {
-                self.consume();
-                insts.push(InstructionNode::Label(ident));
-                continue;
-            }
+    fn parse_statement(&mut self) -> Result<InstructionNode, Error> {
+        if !matches!(self.peek().kind, TokenKind::Ident(_)) {
+            return self.parse_inst();
+        }
 
-            if ident.starts_with('.') {
-                insts.push(self.parse_pseudop(ident_token)?);
-                continue;
-            }
+        let ident_token = self.peek().clone();
+        let ident = self.consume_ident()?;
+
+        if self.peek().kind == TokenKind::Symbol(Symbol::Colon) {
+            self.consume();
+            return Ok(InstructionNode::Label(ident));
+        }
 
-            return Err(unexpected(ident_token));
+        if ident.starts_with('.') {
+            return self.parse_pseudop(ident_token);
         }
-        Ok(Program { insts })
+
+        if self.syntax == Syntax::Att {
+            // mnemonics with an operand size suffix, e.g. `movq`
+            return self.parse_att_inst(ident_token);
+        }
+
+        Err(Error::new(
+            ident_token.pos,
+            ErrorKind::UnknownMnemonic { name: ident },
+        ))
     }
 
     fn parse_inst(&mut self) -> Result<InstructionNode, Error> {
-        let token = self.consume().clone();
+        let token = self.consume();
+        if self.syntax == Syntax::Att {
+            return self.parse_att_inst(token);
+        }
+
         match token.kind {
             TokenKind::Mnemonic(mnemonic) => match mnemonic.typ() {
                 mnemonic::Type::Nullary => Ok(InstructionNode::NullaryOp(mnemonic)),
@@ -86,11 +155,13 @@ impl Parser {
     fn parse_operand(&mut self) -> Result<OperandNode, Error> {
         let token = self.consume();
         match token.kind {
-            TokenKind::Symbol(Symbol::Plus) => Ok(OperandNode::Immidiate(self.consume_integer()?)),
+            TokenKind::Symbol(Symbol::Plus) => {
+                Ok(OperandNode::Immidiate(self.consume_integer()? as i32))
+            }
             TokenKind::Symbol(Symbol::Minus) => {
-                Ok(OperandNode::Immidiate(-self.consume_integer()?))
+                Ok(OperandNode::Immidiate(-self.consume_integer()? as i32))
             }
-            TokenKind::Integer(value) => Ok(OperandNode::Immidiate(value)),
+            TokenKind::Integer(value) => Ok(OperandNode::Immidiate(value as i32)),
             TokenKind::Ident(name) => Ok(OperandNode::Label(name.to_owned())),
             TokenKind::Register(reg) => Ok(OperandNode::Register(reg.to_owned())),
             TokenKind::Symbol(Symbol::LBracket) => self.parse_operand_address(),
@@ -108,6 +179,16 @@ impl Parser {
         let token = self.consume();
         let base = match token.kind {
             TokenKind::Register(reg) => reg.clone(),
+            // absolute address, e.g. `[symbol+8]`
+            TokenKind::Ident(name) => {
+                let disp = DispNode::Label(self.parse_symbol(name)?);
+                self.expect(TokenKind::Symbol(Symbol::RBracket))?;
+                return Ok(OperandNode::Memory(MemoryNode {
+                    base: None,
+                    index: None,
+                    disp: Some(disp),
+                }));
+            }
             _ => return Err(unexpected(token)),
         };
 
@@ -117,14 +198,14 @@ impl Parser {
             TokenKind::Symbol(Symbol::RBracket) => None,
             TokenKind::Symbol(Symbol::Minus) => {
                 self.consume();
-                Some(DispNode::Immediate(-self.consume_integer()?))
+                Some(DispNode::Immediate(-self.consume_integer()? as i32))
             }
             TokenKind::Symbol(Symbol::Plus) => {
                 self.consume();
                 let token = self.consume();
                 match token.kind {
-                    TokenKind::Integer(value) => Some(DispNode::Immediate(value)),
-                    TokenKind::Ident(name) => Some(DispNode::Label(name)),
+                    TokenKind::Integer(value) => Some(DispNode::Immediate(value as i32)),
+                    TokenKind::Ident(name) => Some(DispNode::Label(self.parse_symbol(name)?)),
                     _ => return Err(unexpected(token)),
                 }
             }
@@ -132,7 +213,11 @@ impl Parser {
         };
 
         self.expect(TokenKind::Symbol(Symbol::RBracket))?;
-        Ok(OperandNode::Memory(MemoryNode { base, disp }))
+        Ok(OperandNode::Memory(MemoryNode {
+            base: Some(base),
+            index: None,
+            disp,
+        }))
     }
 
     fn parse_pseudop(&mut self, ident_token: Token) -> Result<InstructionNode, Error> {
@@ -147,19 +232,164 @@ impl Parser {
                 args.push(PseudoOpArg::Integer(self.consume_signed_integer()?));
                 args
             }
-            PseudoOp::IntelSyntax | PseudoOp::Global => {
+            PseudoOp::IntelSyntax => {
+                self.syntax = Syntax::Intel;
                 vec![PseudoOpArg::String(self.consume_ident()?)]
             }
+            PseudoOp::AttSyntax => {
+                self.syntax = Syntax::Att;
+                vec![]
+            }
+            PseudoOp::Global => vec![PseudoOpArg::String(self.consume_ident()?)],
+            PseudoOp::Local => {
+                self.parse_arg_list(|p| Ok(PseudoOpArg::String(p.consume_ident()?)))?
+            }
             PseudoOp::Zero => vec![PseudoOpArg::Integer(self.consume_integer()?)],
-            PseudoOp::Ascii => vec![PseudoOpArg::String(self.consume_string()?)],
-            PseudoOp::Byte => vec![PseudoOpArg::Integer(self.consume_signed_integer()?)],
-            PseudoOp::Long => vec![PseudoOpArg::Integer(self.consume_signed_integer()?)],
+            PseudoOp::Ascii | PseudoOp::Asciz => {
+                self.parse_arg_list(|p| Ok(PseudoOpArg::String(p.consume_string()?)))?
+            }
+            PseudoOp::Byte | PseudoOp::Word => {
+                self.parse_arg_list(|p| Ok(PseudoOpArg::Integer(p.consume_signed_integer()?)))?
+            }
+            PseudoOp::Long | PseudoOp::Quad => self.parse_arg_list(Self::parse_data_arg)?,
+            PseudoOp::Align | PseudoOp::P2align => {
+                self.parse_align_args(op == PseudoOp::P2align)?
+            }
+            PseudoOp::Section => self.parse_section_args()?,
+            PseudoOp::Comm | PseudoOp::Lcomm => {
+                let mut args = Vec::new();
+                args.push(PseudoOpArg::String(self.consume_ident()?));
+                self.expect(TokenKind::Symbol(Symbol::Comma))?;
+                args.push(PseudoOpArg::Integer(self.consume_integer()?));
+                if self.consume_if(TokenKind::Symbol(Symbol::Comma)) {
+                    args.push(PseudoOpArg::Integer(self.consume_integer()?));
+                }
+                args
+            }
+            PseudoOp::Type => {
+                let mut args = Vec::new();
+                args.push(PseudoOpArg::String(self.consume_ident()?));
+                self.expect(TokenKind::Symbol(Symbol::Comma))?;
+                self.consume_if(TokenKind::Symbol(Symbol::At));
+                args.push(PseudoOpArg::String(self.consume_ident()?));
+                args
+            }
+            PseudoOp::Size => {
+                let mut args = Vec::new();
+                args.push(PseudoOpArg::String(self.consume_ident()?));
+                self.expect(TokenKind::Symbol(Symbol::Comma))?;
+                if self.consume_if(TokenKind::Ident(".".into())) {
+                    self.expect(TokenKind::Symbol(Symbol::Minus))?;
+                    args.push(PseudoOpArg::Distance(self.consume_ident()?));
+                } else {
+                    args.push(PseudoOpArg::Integer(self.consume_integer()?));
+                }
+                args
+            }
             _ => vec![],
         };
 
         Ok(InstructionNode::PseudoOp(op, args))
     }
 
+    fn parse_arg_list<F>(&mut self, parse_arg: F) -> Result<Vec<PseudoOpArg>, Error>
+    where
+        F: Fn(&mut Self) -> Result<PseudoOpArg, Error>,
+    {
+        let mut args = vec![parse_arg(self)?];
+        while self.consume_if(TokenKind::Symbol(Symbol::Comma)) {
+            args.push(parse_arg(self)?);
+        }
+        Ok(args)
+    }
+
+    // an integer or the address of a symbol
+    fn parse_data_arg(&mut self) -> Result<PseudoOpArg, Error> {
+        if let TokenKind::Ident(name) = self.peek().kind {
+            self.consume();
+            return Ok(PseudoOpArg::Symbol(self.parse_symbol(name)?));
+        }
+        Ok(PseudoOpArg::Integer(self.consume_signed_integer()?))
+    }
+
+    // the rest of `symbol+addend`, `symbol-addend` or `symbol@GOTPCREL` after the name
+    fn parse_symbol(&mut self, name: String) -> Result<SymbolNode, Error> {
+        let addend = if self.consume_if(TokenKind::Symbol(Symbol::Plus)) {
+            self.consume_integer()?
+        } else if self.consume_if(TokenKind::Symbol(Symbol::Minus)) {
+            -self.consume_integer()?
+        } else {
+            0
+        };
+
+        let gotpcrel = self.consume_if(TokenKind::Symbol(Symbol::At));
+        if gotpcrel {
+            let token = self.consume();
+            if token.kind != TokenKind::Ident("GOTPCREL".into()) {
+                return Err(unexpected(token));
+            }
+        }
+
+        Ok(SymbolNode {
+            name,
+            addend,
+            gotpcrel,
+        })
+    }
+
+    // .align/.p2align alignment[, [fill][, max]]
+    // the maximum number of padding bytes is accepted but not honoured
+    // `.align` takes the alignment and `.p2align` its log2
+    fn parse_align_args(&mut self, log2: bool) -> Result<Vec<PseudoOpArg>, Error> {
+        let pos = self.peek().pos;
+        let value = self.consume_integer()?;
+        let valid = if log2 {
+            (0..=MAX_ALIGN_LOG2).contains(&value)
+        } else {
+            value == 0 || (value as u64).is_power_of_two() && value <= 1 << MAX_ALIGN_LOG2
+        };
+        if !valid {
+            return Err(Error::new(
+                pos,
+                ErrorKind::InvalidAlignment {
+                    actual: value,
+                    log2,
+                },
+            ));
+        }
+
+        let mut args = vec![PseudoOpArg::Integer(value)];
+        if !self.consume_if(TokenKind::Symbol(Symbol::Comma)) {
+            return Ok(args);
+        }
+        if !matches!(self.peek().kind, TokenKind::Symbol(Symbol::Comma)) {
+            args.push(PseudoOpArg::Integer(self.consume_signed_integer()?));
+        }
+        if self.consume_if(TokenKind::Symbol(Symbol::Comma)) {
+            self.consume_integer()?;
+        }
+        Ok(args)
+    }
+
+    // .section name[, "flags"[, @type]]
+    fn parse_section_args(&mut self) -> Result<Vec<PseudoOpArg>, Error> {
+        let token = self.consume();
+        let name = match token.kind {
+            TokenKind::Ident(name) | TokenKind::String(name) => name,
+            _ => return Err(unexpected(token)),
+        };
+
+        let mut args = vec![PseudoOpArg::String(name)];
+        if self.consume_if(TokenKind::Symbol(Symbol::Comma)) {
+            args.push(PseudoOpArg::String(self.consume_string()?));
+            if self.consume_if(TokenKind::Symbol(Symbol::Comma)) {
+                self.expect(TokenKind::Symbol(Symbol::At))?;
+                args.push(PseudoOpArg::String(self.consume_ident()?));
+            }
+        }
+        Ok(args)
+    }
+
     fn expect(&mut self, token: TokenKind) -> Result<Token, Error> {
         let next_token = self.consume();
         if next_token.kind == token {
@@ -175,7 +405,16 @@ impl Parser {
         }
     }
 
-    fn consume_integer(&mut self) -> Result<i32, Error> {
+    fn consume_if(&mut self, token: TokenKind) -> bool {
+        if self.peek().kind == token {
+            self.consume();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn consume_integer(&mut self) -> Result<i64, Error> {
         let next_token = self.consume();
         match next_token.kind {
             TokenKind::Integer(value) => Ok(value),
@@ -186,7 +425,7 @@ impl Parser {
         }
     }
 
-    fn consume_signed_integer(&mut self) -> Result<i32, Error> {
+    fn consume_signed_integer(&mut self) -> Result<i64, Error> {
         let next_token = self.consume();
         match next_token.kind {
             TokenKind::Symbol(Symbol::Minus) => Ok(-self.consume_integer()?),
@@ -212,6 +451,14 @@ impl Parser {
         }
     }
 
+    fn consume_register(&mut self) -> Result<Register, Error> {
+        let next_token = self.consume();
+        match next_token.kind {
+            TokenKind::Register(reg) => Ok(reg),
+            _ => Err(unexpected(next_token)),
+        }
+    }
+
     fn consume_ident(&mut self) -> Result<String, Error> {
         let next_token = self.consume();
         match next_token.kind {
@@ -226,7 +473,8 @@ impl Parser {
     fn consume(&mut self) -> Token {
         let token = self.tokens.get(self.pos).unwrap();
 
-        if self.pos < self.tokens.len() {
+        // stay at EOF
+        if self.pos < self.tokens.len() - 1 {
             self.pos += 1;
         }
 
@@ -249,8 +497,9 @@ fn find_pseudoop(ident: Token) -> Result<PseudoOp, Error> {
     };
 
     match name.as_str() {
-        ".global" => Ok(PseudoOp::Global),
+        ".global" | ".globl" => Ok(PseudoOp::Global),
         ".intel_syntax" => Ok(PseudoOp::IntelSyntax),
+        ".att_syntax" => Ok(PseudoOp::AttSyntax),
         ".data" => Ok(PseudoOp::Data),
         ".text" => Ok(PseudoOp::Text),
         ".zero" => Ok(PseudoOp::Zero),
@@ -258,6 +507,19 @@ fn find_pseudoop(ident: Token) -> Result<PseudoOp, Error> {
         ".tse" => Ok(PseudoOp::Tse),
         ".byte" => Ok(PseudoOp::Byte),
         ".long" => Ok(PseudoOp::Long),
+        ".section" => Ok(PseudoOp::Section),
+        ".rodata" => Ok(PseudoOp::Rodata),
+        ".bss" => Ok(PseudoOp::Bss),
+        ".quad" => Ok(PseudoOp::Quad),
+        ".word" => Ok(PseudoOp::Word),
+        ".asciz" | ".string" => Ok(PseudoOp::Asciz),
+        ".align" => Ok(PseudoOp::Align),
+        ".p2align" => Ok(PseudoOp::P2align),
+        ".comm" => Ok(PseudoOp::Comm),
+        ".lcomm" => Ok(PseudoOp::Lcomm),
+        ".type" => Ok(PseudoOp::Type),
+        ".size" => Ok(PseudoOp::Size),
+        ".local" => Ok(PseudoOp::Local),
         x => Err(Error::new(
             ident.pos,
             ErrorKind::UnknownPseudoOp {
@@ -267,6 +529,43 @@ fn find_pseudoop(ident: Token) -> Result<PseudoOp, Error> {
     }
 }
 
+// registers of different sizes can be mixed only by `movsx`, and by shifts
+// which take the count in `cl`
+fn check_operand_size(pos: &Pos, inst: InstructionNode) -> Result<InstructionNode, Error> {
+    if let InstructionNode::BinaryOp(
+        mnemonic,
+        OperandNode::Register(left),
+        OperandNode::Register(right),
+    ) = &inst
+    {
+        if is_shift(mnemonic) {
+            if *right != Register::Cl {
+                return Err(Error::new(
+                    pos.clone(),
+                    ErrorKind::UnexpectedRegister {
+                        actual: right.clone(),
+                    },
+                ));
+            }
+            return Ok(inst);
+        }
+        if *mnemonic != Mnemonic::Movsx && left.size() != right.size() {
+            return Err(Error::new(
+                pos.clone(),
+                ErrorKind::OperandSizeMismatch {
+                    left: left.clone(),
+                    right: right.clone(),
+                },
+            ));
+        }
+    }
+    Ok(inst)
+}
+
+fn is_shift(mnemonic: &Mnemonic) -> bool {
+    matches!(mnemonic, Mnemonic::Sar | Mnemonic::Shl | Mnemonic::Shr)
+}
+
 fn unexpected(token: Token) -> Error {
     Error::new(
         token.pos,
@@ -0,0 +1,224 @@
+use x86asm::instruction::{
+    mnemonic::{self, Mnemonic},
+    operand::{memory::Index, register},
+};
+
+use crate::{
+    common::error::{Error, ErrorKind},
+    frontend::{
+        lexer::{
+            find_keyword,
+            token::{Symbol, Token, TokenKind},
+        },
+        parser::{
+            is_shift,
+            node::{DispNode, InstructionNode, MemoryNode, OperandNode, SymbolNode},
+            unexpected, Parser,
+        },
+    },
+};
+
+impl Parser {
+    // operands are written in the reverse order of Intel syntax,
+    // so they are swapped here to share the backend
+    pub(super) fn parse_att_inst(&mut self, token: Token) -> Result<InstructionNode, Error> {
+        let (mnemonic, suffix) = match token.kind {
+            TokenKind::Mnemonic(ref mnemonic) => (mnemonic.clone(), None),
+            TokenKind::Ident(ref name) => match split_suffix(name) {
+                Some((mnemonic, size)) => (mnemonic, Some((name.clone(), size))),
+                None => {
+                    return Err(Error::new(
+                        token.pos,
+                        ErrorKind::UnknownMnemonic { name: name.clone() },
+                    ))
+                }
+            },
+            _ => return Err(unexpected(token)),
+        };
+
+        let inst = match mnemonic.typ() {
+            mnemonic::Type::Nullary => InstructionNode::NullaryOp(mnemonic),
+            mnemonic::Type::Unary => {
+                let operand1 = self.parse_att_operand()?;
+                if is_branch(&mnemonic) {
+                    InstructionNode::UnaryOp(mnemonic, operand1)
+                } else {
+                    InstructionNode::UnaryOp(mnemonic, label_to_memory(operand1))
+                }
+            }
+            mnemonic::Type::Binary => {
+                let src = label_to_memory(self.parse_att_operand()?);
+                self.expect(TokenKind::Symbol(Symbol::Comma))?;
+                let dst = label_to_memory(self.parse_att_operand()?);
+                InstructionNode::BinaryOp(mnemonic, dst, src)
+            }
+        };
+
+        if let Some((mnemonic, size)) = suffix {
+            if !suffix_matches(&inst, &size) {
+                return Err(Error::new(token.pos, ErrorKind::InvalidSuffix { mnemonic }));
+            }
+        }
+
+        Ok(inst)
+    }
+
+    fn parse_att_operand(&mut self) -> Result<OperandNode, Error> {
+        let token = self.consume();
+        match token.kind {
+            TokenKind::Symbol(Symbol::Dollar) => match self.peek().kind {
+                TokenKind::Ident(name) => {
+                    self.consume();
+                    Ok(OperandNode::SymbolImmediate(self.parse_symbol(name)?))
+                }
+                _ => Ok(OperandNode::Immidiate(self.consume_signed_integer()? as i32)),
+            },
+            TokenKind::Symbol(Symbol::Percent) => {
+                Ok(OperandNode::Register(self.consume_register()?))
+            }
+            // indirect jump and call, e.g. `call *%rax`, where a bare symbol
+            // is the address of the target rather than the target itself
+            TokenKind::Symbol(Symbol::Star) => Ok(label_to_memory(self.parse_att_operand()?)),
+            TokenKind::Symbol(Symbol::LParen) => self.parse_att_address(None),
+            TokenKind::Symbol(Symbol::Minus) => {
+                let disp = -self.consume_integer()?;
+                self.parse_att_memory(DispNode::Immediate(disp as i32))
+            }
+            TokenKind::Integer(disp) => self.parse_att_memory(DispNode::Immediate(disp as i32)),
+            TokenKind::Ident(name) => {
+                let symbol = self.parse_symbol(name)?;
+                let is_plain = symbol.addend == 0 && !symbol.gotpcrel;
+                if is_plain && self.peek().kind != TokenKind::Symbol(Symbol::LParen) {
+                    // a jump target, or an absolute address as in `movq symbol, %rax`
+                    Ok(OperandNode::Label(symbol.name))
+                } else {
+                    self.parse_att_memory(DispNode::Label(symbol))
+                }
+            }
+            _ => Err(unexpected(token)),
+        }
+    }
+
+    // `disp` or `disp(base, index, scale)`
+    fn parse_att_memory(&mut self, disp: DispNode) -> Result<OperandNode, Error> {
+        if self.consume_if(TokenKind::Symbol(Symbol::LParen)) {
+            return self.parse_att_address(Some(disp));
+        }
+
+        Ok(OperandNode::Memory(MemoryNode {
+            base: None,
+            index: None,
+            disp: Some(disp),
+        }))
+    }
+
+    // `(base, index, scale)` after the opening parenthesis, where every part is optional
+    fn parse_att_address(&mut self, disp: Option<DispNode>) -> Result<OperandNode, Error> {
+        let base = if self.peek().kind == TokenKind::Symbol(Symbol::Comma) {
+            None
+        } else {
+            self.expect(TokenKind::Symbol(Symbol::Percent))?;
+            Some(self.consume_register()?)
+        };
+
+        let index = if self.consume_if(TokenKind::Symbol(Symbol::Comma)) {
+            self.expect(TokenKind::Symbol(Symbol::Percent))?;
+            let reg = self.consume_register()?;
+            let scale = if self.consume_if(TokenKind::Symbol(Symbol::Comma)) {
+                self.consume_scale()?
+            } else {
+                1
+            };
+            Some(Index::new(reg, scale))
+        } else {
+            None
+        };
+
+        self.expect(TokenKind::Symbol(Symbol::RParen))?;
+        Ok(OperandNode::Memory(MemoryNode { base, index, disp }))
+    }
+
+    fn consume_scale(&mut self) -> Result<u8, Error> {
+        let pos = self.peek().pos;
+        match self.consume_integer()? {
+            scale @ (1 | 2 | 4 | 8) => Ok(scale as u8),
+            actual => Err(Error::new(pos, ErrorKind::InvalidScale { actual })),
+        }
+    }
+}
+
+fn is_branch(mnemonic: &Mnemonic) -> bool {
+    matches!(mnemonic, Mnemonic::Call | Mnemonic::Jmp | Mnemonic::Je)
+}
+
+// a bare symbol is an absolute memory operand unless it is a jump target
+fn label_to_memory(operand: OperandNode) -> OperandNode {
+    match operand {
+        OperandNode::Label(name) => OperandNode::Memory(MemoryNode {
+            base: None,
+            index: None,
+            disp: Some(DispNode::Label(SymbolNode {
+                name,
+                addend: 0,
+                gotpcrel: false,
+            })),
+        }),
+        operand => operand,
+    }
+}
+
+fn split_suffix(name: &str) -> Option<(Mnemonic, register::Size)> {
+    // `movs` + `b` + the size of the destination, e.g. `movsbq`
+    if let Some(suffix) = name.strip_prefix("movsb") {
+        return Some((Mnemonic::Movsx, suffix_size(suffix)?));
+    }
+
+    let (suffix_pos, _) = name.char_indices().last()?;
+    let (name, suffix) = name.split_at(suffix_pos);
+    match find_keyword(TokenKind::Ident(name.to_string())) {
+        TokenKind::Mnemonic(mnemonic) => Some((mnemonic, suffix_size(suffix)?)),
+        _ => None,
+    }
+}
+
+fn suffix_size(suffix: &str) -> Option<register::Size> {
+    match suffix {
+        "b" => Some(register::Size::Byte),
+        "w" => Some(register::Size::Word),
+        "l" => Some(register::Size::DWord),
+        "q" => Some(register::Size::QWord),
+        _ => None,
+    }
+}
+
+fn suffix_matches(inst: &InstructionNode, size: &register::Size) -> bool {
+    let operands = match inst {
+        InstructionNode::UnaryOp(_, opr1) => vec![opr1],
+        // the suffix of movsx and shifts is the size of the destination
+        InstructionNode::BinaryOp(Mnemonic::Movsx, opr1, _) => vec![opr1],
+        InstructionNode::BinaryOp(mnemonic, opr1, _) if is_shift(mnemonic) => vec![opr1],
+        InstructionNode::BinaryOp(_, opr1, opr2) => vec![opr1, opr2],
+        _ => vec![],
+    };
+
+    let registers: Vec<_> = operands
+        .iter()
+        .filter_map(|opr| match opr {
+            OperandNode::Register(reg) => Some(reg),
+            _ => None,
+        })
+        .collect();
+    if !registers.is_empty() {
+        return registers.iter().all(|reg| reg.size() == *size);
+    }
+
+    // the backend always accesses memory without a register operand as a qword
+    if operands
+        .iter()
+        .any(|opr| matches!(opr, OperandNode::Memory(_)))
+    {
+        return *size == register::Size::QWord;
+    }
+
+    true
+}
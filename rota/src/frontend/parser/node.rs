@@ -1,7 +1,14 @@
-use x86asm::instruction::{mnemonic::Mnemonic, operand::register::Register};
+use x86asm::instruction::{
+    mnemonic::Mnemonic,
+    operand::{memory::Index, register::Register},
+};
+
+use crate::common::pos::Pos;
 
 pub struct Program {
     pub insts: Vec<InstructionNode>,
+    // position where each instruction starts
+    pub positions: Vec<Pos>,
 }
 
 #[derive(Debug)]
@@ -17,6 +24,7 @@ pub enum InstructionNode {
 pub enum PseudoOp {
     Global,
     IntelSyntax,
+    AttSyntax,
     Data,
     Text,
     Zero,
@@ -24,12 +32,28 @@ pub enum PseudoOp {
     Tse,
     Byte,
     Long,
+    Section,
+    Rodata,
+    Bss,
+    Quad,
+    Word,
+    Asciz,
+    Align,
+    P2align,
+    Comm,
+    Lcomm,
+    Type,
+    Size,
+    Local,
 }
 
 #[derive(Debug)]
 pub enum PseudoOpArg {
     String(String),
-    Integer(i32),
+    Integer(i64),
+    // `.-symbol`
+    Distance(String),
+    Symbol(SymbolNode),
 }
 
 impl PseudoOpArg {
@@ -40,7 +64,7 @@ impl PseudoOpArg {
         }
     }
 
-    pub fn as_integer(&self) -> &i32 {
+    pub fn as_integer(&self) -> &i64 {
         match self {
             PseudoOpArg::Integer(i) => i,
             _ => panic!(),
@@ -51,6 +75,8 @@ impl PseudoOpArg {
 #[derive(Debug, Clone)]
 pub enum OperandNode {
     Immidiate(i32),
+    // the address of a symbol as an immediate, e.g. `$symbol`
+    SymbolImmediate(SymbolNode),
     Register(Register),
     Label(String),
     Memory(MemoryNode),
@@ -58,12 +84,21 @@ pub enum OperandNode {
 
 #[derive(Debug, Clone)]
 pub struct MemoryNode {
-    pub base: Register,
+    pub base: Option<Register>,
+    pub index: Option<Index>,
     pub disp: Option<DispNode>,
 }
 
 #[derive(Debug, Clone)]
 pub enum DispNode {
     Immediate(i32),
-    Label(String),
+    Label(SymbolNode),
+}
+
+// `symbol+addend` or `symbol@GOTPCREL`
+#[derive(Debug, Clone)]
+pub struct SymbolNode {
+    pub name: String,
+    pub addend: i64,
+    pub gotpcrel: bool,
 }
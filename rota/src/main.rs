@@ -1,31 +1,59 @@
-use rota::assembler;
-use std::env;
+use rota::{assembler, frontend::parser::Syntax};
+use std::{env, error};
 
 extern crate rota;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if !matches!(args.len(), 3 | 4) {
+    let (files, flags): (Vec<&String>, Vec<&String>) =
+        args[1..].iter().partition(|arg| !arg.starts_with("--"));
+    if files.len() != 2
+        || flags.iter().any(|flag| {
+            !matches!(
+                flag.as_str(),
+                "--raw" | "--att" | "--listing" | "--function-sections"
+            )
+        })
+    {
         show_usage();
     }
 
-    let input_file = args[1].to_string();
-    let output_file = args[2].to_string();
-    let output_raw = args.get(3).map_or(false, |arg| arg == "--raw");
+    let input_file = files[0].to_string();
+    let output_file = files[1].to_string();
+    let output_raw = flags.iter().any(|flag| *flag == "--raw");
+    let syntax = if flags.iter().any(|flag| *flag == "--att") {
+        Syntax::Att
+    } else {
+        Syntax::Intel
+    };
+    let split_functions = flags.iter().any(|flag| *flag == "--function-sections");
+
+    if flags.iter().any(|flag| *flag == "--listing") {
+        match assembler::list_file(input_file.clone(), syntax, split_functions) {
+            Ok(listing) => print!("{}", listing),
+            Err(err) => exit_with_error(err),
+        }
+    }
 
     let err = if output_raw {
-        assembler::assemble_raw_to_file(input_file, output_file)
+        assembler::assemble_raw_to_file(input_file, output_file, syntax)
     } else {
-        assembler::assemble_to_file(input_file, output_file)
+        assembler::assemble_to_file(input_file, output_file, syntax, split_functions)
     };
 
     if let Err(err) = err {
-        println!("Failed to assemble:\n{}", err);
-        std::process::exit(1);
+        exit_with_error(err);
     }
 }
 
+fn exit_with_error(err: Box<dyn error::Error>) -> ! {
+    eprintln!("{}", err.to_string().trim_end());
+    std::process::exit(1);
+}
+
 fn show_usage() {
-    println!("usage: rota <input_file> <output_file> [--raw]");
+    println!(
+        "usage: rota <input_file> <output_file> [--raw] [--att] [--listing] [--function-sections]"
+    );
     std::process::exit(0);
 }
@@ -1,10 +1,12 @@
 extern crate rota;
 
 use rota::{
+    assembler,
     backend::gen_code::{self, SectionName},
+    common::error::{ErrorKind, Errors},
     frontend::{
         lexer::{self, SourceFile},
-        parser,
+        parser::{self, Syntax},
     },
 };
 
@@ -220,6 +222,17 @@ fn movsx() {
     do_test("movsx rax, byte ptr [r9-129]", "49 0f be 81 7f ff ff ff");
 }
 
+#[test]
+fn shift() {
+    do_test("shl rax, 3", "48 c1 e0 03");
+    do_test("shr rax, 1", "48 c1 e8 01");
+    do_test("sar r9, 63", "49 c1 f9 3f");
+    do_test("shl eax, 200", "c1 e0 c8");
+    do_test("shl rax, cl", "48 d3 e0");
+    do_test("shr r9, cl", "49 d3 e9");
+    do_test("sar [rax], cl", "48 d3 38");
+}
+
 #[test]
 fn and() {
     do_test("and eax,1", "83 e0 01");
@@ -331,14 +344,192 @@ fn long() {
     do_test(".long -1", "ff ff ff ff"); // skip
 }
 
+#[test]
+fn word() {
+    do_test(".word 4660", "34 12"); // skip
+    do_test(".word -2", "fe ff"); // skip
+}
+
+#[test]
+fn quad() {
+    do_test(".quad 1", "01 00 00 00 00 00 00 00"); // skip
+    do_test(".quad 0x123456789abcdef0", "f0 de bc 9a 78 56 34 12"); // skip
+    do_test(
+        ".quad 1, 2",
+        "01 00 00 00 00 00 00 00 02 00 00 00 00 00 00 00",
+    ); // skip
+}
+
+#[test]
+fn asciz() {
+    do_test(".asciz \"hi\"", "68 69 00"); // skip
+    do_test(".string \"a\", \"b\"", "61 00 62 00"); // skip
+}
+
+#[test]
+fn align() {
+    do_test("ret\n.align 4", "c3 90 90 90"); // skip
+    do_test("ret\n.align 4, 0", "c3 00 00 00"); // skip
+    do_test("ret\n.p2align 3", "c3 90 90 90 90 90 90 90"); // skip
+    do_test("ret\nret\n.align 2", "c3 c3"); // skip
+}
+
+#[test]
+fn align_errors() {
+    let source = "ret\n.align 3\n.p2align 64\n.align 8\n";
+    let source_file = SourceFile {
+        filename: "test.s".to_string(),
+        content: source.to_string(),
+    };
+    let errs = assembler::assemble(source_file).unwrap_err().0;
+
+    assert_eq!(errs.len(), 2);
+    assert!(matches!(
+        errs[0].kind,
+        ErrorKind::InvalidAlignment {
+            actual: 3,
+            log2: false
+        }
+    ));
+    assert_eq!(
+        errs[1].kind.to_string(),
+        "alignment must be 2**0 to 2**31: 2**64"
+    );
+    assert_eq!((errs[1].pos.line, errs[1].pos.column), (3, 10));
+}
+
+#[test]
+fn nobits_errors() {
+    let source =
+        ".bss\n.zero 8\n.byte 1\n.lcomm buf, 4\n.quad 5\n.section .tbss, \"aw\", @nobits\nret\n";
+    let source_file = SourceFile {
+        filename: "test.s".to_string(),
+        content: source.to_string(),
+    };
+    let errs = assembler::assemble(source_file).unwrap_err().0;
+
+    let lines: Vec<usize> = errs.iter().map(|err| err.pos.line).collect();
+    assert_eq!(lines, vec![3, 5, 7]);
+    assert_eq!(
+        errs[0].kind.to_string(),
+        "attempt to store non-zero value in section '.bss'"
+    );
+    assert!(matches!(
+        &errs[2].kind,
+        ErrorKind::NonZeroInNobits { section } if section == ".tbss"
+    ));
+}
+
+#[test]
+fn hex() {
+    do_test("push 0x10", "6a 10");
+    do_test(".byte 0xff", "ff"); // skip
+}
+
+#[test]
+fn sib() {
+    do_test("mov rax, [rsp+8]", "48 8b 44 24 08");
+    do_test("mov rax, [rbp]", "48 8b 45 00");
+    do_test("mov [rbp-4], eax", "89 45 fc");
+}
+
+#[test]
+fn att() {
+    do_test_att("movq %rsp, %rbp", "48 89 e5");
+    do_test_att("movq -8(%rbp), %rax", "48 8b 45 f8");
+    do_test_att("movl %eax, -4(%rbp)", "89 45 fc");
+    do_test_att("movq (%rax,%rcx,8), %rdx", "48 8b 14 c8");
+    do_test_att("leaq 16(%rbx,%rsi,4), %rdi", "48 8d 7c b3 10");
+    do_test_att("movq (,%rax,8), %rax", "48 8b 04 c5 00 00 00 00");
+    do_test_att("movq (%r12,%r13,2), %r8", "4f 8b 04 6c");
+    do_test_att("addq $8, %rsp", "48 83 c4 08");
+    do_test_att("subq $-1000, %rax", "48 81 e8 18 fc ff ff");
+    do_test_att("pushq %rbp\npopq %rbp", "55 5d");
+    do_test_att("movsbq -1(%rbp), %rax", "48 0f be 45 ff");
+    do_test_att("shlq %cl, %rax", "48 d3 e0");
+    do_test_att("sarq $2, -8(%rbp)", "48 c1 7d f8 02");
+    do_test_att("imul %rcx, %rax", "48 0f af c1");
+    do_test_att("call *%rax", "48 ff d0");
+    do_test_att("jmp *8(%rax)", "48 ff 60 08");
+    do_test_att("func:\ncall *func", "48 ff 14 25 00 00 00 00");
+    do_test_att("func:\njmp *func", "48 ff 24 25 00 00 00 00");
+    do_test_att("retq", "c3");
+    do_test_att("sete %al # comment", "0f 94 c0");
+}
+
+#[test]
+fn syntax_directive() {
+    do_test(".att_syntax\nmovq %rsp, %rbp", "48 89 e5");
+    do_test_att(".intel_syntax noprefix\nmov rbp, rsp", "48 89 e5");
+}
+
+#[test]
+fn att_error() {
+    assert!(parse_att("movl $0, -4(%rbp)").is_err());
+    assert!(parse_att("movq %eax, %ebx").is_err());
+    assert!(parse_att("movq (%rax,%rcx,3), %rax").is_err());
+    assert!(parse_att("frob %rax").is_err());
+    assert!(parse_att("shlq %rcx, %rax").is_err());
+}
+
+#[test]
+fn errors() {
+    let source = "main:\n  movzx eax, bl\n  mov rax, ?\n  mov rax, ebx\n  ret\n";
+    let source_file = SourceFile {
+        filename: "test.s".to_string(),
+        content: source.to_string(),
+    };
+    let errs = assembler::assemble(source_file).unwrap_err().0;
+
+    assert_eq!(errs.len(), 3);
+    assert!(matches!(errs[0].kind, ErrorKind::UnknownMnemonic { .. }));
+    assert!(matches!(
+        errs[1].kind,
+        ErrorKind::UnexpectedChar { actual: '?' }
+    ));
+    assert!(matches!(
+        errs[2].kind,
+        ErrorKind::OperandSizeMismatch { .. }
+    ));
+    assert_eq!((errs[1].pos.line, errs[1].pos.column), (3, 12));
+
+    assert_eq!(
+        errs[0].render(source),
+        "\
+error: unknown mnemonic: 'movzx'
+ --> test.s:2:3
+  |
+2 |   movzx eax, bl
+  |   ^
+  = hint: not supported by x86asm
+"
+    );
+}
+
+fn parse_att(source: &str) -> Result<parser::node::Program, Errors> {
+    let source_file = SourceFile {
+        filename: "".to_string(),
+        content: source.to_string(),
+    };
+    lexer::tokenize(source_file).and_then(|tokens| parser::parse_with_syntax(tokens, Syntax::Att))
+}
+
 fn do_test(source: &str, expected_output: &str) {
+    do_test_with_syntax(source, expected_output, Syntax::Intel);
+}
+
+fn do_test_att(source: &str, expected_output: &str) {
+    do_test_with_syntax(source, expected_output, Syntax::Att);
+}
+
+fn do_test_with_syntax(source: &str, expected_output: &str, syntax: Syntax) {
     let source_file = SourceFile {
         filename: "".to_string(),
         content: source.to_string(),
     };
     let obj = lexer::tokenize(source_file)
-        .and_then(|tokens| parser::parse(tokens))
-        .and_then(|insts| gen_code::generate(insts))
+        .and_then(|tokens| parser::parse_with_syntax(tokens, syntax))
+        .and_then(gen_code::generate)
         .unwrap();
 
     let text_section = obj
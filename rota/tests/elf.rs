@@ -2,7 +2,19 @@ extern crate rota;
 
 use std::fs;
 
-use rota::{assembler, frontend::lexer::SourceFile};
+use elfen::{
+    elf::Elf,
+    rel, section,
+    symbol::{self, Symbol},
+};
+use rota::{
+    assembler,
+    backend::{function_sections, gen_code, gen_elf},
+    frontend::{
+        lexer::{self, SourceFile},
+        parser,
+    },
+};
 
 #[test]
 fn simple() {
@@ -16,3 +28,202 @@ fn simple() {
 
     assert_eq!(actual_output, expected_output);
 }
+
+#[test]
+fn sections() {
+    let elf = generate(
+        "
+.section .rodata
+  .byte 1
+.bss
+  .zero 16
+.section .init_array, \"aw\", @progbits
+  .quad 0
+.text
+  ret
+",
+    );
+
+    let text = elf.get_section(".text").unwrap();
+    assert!(section::Flags::Execinstr.contained_in(text.header.flags));
+
+    let rodata = elf.get_section(".rodata").unwrap();
+    assert!(rodata.header.get_type() == section::Type::Progbits);
+    assert!(section::Flags::Alloc.contained_in(rodata.header.flags));
+    assert!(!section::Flags::Write.contained_in(rodata.header.flags));
+
+    let bss = elf.get_section(".bss").unwrap();
+    assert!(bss.header.get_type() == section::Type::Nobits);
+    assert_eq!(bss.header.size, 16);
+    assert_eq!(bss.data.len(), 0);
+
+    let init_array = elf.get_section(".init_array").unwrap();
+    assert!(section::Flags::Write.contained_in(init_array.header.flags));
+    assert_eq!(init_array.header.size, 8);
+}
+
+#[test]
+fn symbols() {
+    let elf = generate(
+        "
+.global main
+.type main, @function
+main:
+  call helper
+  ret
+.size main, .-main
+helper:
+  ret
+.comm buf, 32, 8
+.lcomm counter, 4
+",
+    );
+
+    let main = find_symbol(&elf, "main");
+    assert!(main.get_binding() == symbol::Binding::Global);
+    assert!(main.get_type() == symbol::Type::Func);
+    assert_eq!(main.size, 6);
+
+    let helper = find_symbol(&elf, "helper");
+    assert!(helper.get_binding() == symbol::Binding::Local);
+    assert_eq!(helper.value, 6);
+
+    let buf = find_symbol(&elf, "buf");
+    assert!(buf.get_index_type() == symbol::IndexType::Common);
+    assert_eq!(buf.value, 8);
+    assert_eq!(buf.size, 32);
+
+    let counter = find_symbol(&elf, "counter");
+    let bss_index = elf.find_section(".bss").unwrap() as u16;
+    assert!(counter.get_binding() == symbol::Binding::Local);
+    assert!(counter.get_type() == symbol::Type::Object);
+    assert!(counter.get_index_type() == symbol::IndexType::Index(bss_index));
+    assert_eq!(counter.size, 4);
+}
+
+#[test]
+fn label_in_empty_section() {
+    let elf = generate("ret\n.data\nend:\n");
+
+    let end = find_symbol(&elf, "end");
+    let data_index = elf.find_section(".data").unwrap() as u16;
+    assert!(end.get_index_type() == symbol::IndexType::Index(data_index));
+    assert_eq!(end.value, 0);
+}
+
+#[test]
+fn relocations() {
+    let elf = generate(
+        "
+.att_syntax
+.global foo
+foo:
+  movq foo+4(%rip), %rax
+  movq $1, foo-8(%rip)
+  movq foo@GOTPCREL(%rip), %rax
+  movq foo(,%rcx,8), %rdx
+  movl $foo, %ecx
+.data
+  .quad foo+16
+  .long foo
+",
+    );
+
+    let rela_text = relas(&elf, ".rela.text");
+    assert_eq!(
+        rela_text,
+        vec![
+            (rel::Type::Pc32, 3, 0),
+            (rel::Type::Pc32, 0xa, -0x10),
+            (rel::Type::GotPcRel, 0x15, -4),
+            (rel::Type::Abs32S, 0x1d, 0),
+            (rel::Type::Abs32, 0x23, 0),
+        ]
+    );
+
+    let rela_data = relas(&elf, ".rela.data");
+    assert_eq!(
+        rela_data,
+        vec![(rel::Type::Abs64, 0, 16), (rel::Type::Abs32, 8, 0)]
+    );
+}
+
+#[test]
+fn function_sections() {
+    let source_file = SourceFile {
+        filename: "".to_string(),
+        content: "
+.global main
+main:
+  call helper
+  ret
+.type helper, @function
+helper:
+  ret
+local:
+  ret
+.data
+.global value
+value:
+  .byte 1
+"
+        .to_string(),
+    };
+    let elf = lexer::tokenize(source_file)
+        .and_then(parser::parse)
+        .map(function_sections::split)
+        .and_then(gen_code::generate)
+        .and_then(gen_elf::generate)
+        .unwrap();
+
+    let main = elf.get_section(".text.main").unwrap();
+    assert!(section::Flags::Execinstr.contained_in(main.header.flags));
+    assert_eq!(main.header.size, 6);
+    // `local` is not a function, so it stays in `.text.helper`
+    assert_eq!(elf.get_section(".text.helper").unwrap().header.size, 2);
+    assert_eq!(relas(&elf, ".rela.text.main").len(), 1);
+    assert!(elf.get_section(".data").is_some());
+    assert!(elf.get_section(".data.value").is_none());
+}
+
+fn generate(source: &str) -> Elf {
+    let source_file = SourceFile {
+        filename: "".to_string(),
+        content: source.to_string(),
+    };
+    lexer::tokenize(source_file)
+        .and_then(parser::parse)
+        .and_then(gen_code::generate)
+        .and_then(gen_elf::generate)
+        .unwrap()
+}
+
+fn relas(elf: &Elf, name: &str) -> Vec<(rel::Type, u64, i64)> {
+    let section = elf.get_section(name).unwrap();
+    section
+        .data
+        .as_rela()
+        .unwrap()
+        .iter()
+        .map(|rela| (rela.get_type(), rela.offset, rela.addend))
+        .collect()
+}
+
+fn find_symbol(elf: &Elf, name: &str) -> Symbol {
+    let strtab = elf
+        .get_section(".strtab")
+        .unwrap()
+        .data
+        .as_strtab()
+        .unwrap();
+    let symbols = elf
+        .get_section(".symtab")
+        .unwrap()
+        .data
+        .as_symbols()
+        .unwrap();
+    *symbols
+        .iter()
+        .find(|symbol| strtab.get(symbol.name as usize) == name)
+        .unwrap()
+}
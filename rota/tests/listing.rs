@@ -0,0 +1,54 @@
+extern crate rota;
+
+use rota::{assembler, frontend::lexer::SourceFile, frontend::parser::Syntax};
+
+#[test]
+fn listing() {
+    let source = SourceFile {
+        filename: "".to_string(),
+        content: "\
+.global main
+main:
+  mov rax, [rip+foo]
+  ret
+.data
+foo:
+  .quad main+8
+"
+        .to_string(),
+    };
+    let listing = assembler::list(source, Syntax::Intel, false).unwrap();
+    let lines: Vec<&str> = listing.lines().collect();
+
+    assert_eq!(lines.len(), 7);
+    assert_eq!(lines[1], "   2                                       main:");
+    assert_eq!(
+        lines[2],
+        "   3 .text    0000 48 8b 05 00 00 00 00      mov rax, [rip+foo]  R_X86_64_PC32 foo-4"
+    );
+    assert_eq!(lines[3], "   4 .text    0007 c3                        ret");
+    assert_eq!(
+        lines[6],
+        "   7 .data    0000 00 00 00 00 00 00 00 00   .quad main+8  R_X86_64_64 main+8"
+    );
+}
+
+#[test]
+fn listing_with_function_sections() {
+    let source = SourceFile {
+        filename: "".to_string(),
+        content: "\
+.global main
+main:
+  ret
+"
+        .to_string(),
+    };
+    let listing = assembler::list(source, Syntax::Intel, true).unwrap();
+    let lines: Vec<&str> = listing.lines().collect();
+
+    assert_eq!(
+        lines[2],
+        "   3 .text.main 0000 c3                        ret"
+    );
+}
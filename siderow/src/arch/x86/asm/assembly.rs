@@ -3,11 +3,14 @@ use super::Function;
 #[derive(Debug)]
 pub struct Assembly {
     pub data: DataSection,
+    // zero-initialized data, which takes no space in the object file
+    pub bss: DataSection,
     pub text: TextSection,
 }
 
 #[derive(Debug)]
 pub struct DataSection {
+    pub name: &'static str,
     pub items: Vec<DataSectionItem>,
 }
 
@@ -31,15 +34,19 @@ pub struct TextSection {
 impl Assembly {
     pub fn new() -> Self {
         Self {
-            data: DataSection::new(),
+            data: DataSection::new(".data"),
+            bss: DataSection::new(".bss"),
             text: TextSection::new(),
         }
     }
 }
 
 impl DataSection {
-    pub fn new() -> Self {
-        Self { items: Vec::new() }
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            items: Vec::new(),
+        }
     }
 
     pub fn add_data(&mut self, name: String, bytes: Vec<DataItem>) {
@@ -66,6 +73,7 @@ impl Assembly {
         s.push_str(".intel_syntax noprefix\n");
 
         s.push_str(&self.data.stringify());
+        s.push_str(&self.bss.stringify());
         s.push_str(&self.text.stringify());
 
         s
@@ -76,7 +84,12 @@ impl DataSection {
     pub fn stringify(&self) -> String {
         let mut s = String::new();
 
-        s.push_str(".data\n");
+        if self.items.is_empty() {
+            return s;
+        }
+
+        s.push_str(self.name);
+        s.push('\n');
         for item in &self.items {
             s.push_str(&item.stringify())
         }
@@ -65,7 +65,11 @@ impl InstructionSelector {
                 .collect::<Vec<asm::DataItem>>(),
             ref val => vec![const2dataitem(val.clone(), global_typ)],
         };
-        self.assembly.data.add_data(global.name.clone(), bytes);
+        if let ssa::Constant::ZeroInitializer = global.init_value {
+            self.assembly.bss.add_data(global.name.clone(), bytes);
+        } else {
+            self.assembly.data.add_data(global.name.clone(), bytes);
+        }
     }
 
     fn trans_function(&mut self, module: &ssa::Module, ssa_func: &ssa::Function) {
@@ -1,11 +1,13 @@
 pub mod decoding;
 
+use std::{error, fmt};
+
 use crate::{
     common::{modrm::ModRM, rex::Rex, sib::Sib},
     instruction::{
         mnemonic::Mnemonic,
         operand::{
-            memory::{Displacement, Memory},
+            memory::{Displacement, Index, Memory},
             register::{self, Register},
             Operand,
         },
@@ -16,11 +18,48 @@ use crate::{
 pub struct Decoder {
     code: Vec<u8>,
     pos: usize,
+    // where the instruction being decoded starts
+    start: usize,
 
     rex: Option<Rex>,
 }
 
-pub fn decode(code: &[u8]) -> Vec<Instruction> {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    pub kind: DecodeErrorKind,
+    // offset of the instruction in the code
+    pub offset: usize,
+    // bytes of the instruction read until the error
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeErrorKind {
+    UnknownOpcode,
+    // the code ends in the middle of an instruction
+    UnexpectedEnd,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes: Vec<String> = self.bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        let message = match self.kind {
+            DecodeErrorKind::UnknownOpcode => "unknown opcode",
+            DecodeErrorKind::UnexpectedEnd => "unexpected end of code",
+        };
+        write!(
+            f,
+            "{} at offset {:#x}: [{}]",
+            message,
+            self.offset,
+            bytes.join(" ")
+        )
+    }
+}
+
+impl error::Error for DecodeError {}
+
+pub fn decode(code: &[u8]) -> Result<Vec<Instruction>, DecodeError> {
     let mut decoder = Decoder::new(code);
     decoder.decode()
 }
@@ -30,26 +69,28 @@ impl Decoder {
         Self {
             code: code.to_vec(),
             pos: 0,
+            start: 0,
             rex: None,
         }
     }
 
-    pub fn decode(&mut self) -> Vec<Instruction> {
+    pub fn decode(&mut self) -> Result<Vec<Instruction>, DecodeError> {
         let mut inst = Vec::new();
 
         while self.pos < self.code.len() {
-            inst.push(self.decode_inst());
+            inst.push(self.decode_inst()?);
         }
 
-        inst
+        Ok(inst)
     }
 
-    pub fn decode_inst(&mut self) -> Instruction {
-        let mut opcode = self.consume_u8();
+    pub fn decode_inst(&mut self) -> Result<Instruction, DecodeError> {
+        self.start = self.pos;
+        let mut opcode = self.consume_u8()?;
 
         if opcode & 0xf0 == 0x40 {
             self.rex = Some(Rex::from_byte(opcode));
-            opcode = self.consume_u8();
+            opcode = self.consume_u8()?;
         } else {
             self.rex = None;
         }
@@ -60,9 +101,9 @@ impl Decoder {
             0x09 => self.decode_mr(Mnemonic::Or),
             0x0b => self.decode_rm(Mnemonic::Or),
             0x0f => {
-                let op = self.consume_u8();
+                let op = self.consume_u8()?;
                 match op {
-                    0x05 => Instruction::new_nullary(Mnemonic::Syscall),
+                    0x05 => Ok(Instruction::new_nullary(Mnemonic::Syscall)),
                     0x84 => self.decode_d32(Mnemonic::Je),
                     0x94 => self.decode_set(Mnemonic::Sete),
                     0x95 => self.decode_set(Mnemonic::Setne),
@@ -71,8 +112,8 @@ impl Decoder {
                     0x9e => self.decode_set(Mnemonic::Setle),
                     0x9f => self.decode_set(Mnemonic::Setg),
                     0xaf => self.decode_rm(Mnemonic::IMul),
-                    0xbe => self.decode_rm(Mnemonic::Movsx),
-                    _ => panic!(),
+                    0xbe => self.decode_rm8(Mnemonic::Movsx),
+                    _ => Err(self.error(DecodeErrorKind::UnknownOpcode)),
                 }
             }
             0x21 => self.decode_mr(Mnemonic::And),
@@ -91,7 +132,7 @@ impl Decoder {
             0x6b => self.decode_rmi8(Mnemonic::IMul),
             0x74 => self.decode_d8(Mnemonic::Je),
             0x81 => {
-                let modrm = ModRM::from_byte(self.consume_u8());
+                let modrm = ModRM::from_byte(self.consume_u8()?);
                 match modrm.reg {
                     0 => self.decode_mi32(Mnemonic::Add, modrm),
                     1 => self.decode_mi32(Mnemonic::Or, modrm),
@@ -99,11 +140,11 @@ impl Decoder {
                     5 => self.decode_mi32(Mnemonic::Sub, modrm),
                     6 => self.decode_mi32(Mnemonic::Xor, modrm),
                     7 => self.decode_mi32(Mnemonic::Cmp, modrm),
-                    _ => panic!(),
+                    _ => Err(self.error(DecodeErrorKind::UnknownOpcode)),
                 }
             }
             0x83 => {
-                let modrm = ModRM::from_byte(self.consume_u8());
+                let modrm = ModRM::from_byte(self.consume_u8()?);
                 match modrm.reg {
                     0 => self.decode_mi8(Mnemonic::Add, modrm),
                     1 => self.decode_mi8(Mnemonic::Or, modrm),
@@ -111,45 +152,65 @@ impl Decoder {
                     5 => self.decode_mi8(Mnemonic::Sub, modrm),
                     6 => self.decode_mi8(Mnemonic::Xor, modrm),
                     7 => self.decode_mi8(Mnemonic::Cmp, modrm),
-                    _ => panic!(),
+                    _ => Err(self.error(DecodeErrorKind::UnknownOpcode)),
                 }
             }
             0x88 => self.decode_mr8(Mnemonic::Mov),
             0x89 => self.decode_mr(Mnemonic::Mov),
             0x8b => self.decode_rm(Mnemonic::Mov),
             0x8d => self.decode_rm(Mnemonic::Lea),
-            0x8f => self.decode_m(Mnemonic::Pop),
-            0xc3 => Instruction::new_nullary(Mnemonic::Ret),
+            0x8f => {
+                let modrm = ModRM::from_byte(self.consume_u8()?);
+                match modrm.reg {
+                    0 => self.decode_m64(Mnemonic::Pop, modrm),
+                    _ => Err(self.error(DecodeErrorKind::UnknownOpcode)),
+                }
+            }
+            0xc1 | 0xd1 | 0xd3 => {
+                let modrm = ModRM::from_byte(self.consume_u8()?);
+                let mnemonic = match modrm.reg {
+                    4 => Mnemonic::Shl,
+                    5 => Mnemonic::Shr,
+                    7 => Mnemonic::Sar,
+                    _ => return Err(self.error(DecodeErrorKind::UnknownOpcode)),
+                };
+                match opcode {
+                    0xc1 => self.decode_mi8(mnemonic, modrm),
+                    0xd1 => self.decode_m1(mnemonic, modrm),
+                    _ => self.decode_mcl(mnemonic, modrm),
+                }
+            }
+            0xc3 => Ok(Instruction::new_nullary(Mnemonic::Ret)),
             0xc7 => {
-                let modrm = ModRM::from_byte(self.consume_u8());
+                let modrm = ModRM::from_byte(self.consume_u8()?);
                 match modrm.reg {
                     0 => self.decode_mi32(Mnemonic::Mov, modrm),
-                    _ => panic!(),
+                    _ => Err(self.error(DecodeErrorKind::UnknownOpcode)),
                 }
             }
             0xe8 => self.decode_d32(Mnemonic::Call),
             0xe9 => self.decode_d32(Mnemonic::Jmp),
             0xeb => self.decode_d8(Mnemonic::Jmp),
-            0xf4 => Instruction::new_nullary(Mnemonic::Hlt),
+            0xf4 => Ok(Instruction::new_nullary(Mnemonic::Hlt)),
             0xf7 => {
-                let modrm = ModRM::from_byte(self.consume_u8());
-                let opr = self.decode_modrm(&modrm);
+                let modrm = ModRM::from_byte(self.consume_u8()?);
+                let opr = self.decode_modrm(&modrm)?;
                 match modrm.reg {
-                    7 => Instruction::new_unary(Mnemonic::IDiv, opr),
-                    _ => panic!(),
+                    7 => Ok(Instruction::new_unary(Mnemonic::IDiv, opr)),
+                    _ => Err(self.error(DecodeErrorKind::UnknownOpcode)),
                 }
             }
             0xff => {
-                let modrm = ModRM::from_byte(self.consume_u8());
-                let opr = self.decode_modrm(&modrm);
+                let modrm = ModRM::from_byte(self.consume_u8()?);
+                let opr = self.decode_modrm(&modrm)?;
                 match modrm.reg {
-                    2 => Instruction::new_unary(Mnemonic::Call, opr),
-                    4 => Instruction::new_unary(Mnemonic::Jmp, opr),
-                    6 => Instruction::new_unary(Mnemonic::Push, opr),
-                    _ => panic!(),
+                    2 => Ok(Instruction::new_unary(Mnemonic::Call, opr)),
+                    4 => Ok(Instruction::new_unary(Mnemonic::Jmp, opr)),
+                    6 => Ok(Instruction::new_unary(Mnemonic::Push, opr)),
+                    _ => Err(self.error(DecodeErrorKind::UnknownOpcode)),
                 }
             }
-            _ => panic!(),
+            _ => Err(self.error(DecodeErrorKind::UnknownOpcode)),
         }
     }
 
@@ -227,8 +288,8 @@ impl Decoder {
                 if !extend {
                     match num {
                         0 => Register::Al,
-                        2 => Register::Cl,
-                        1 => Register::Dl,
+                        1 => Register::Cl,
+                        2 => Register::Dl,
                         3 => Register::Bl,
                         4 => Register::Spl,
                         5 => Register::Bpl,
@@ -254,57 +315,81 @@ impl Decoder {
         }
     }
 
-    fn decode_modrm(&mut self, modrm: &ModRM) -> Operand {
-        match modrm.modval {
-            0b00 => match modrm.rm {
-                0b100 => {
-                    let sib = Sib::from_byte(self.consume_u8());
-                    match sib.base {
-                        0b100 => Operand::Memory(Memory::new(Register::R12, None)),
-                        0b101 => Operand::Memory(Memory::new_disp(Displacement::Disp32(
-                            self.consume_i32(),
-                        ))),
-                        _ => panic!(),
-                    }
-                }
-                0b101 => Operand::Memory(Memory::new(
-                    Register::Rip,
-                    Some(Displacement::Disp32(self.consume_i32())),
-                )),
-                _ => Operand::Memory(Memory::new(self.decode_register_rm(modrm.rm), None)),
+    fn decode_register_addr(&mut self, num: u8, extend: bool) -> Register {
+        self.decode_register(num, register::Size::QWord, extend)
+    }
+
+    fn decode_modrm(&mut self, modrm: &ModRM) -> Result<Operand, DecodeError> {
+        if modrm.modval == 0b11 {
+            return Ok(Operand::Register(self.decode_register_rm(modrm.rm)));
+        }
+
+        let rex_b = self.rex.as_ref().is_some_and(|rex| rex.b);
+        let rex_x = self.rex.as_ref().is_some_and(|rex| rex.x);
+        let (base, index) = match modrm.rm {
+            0b100 => {
+                let sib = Sib::from_byte(self.consume_u8()?);
+                let index = if sib.index == 0b100 && !rex_x {
+                    None
+                } else {
+                    let reg = self.decode_register_addr(sib.index, rex_x);
+                    Some(Index::new(reg, 1 << sib.scale))
+                };
+                let base = if sib.base == 0b101 && modrm.modval == 0b00 {
+                    None
+                } else {
+                    Some(self.decode_register_addr(sib.base, rex_b))
+                };
+                (base, index)
+            }
+            0b101 if modrm.modval == 0b00 => (Some(Register::Rip), None),
+            rm => (Some(self.decode_register_addr(rm, rex_b)), None),
+        };
+
+        let disp = match modrm.modval {
+            0b00 => match base {
+                None | Some(Register::Rip) => Some(Displacement::Disp32(self.consume_i32()?)),
+                _ => None,
             },
-            0b01 => Operand::Memory(Memory::new(
-                self.decode_register_rm(modrm.rm),
-                Some(Displacement::Disp8(self.consume_i8())),
-            )),
-            0b10 => Operand::Memory(Memory::new(
-                self.decode_register_rm(modrm.rm),
-                Some(Displacement::Disp32(self.consume_i32())),
-            )),
-            0b11 => Operand::Register(self.decode_register_rm(modrm.rm)),
+            0b01 => Some(Displacement::Disp8(self.consume_i8()?)),
+            0b10 => Some(Displacement::Disp32(self.consume_i32()?)),
             _ => panic!(),
+        };
+
+        Ok(Operand::Memory(Memory { base, index, disp }))
+    }
+
+    fn error(&self, kind: DecodeErrorKind) -> DecodeError {
+        DecodeError {
+            kind,
+            offset: self.start,
+            bytes: self.code[self.start..self.pos].to_vec(),
         }
     }
 
-    fn consume_u8(&mut self) -> u8 {
-        let code = *self.code.get(self.pos).unwrap();
-        self.pos += 1;
-        code
+    fn consume_u8(&mut self) -> Result<u8, DecodeError> {
+        match self.code.get(self.pos) {
+            Some(&code) => {
+                self.pos += 1;
+                Ok(code)
+            }
+            None => Err(self.error(DecodeErrorKind::UnexpectedEnd)),
+        }
     }
 
-    fn consume_u32(&mut self) -> u32 {
+    fn consume_u32(&mut self) -> Result<u32, DecodeError> {
         let mut ret: u32 = 0;
         for i in 0..4 {
-            ret |= (self.consume_u8() as u32) << (i * 8)
+            ret |= (self.consume_u8()? as u32) << (i * 8)
         }
-        return ret;
+        Ok(ret)
     }
 
-    fn consume_i8(&mut self) -> i8 {
-        self.consume_u8() as i8
+    fn consume_i8(&mut self) -> Result<i8, DecodeError> {
+        Ok(self.consume_u8()? as i8)
     }
 
-    fn consume_i32(&mut self) -> i32 {
-        self.consume_u32() as i32
+    fn consume_i32(&mut self) -> Result<i32, DecodeError> {
+        Ok(self.consume_u32()? as i32)
     }
 }
@@ -2,99 +2,199 @@ use crate::{
     common::modrm::ModRM,
     instruction::{
         mnemonic::Mnemonic,
-        operand::{immediate::Immediate, offset::Offset, register, Operand},
+        operand::{
+            immediate::Immediate,
+            offset::Offset,
+            register::{self, Register},
+            Operand,
+        },
         Instruction,
     },
 };
 
-use super::Decoder;
+use super::{DecodeError, Decoder};
 
 impl Decoder {
-    pub fn decode_m(&mut self, mnemonic: Mnemonic) -> Instruction {
-        let modrm = ModRM::from_byte(self.consume_u8());
-        let opr = self.decode_modrm(&modrm);
-        Instruction::new_unary(mnemonic, opr)
+    // an operand which is always 64-bit when it is a register
+    pub fn decode_m64(
+        &mut self,
+        mnemonic: Mnemonic,
+        modrm: ModRM,
+    ) -> Result<Instruction, DecodeError> {
+        let opr = if modrm.modval == 0b11 {
+            let extend = self.rex.as_ref().is_some_and(|rex| rex.b);
+            Operand::Register(self.decode_register(modrm.rm, register::Size::QWord, extend))
+        } else {
+            self.decode_modrm(&modrm)?
+        };
+        Ok(Instruction::new_unary(mnemonic, opr))
     }
 
-    pub fn decode_o(&mut self, mnemonic: Mnemonic, reg: u8) -> Instruction {
+    pub fn decode_o(&mut self, mnemonic: Mnemonic, reg: u8) -> Result<Instruction, DecodeError> {
         let extend = self.rex.as_ref().map_or(false, |rex| rex.b);
         let reg = self.decode_register(reg, register::Size::QWord, extend);
-        Instruction::new_unary(mnemonic, Operand::Register(reg))
+        Ok(Instruction::new_unary(mnemonic, Operand::Register(reg)))
     }
 
-    pub fn decode_i8(&mut self, mnemonic: Mnemonic) -> Instruction {
-        let imm = Immediate::Imm8(self.consume_i8());
-        Instruction::new_unary(mnemonic, Operand::Immediate(imm))
+    pub fn decode_i8(&mut self, mnemonic: Mnemonic) -> Result<Instruction, DecodeError> {
+        let imm = Immediate::Imm8(self.consume_i8()?);
+        Ok(Instruction::new_unary(mnemonic, Operand::Immediate(imm)))
     }
 
-    pub fn decode_i32(&mut self, mnemonic: Mnemonic) -> Instruction {
-        let imm = Immediate::Imm32(self.consume_i32());
-        Instruction::new_unary(mnemonic, Operand::Immediate(imm))
+    pub fn decode_i32(&mut self, mnemonic: Mnemonic) -> Result<Instruction, DecodeError> {
+        let imm = Immediate::Imm32(self.consume_i32()?);
+        Ok(Instruction::new_unary(mnemonic, Operand::Immediate(imm)))
     }
 
-    pub fn decode_d8(&mut self, mnemonic: Mnemonic) -> Instruction {
-        let off = Offset::Off8(self.consume_i8());
-        Instruction::new_unary(mnemonic, Operand::Offset(off))
+    pub fn decode_d8(&mut self, mnemonic: Mnemonic) -> Result<Instruction, DecodeError> {
+        let off = Offset::Off8(self.consume_i8()?);
+        Ok(Instruction::new_unary(mnemonic, Operand::Offset(off)))
     }
 
-    pub fn decode_d32(&mut self, mnemonic: Mnemonic) -> Instruction {
-        let off = Offset::Off32(self.consume_i32());
-        Instruction::new_unary(mnemonic, Operand::Offset(off))
+    pub fn decode_d32(&mut self, mnemonic: Mnemonic) -> Result<Instruction, DecodeError> {
+        let off = Offset::Off32(self.consume_i32()?);
+        Ok(Instruction::new_unary(mnemonic, Operand::Offset(off)))
     }
 
-    pub fn decode_mi8(&mut self, mnemonic: Mnemonic, modrm: ModRM) -> Instruction {
-        let opr1 = self.decode_modrm(&modrm);
-        let opr2 = Immediate::Imm8(self.consume_i8());
-        Instruction::new_binary(mnemonic, opr1, Operand::Immediate(opr2))
+    pub fn decode_mi8(
+        &mut self,
+        mnemonic: Mnemonic,
+        modrm: ModRM,
+    ) -> Result<Instruction, DecodeError> {
+        let opr1 = self.decode_modrm(&modrm)?;
+        let opr2 = Immediate::Imm8(self.consume_i8()?);
+        Ok(Instruction::new_binary(
+            mnemonic,
+            opr1,
+            Operand::Immediate(opr2),
+        ))
     }
 
-    pub fn decode_mi32(&mut self, mnemonic: Mnemonic, modrm: ModRM) -> Instruction {
-        let opr1 = self.decode_modrm(&modrm);
-        let opr2 = Immediate::Imm32(self.consume_i32());
-        Instruction::new_binary(mnemonic, opr1, Operand::Immediate(opr2))
+    pub fn decode_mi32(
+        &mut self,
+        mnemonic: Mnemonic,
+        modrm: ModRM,
+    ) -> Result<Instruction, DecodeError> {
+        let opr1 = self.decode_modrm(&modrm)?;
+        let opr2 = Immediate::Imm32(self.consume_i32()?);
+        Ok(Instruction::new_binary(
+            mnemonic,
+            opr1,
+            Operand::Immediate(opr2),
+        ))
     }
 
-    pub fn decode_mr8(&mut self, mnemonic: Mnemonic) -> Instruction {
-        let modrm = ModRM::from_byte(self.consume_u8());
-        let opr1 = self.decode_modrm(&modrm);
+    // a shift by one, which is encoded without an immediate
+    pub fn decode_m1(
+        &mut self,
+        mnemonic: Mnemonic,
+        modrm: ModRM,
+    ) -> Result<Instruction, DecodeError> {
+        let opr1 = self.decode_modrm(&modrm)?;
+        Ok(Instruction::new_binary(
+            mnemonic,
+            opr1,
+            Operand::Immediate(Immediate::Imm8(1)),
+        ))
+    }
+
+    // a shift by cl
+    pub fn decode_mcl(
+        &mut self,
+        mnemonic: Mnemonic,
+        modrm: ModRM,
+    ) -> Result<Instruction, DecodeError> {
+        let opr1 = self.decode_modrm(&modrm)?;
+        Ok(Instruction::new_binary(
+            mnemonic,
+            opr1,
+            Operand::Register(Register::Cl),
+        ))
+    }
+
+    pub fn decode_mr8(&mut self, mnemonic: Mnemonic) -> Result<Instruction, DecodeError> {
+        let modrm = ModRM::from_byte(self.consume_u8()?);
+        let opr1 = if modrm.modval == 0b11 {
+            let extend = self.rex.as_ref().is_some_and(|rex| rex.b);
+            Operand::Register(self.decode_register(modrm.rm, register::Size::Byte, extend))
+        } else {
+            self.decode_modrm(&modrm)?
+        };
         let opr2 = self.decode_register_reg8(modrm.reg);
-        Instruction::new_binary(mnemonic, opr1, Operand::Register(opr2))
+        Ok(Instruction::new_binary(
+            mnemonic,
+            opr1,
+            Operand::Register(opr2),
+        ))
     }
 
-    pub fn decode_mr(&mut self, mnemonic: Mnemonic) -> Instruction {
-        let modrm = ModRM::from_byte(self.consume_u8());
-        let opr1 = self.decode_modrm(&modrm);
+    pub fn decode_mr(&mut self, mnemonic: Mnemonic) -> Result<Instruction, DecodeError> {
+        let modrm = ModRM::from_byte(self.consume_u8()?);
+        let opr1 = self.decode_modrm(&modrm)?;
         let opr2 = self.decode_register_reg(modrm.reg);
-        Instruction::new_binary(mnemonic, opr1, Operand::Register(opr2))
+        Ok(Instruction::new_binary(
+            mnemonic,
+            opr1,
+            Operand::Register(opr2),
+        ))
+    }
+
+    pub fn decode_rm(&mut self, mnemonic: Mnemonic) -> Result<Instruction, DecodeError> {
+        let modrm = ModRM::from_byte(self.consume_u8()?);
+        let opr1 = self.decode_register_reg(modrm.reg);
+        let opr2 = self.decode_modrm(&modrm)?;
+        Ok(Instruction::new_binary(
+            mnemonic,
+            Operand::Register(opr1),
+            opr2,
+        ))
     }
 
-    pub fn decode_rm(&mut self, mnemonic: Mnemonic) -> Instruction {
-        let modrm = ModRM::from_byte(self.consume_u8());
+    // a byte source, as in movsx
+    pub fn decode_rm8(&mut self, mnemonic: Mnemonic) -> Result<Instruction, DecodeError> {
+        let modrm = ModRM::from_byte(self.consume_u8()?);
         let opr1 = self.decode_register_reg(modrm.reg);
-        let opr2 = self.decode_modrm(&modrm);
-        Instruction::new_binary(mnemonic, Operand::Register(opr1), opr2)
+        let opr2 = if modrm.modval == 0b11 {
+            let extend = self.rex.as_ref().is_some_and(|rex| rex.b);
+            Operand::Register(self.decode_register(modrm.rm, register::Size::Byte, extend))
+        } else {
+            self.decode_modrm(&modrm)?
+        };
+        Ok(Instruction::new_binary(
+            mnemonic,
+            Operand::Register(opr1),
+            opr2,
+        ))
     }
 
     // TODO
-    pub fn decode_rmi8(&mut self, mnemonic: Mnemonic) -> Instruction {
-        let modrm = ModRM::from_byte(self.consume_u8());
+    pub fn decode_rmi8(&mut self, mnemonic: Mnemonic) -> Result<Instruction, DecodeError> {
+        let modrm = ModRM::from_byte(self.consume_u8()?);
         let opr1 = self.decode_register_reg(modrm.reg);
-        let opr2 = Immediate::Imm8(self.consume_i8());
-        Instruction::new_binary(mnemonic, Operand::Register(opr1), Operand::Immediate(opr2))
+        let opr2 = Immediate::Imm8(self.consume_i8()?);
+        Ok(Instruction::new_binary(
+            mnemonic,
+            Operand::Register(opr1),
+            Operand::Immediate(opr2),
+        ))
     }
 
     // TODO
-    pub fn decode_rmi32(&mut self, mnemonic: Mnemonic) -> Instruction {
-        let modrm = ModRM::from_byte(self.consume_u8());
+    pub fn decode_rmi32(&mut self, mnemonic: Mnemonic) -> Result<Instruction, DecodeError> {
+        let modrm = ModRM::from_byte(self.consume_u8()?);
         let opr1 = self.decode_register_reg(modrm.reg);
-        let opr2 = Immediate::Imm32(self.consume_i32());
-        Instruction::new_binary(mnemonic, Operand::Register(opr1), Operand::Immediate(opr2))
+        let opr2 = Immediate::Imm32(self.consume_i32()?);
+        Ok(Instruction::new_binary(
+            mnemonic,
+            Operand::Register(opr1),
+            Operand::Immediate(opr2),
+        ))
     }
 
-    pub fn decode_set(&mut self, mnemonic: Mnemonic) -> Instruction {
-        let modrm = ModRM::from_byte(self.consume_u8());
+    pub fn decode_set(&mut self, mnemonic: Mnemonic) -> Result<Instruction, DecodeError> {
+        let modrm = ModRM::from_byte(self.consume_u8()?);
         let extend = self.rex.as_ref().map_or(false, |rex| rex.b);
         let opr = self.decode_register(modrm.rm, register::Size::Byte, extend);
-        Instruction::new_unary(mnemonic, Operand::Register(opr))
+        Ok(Instruction::new_unary(mnemonic, Operand::Register(opr)))
     }
 }
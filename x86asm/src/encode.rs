@@ -238,6 +238,9 @@ fn encode_binary_op(inst: &Instruction) -> EncodedInst {
             }
             _ => panic!(),
         },
+        Mnemonic::Sar => encode_shift(opr1, opr2, 7),
+        Mnemonic::Shl => encode_shift(opr1, opr2, 4),
+        Mnemonic::Shr => encode_shift(opr1, opr2, 5),
         Mnemonic::Sub => match (opr1, opr2) {
             (Operand::Register(_), Operand::Immediate(imm))
             | (Operand::Memory(_), Operand::Immediate(imm)) => match imm {
@@ -272,6 +275,17 @@ fn encode_binary_op(inst: &Instruction) -> EncodedInst {
     }
 }
 
+// shifts by an 8-bit immediate or by cl, with `reg` selecting the kind of shift
+fn encode_shift(opr1: &Operand, opr2: &Operand, reg: u8) -> EncodedInst {
+    match opr2 {
+        Operand::Immediate(imm @ Immediate::Imm8(_)) => {
+            encoding::encode_mi(&[0xc1], opr1.to_rm(), imm).set_reg(reg)
+        }
+        Operand::Register(Register::Cl) => encoding::encode_m(&[0xd3], opr1.to_rm()).set_reg(reg),
+        _ => panic!(),
+    }
+}
+
 // TODO
 impl EncodedInst {
     fn set_reg(mut self, reg: u8) -> Self {
@@ -115,72 +115,74 @@ pub fn encode_set(opcode: &[u8], opr1: RM) -> EncodedInst {
     enc
 }
 
-// TODO fix REX.W is always true
-// e.g. push r8 => expected: [0x41, 0x58], actual: [0x49, 0x58]
+// REX.W follows the size of the register operands, and memory without them is a qword
 fn encode_rex(rm: Option<&RM>, reg: Option<&Register>) -> Option<Rex> {
-    let reg_rm = match rm {
-        Some(RM::Register(reg)) => reg,
-        Some(RM::Memory(Memory {
-            base: Some(base), ..
-        })) => base,
-        _ => &Register::Al,
+    let (reg_rm, reg_base, reg_index) = match rm {
+        Some(RM::Register(reg)) => (Some(*reg), Some(*reg), None),
+        Some(RM::Memory(mem)) => (
+            None,
+            mem.base.as_ref(),
+            mem.index.as_ref().map(|index| &index.reg),
+        ),
+        None => (None, None, None),
     };
-    let reg_reg = reg.unwrap_or(&Register::Al);
 
-    if reg_rm.size() != register::Size::QWord
-        && reg_reg.size() != register::Size::QWord
-        && !reg_rm.only_in_64bit()
-        && !reg_reg.only_in_64bit()
-    {
+    let w = match reg.or(reg_rm) {
+        Some(reg) => reg.size() == register::Size::QWord,
+        None => true,
+    };
+    let extended = |reg: Option<&Register>| reg.is_some_and(|reg| reg.only_in_64bit());
+    let (r, x, b) = (extended(reg), extended(reg_index), extended(reg_base));
+
+    if !(w || r || x || b) {
         return None;
     }
 
-    Some(Rex::new(
-        true,
-        reg_reg.only_in_64bit(),
-        false,
-        reg_rm.only_in_64bit(),
-    ))
+    Some(Rex::new(w, r, x, b))
+}
+
+// rsp and r12 can only be a base through SIB, and a missing base is encoded in SIB too
+fn needs_sib(mem: &Memory) -> bool {
+    match &mem.base {
+        _ if mem.index.is_some() => true,
+        Some(Register::Rip) => false,
+        Some(base) => base.number() == 0b100,
+        None => true,
+    }
 }
 
 fn encode_modrm(rm: &RM) -> ModRM {
     match rm {
-        RM::Memory(mem) => match &mem.base {
-            Some(base) => match &mem.disp {
-                None => match base {
-                    Register::R12 => ModRM::new(0b00, 0, 0b100),
-                    Register::R13 => ModRM::new(0b01, 0, 0b101),
-                    _ => ModRM::new(0b00, 0, base.number()),
-                },
-                Some(Displacement::Disp8(_)) => ModRM::new(0b01, 0, base.number()),
-                Some(Displacement::Disp32(_)) => match base {
-                    Register::Rip => ModRM::new(0b00, 0, base.number()),
-                    _ => ModRM::new(0b10, 0, base.number()),
-                },
-            },
-            None => match mem.disp {
-                None => panic!(),
-                Some(Displacement::Disp8(_)) => panic!(),
-                Some(Displacement::Disp32(_)) => ModRM::new(0b00, 0, 0b100),
-            },
-        },
+        RM::Memory(mem) => {
+            let modval = match (&mem.base, &mem.disp) {
+                (None, _) | (Some(Register::Rip), _) => 0b00,
+                // [rbp] and [r13] are only encodable with a displacement
+                (Some(Register::Rbp), None) | (Some(Register::R13), None) => 0b01,
+                (Some(_), None) => 0b00,
+                (Some(_), Some(Displacement::Disp8(_))) => 0b01,
+                (Some(_), Some(Displacement::Disp32(_))) => 0b10,
+            };
+            let rm = if needs_sib(mem) {
+                0b100
+            } else {
+                mem.base.as_ref().unwrap().number()
+            };
+            ModRM::new(modval, 0, rm)
+        }
         RM::Register(reg) => ModRM::new(0b11, 0, reg.number()),
     }
 }
 
 fn encode_sib(rm: &RM) -> Option<Sib> {
     match rm {
-        RM::Memory(Memory {
-            base: Some(Register::R12),
-            disp: None,
-        }) => Some(Sib::new(0, 0b100, 0b100)),
-        RM::Memory(Memory {
-            base: None,
-            disp: Some(disp),
-        }) => match disp {
-            Displacement::Disp8(_) => panic!(),
-            Displacement::Disp32(_) => Some(Sib::new(0, 0b100, 0b101)),
-        },
+        RM::Memory(mem) if needs_sib(mem) => {
+            let (scale, index) = match &mem.index {
+                Some(index) => (index.scale_bits(), index.reg.number()),
+                None => (0, 0b100),
+            };
+            let base = mem.base.as_ref().map_or(0b101, |base| base.number());
+            Some(Sib::new(scale, index, base))
+        }
         _ => None,
     }
 }
@@ -188,8 +190,24 @@ fn encode_sib(rm: &RM) -> Option<Sib> {
 fn encode_disp(rm: &RM) -> Option<Displacement> {
     match rm {
         RM::Memory(Memory {
+            base: None, disp, ..
+        })
+        | RM::Memory(Memory {
+            base: Some(Register::Rip),
+            disp,
+            ..
+        }) => Some(Displacement::Disp32(
+            disp.as_ref().map_or(0, |disp| disp.value()),
+        )),
+        RM::Memory(Memory {
+            base: Some(Register::Rbp),
+            disp: None,
+            ..
+        })
+        | RM::Memory(Memory {
             base: Some(Register::R13),
             disp: None,
+            ..
         }) => Some(Displacement::Disp8(0)),
         RM::Memory(Memory { disp, .. }) => disp.clone(),
         _ => None,
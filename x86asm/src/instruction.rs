@@ -1,10 +1,12 @@
+use std::fmt;
+
 use mnemonic::Mnemonic;
 use operand::Operand;
 
 pub mod mnemonic;
 pub mod operand;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Instruction {
     pub mnenomic: Mnemonic,
     pub operand1: Option<Operand>,
@@ -36,3 +38,17 @@ impl Instruction {
         }
     }
 }
+
+// Intel syntax, e.g. `mov rax, [rbp - 0x8]`
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.mnenomic)?;
+        if let Some(operand1) = &self.operand1 {
+            write!(f, " {}", operand1)?;
+        }
+        if let Some(operand2) = &self.operand2 {
+            write!(f, ", {}", operand2)?;
+        }
+        Ok(())
+    }
+}
@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Mnemonic {
     Add,
@@ -16,12 +18,15 @@ pub enum Mnemonic {
     Pop,
     Push,
     Ret,
+    Sar,
     Sete,
     Setg,
     Setge,
     Setl,
     Setle,
     Setne,
+    Shl,
+    Shr,
     Sub,
     Syscall,
     Xor,
@@ -54,15 +59,24 @@ impl Mnemonic {
             Pop => Unary,
             Push => Unary,
             Ret => Nullary,
+            Sar => Binary,
             Sete => Unary,
             Setg => Unary,
             Setge => Unary,
             Setl => Unary,
             Setle => Unary,
             Setne => Unary,
+            Shl => Binary,
+            Shr => Binary,
             Sub => Binary,
             Syscall => Nullary,
             Xor => Binary,
         }
     }
 }
+
+impl fmt::Display for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&format!("{:?}", self).to_lowercase())
+    }
+}
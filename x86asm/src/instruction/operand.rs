@@ -3,15 +3,28 @@ pub mod memory;
 pub mod offset;
 pub mod register;
 
+use std::fmt;
+
 use immediate::Immediate;
 use memory::Memory;
 use offset::Offset;
 use register::Register;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Operand {
     Immediate(Immediate),
     Register(Register),
     Memory(Memory),
     Offset(Offset),
 }
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Immediate(imm) => write!(f, "{}", imm),
+            Operand::Register(reg) => write!(f, "{}", reg),
+            Operand::Memory(mem) => write!(f, "{}", mem),
+            Operand::Offset(off) => write!(f, "{}", off),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Immediate {
     Imm8(i8),
@@ -12,3 +14,12 @@ impl Immediate {
         }
     }
 }
+
+impl fmt::Display for Immediate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Immediate::Imm8(value) => write!(f, "{}", value),
+            Immediate::Imm32(value) => write!(f, "{}", value),
+        }
+    }
+}
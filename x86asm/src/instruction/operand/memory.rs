@@ -1,11 +1,21 @@
+use std::fmt;
+
 use super::register::Register;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Memory {
     pub base: Option<Register>,
+    pub index: Option<Index>,
     pub disp: Option<Displacement>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Index {
+    pub reg: Register,
+    // one of 1, 2, 4 and 8
+    pub scale: u8,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Displacement {
     Disp8(i8),
@@ -16,6 +26,7 @@ impl Memory {
     pub fn new(base: Register, disp: Option<Displacement>) -> Self {
         Self {
             base: Some(base),
+            index: None,
             disp,
         }
     }
@@ -23,9 +34,34 @@ impl Memory {
     pub fn new_disp(disp: Displacement) -> Self {
         Self {
             base: None,
+            index: None,
             disp: Some(disp),
         }
     }
+
+    pub fn new_index(base: Option<Register>, index: Index, disp: Option<Displacement>) -> Self {
+        Self {
+            base,
+            index: Some(index),
+            disp,
+        }
+    }
+}
+
+impl Index {
+    pub fn new(reg: Register, scale: u8) -> Self {
+        Self { reg, scale }
+    }
+
+    pub fn scale_bits(&self) -> u8 {
+        match self.scale {
+            1 => 0b00,
+            2 => 0b01,
+            4 => 0b10,
+            8 => 0b11,
+            _ => panic!(),
+        }
+    }
 }
 
 impl Displacement {
@@ -35,4 +71,35 @@ impl Displacement {
             Displacement::Disp32(value) => value.to_le_bytes().to_vec(),
         }
     }
+
+    pub fn value(&self) -> i32 {
+        match self {
+            Displacement::Disp8(value) => *value as i32,
+            Displacement::Disp32(value) => *value,
+        }
+    }
+}
+
+// e.g. `[rbp - 0x8]` and `[rax + rcx*8]`
+impl fmt::Display for Memory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut terms = Vec::new();
+        if let Some(base) = &self.base {
+            terms.push(base.to_string());
+        }
+        if let Some(index) = &self.index {
+            terms.push(format!("{}*{}", index.reg, index.scale));
+        }
+        let mut address = terms.join(" + ");
+
+        let disp = self.disp.as_ref().map_or(0, |disp| disp.value() as i64);
+        if address.is_empty() {
+            address = format!("{:#x}", disp);
+        } else if disp < 0 {
+            address.push_str(&format!(" - {:#x}", -disp));
+        } else if disp > 0 {
+            address.push_str(&format!(" + {:#x}", disp));
+        }
+        write!(f, "[{}]", address)
+    }
 }
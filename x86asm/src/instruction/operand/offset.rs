@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Offset {
     Off8(i8),
@@ -12,3 +14,13 @@ impl Offset {
         }
     }
 }
+
+// relative to the end of the instruction, since the target address is not known here
+impl fmt::Display for Offset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Offset::Off8(value) => write!(f, "{:+}", value),
+            Offset::Off32(value) => write!(f, "{:+}", value),
+        }
+    }
+}
@@ -1,12 +1,14 @@
 extern crate x86asm;
 
+use eir::emulator::{io::CapturedIo, mmu::Perms, Emulator};
 use x86asm::{
-    decode, encode,
+    decode::{self, DecodeError, DecodeErrorKind},
+    encode,
     instruction::{
         mnemonic::Mnemonic,
         operand::{
             immediate::Immediate,
-            memory::{Displacement, Memory},
+            memory::{Displacement, Index, Memory},
             offset::Offset,
             register::Register,
             Operand,
@@ -216,6 +218,11 @@ fn binary_mr() {
         Operand::Memory(Memory::new(Register::R8, None)),
         Operand::Register(Register::Sil),
     ));
+    do_test(Instruction::new_binary(
+        Mnemonic::Mov,
+        Operand::Register(Register::Al),
+        Operand::Register(Register::Cl),
+    ));
 }
 
 #[test]
@@ -260,6 +267,51 @@ fn binary_rm() {
         Operand::Register(Register::Rax),
         Operand::Memory(Memory::new(Register::Rip, Some(Displacement::Disp32(0)))),
     ));
+    do_test(Instruction::new_binary(
+        Mnemonic::Add,
+        Operand::Register(Register::Rax),
+        Operand::Memory(Memory::new(Register::Rsp, Some(Displacement::Disp8(8)))),
+    ));
+}
+
+#[test]
+fn binary_rm_sib() {
+    do_test(Instruction::new_binary(
+        Mnemonic::Mov,
+        Operand::Register(Register::Rax),
+        Operand::Memory(Memory::new_index(
+            Some(Register::Rax),
+            Index::new(Register::Rcx, 8),
+            None,
+        )),
+    ));
+    do_test(Instruction::new_binary(
+        Mnemonic::Mov,
+        Operand::Register(Register::Rax),
+        Operand::Memory(Memory::new_index(
+            Some(Register::R8),
+            Index::new(Register::R9, 4),
+            Some(Displacement::Disp8(-8)),
+        )),
+    ));
+    do_test(Instruction::new_binary(
+        Mnemonic::Lea,
+        Operand::Register(Register::Rax),
+        Operand::Memory(Memory::new_index(
+            Some(Register::Rbp),
+            Index::new(Register::Rdx, 2),
+            Some(Displacement::Disp32(0x100)),
+        )),
+    ));
+    do_test(Instruction::new_binary(
+        Mnemonic::Mov,
+        Operand::Register(Register::Rax),
+        Operand::Memory(Memory::new_index(
+            None,
+            Index::new(Register::Rax, 1),
+            Some(Displacement::Disp32(16)),
+        )),
+    ));
 }
 
 #[test]
@@ -296,9 +348,153 @@ fn binary_rmi() {
     ));
 }
 
+#[test]
+fn binary_shift() {
+    do_test(Instruction::new_binary(
+        Mnemonic::Shl,
+        Operand::Register(Register::Rax),
+        Operand::Immediate(Immediate::Imm8(3)),
+    ));
+    do_test(Instruction::new_binary(
+        Mnemonic::Shr,
+        Operand::Register(Register::Eax),
+        Operand::Register(Register::Cl),
+    ));
+    do_test(Instruction::new_binary(
+        Mnemonic::Sar,
+        Operand::Memory(Memory::new(Register::R8, Some(Displacement::Disp8(8)))),
+        Operand::Register(Register::Cl),
+    ));
+
+    // a shift by one has no immediate
+    assert_eq!(
+        decode::decode(&[0x48, 0xd1, 0xe0]).unwrap(),
+        vec![Instruction::new_binary(
+            Mnemonic::Shl,
+            Operand::Register(Register::Rax),
+            Operand::Immediate(Immediate::Imm8(1)),
+        )]
+    );
+}
+
+#[test]
+fn display() {
+    let inst = Instruction::new_binary(
+        Mnemonic::Mov,
+        Operand::Register(Register::Rax),
+        Operand::Memory(Memory::new(Register::Rbp, Some(Displacement::Disp8(-8)))),
+    );
+    assert_eq!(inst.to_string(), "mov rax, [rbp - 0x8]");
+
+    let inst = Instruction::new_binary(
+        Mnemonic::Lea,
+        Operand::Register(Register::R8),
+        Operand::Memory(Memory::new_index(
+            Some(Register::Rax),
+            Index::new(Register::Rcx, 8),
+            Some(Displacement::Disp32(16)),
+        )),
+    );
+    assert_eq!(inst.to_string(), "lea r8, [rax + rcx*8 + 0x10]");
+
+    let inst = Instruction::new_unary(Mnemonic::Call, Operand::Offset(Offset::Off32(-5)));
+    assert_eq!(inst.to_string(), "call -5");
+    assert_eq!(
+        Instruction::new_nullary(Mnemonic::Syscall).to_string(),
+        "syscall"
+    );
+}
+
+#[test]
+fn decode_errors() {
+    assert_eq!(
+        decode::decode(&[0x0f, 0x0b]),
+        Err(DecodeError {
+            kind: DecodeErrorKind::UnknownOpcode,
+            offset: 0,
+            bytes: vec![0x0f, 0x0b],
+        })
+    );
+    // call with a truncated rel32
+    assert_eq!(
+        decode::decode(&[0xe8, 0x00]),
+        Err(DecodeError {
+            kind: DecodeErrorKind::UnexpectedEnd,
+            offset: 0,
+            bytes: vec![0xe8, 0x00],
+        })
+    );
+    // the offset is that of the failing instruction
+    assert_eq!(
+        decode::decode(&[0xc3, 0x06]),
+        Err(DecodeError {
+            kind: DecodeErrorKind::UnknownOpcode,
+            offset: 1,
+            bytes: vec![0x06],
+        })
+    );
+}
+
+// rsp is left pointing at the stack
+const STEP_REGISTERS: [Register; 15] = [
+    Register::Rax,
+    Register::Rcx,
+    Register::Rdx,
+    Register::Rbx,
+    Register::Rbp,
+    Register::Rsi,
+    Register::Rdi,
+    Register::R8,
+    Register::R9,
+    Register::R10,
+    Register::R11,
+    Register::R12,
+    Register::R13,
+    Register::R14,
+    Register::R15,
+];
+
+#[test]
+fn decode_random_bytes() {
+    // xorshift, so that failures are reproducible
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    for _ in 0..10000 {
+        let len = (next() % 16 + 1) as usize;
+        let code: Vec<u8> = (0..len).map(|_| next() as u8).collect();
+
+        let mut decoder = decode::Decoder::new(&code);
+        match decoder.decode_inst() {
+            Ok(_) => {
+                assert!(*decoder.pos() <= code.len());
+                // whatever decodes must run or fault, but never panic
+                let mut emu = Emulator::new(0x1000, 0x3000);
+                emu.io = Box::new(CapturedIo::default());
+                emu.mmu.map(0x1000, code.len(), Perms::RWX);
+                emu.mmu.map(0x2000, 0x1000, Perms::RW);
+                emu.mmu.poke(0x1000, &code).unwrap();
+                for reg in STEP_REGISTERS.iter() {
+                    emu.cpu.set_register64(reg, next());
+                }
+                emu.step();
+            }
+            Err(err) => {
+                assert_eq!(err.offset, 0);
+                assert!(code.starts_with(&err.bytes), "{:x?}: {}", code, err);
+            }
+        }
+    }
+}
+
 fn do_test(inst: Instruction) {
     let code = encode::encode(&inst);
-    let inst_disasm = &decode::decode(&code)[0];
+    let inst_disasm = &decode::decode(&code).unwrap()[0];
 
     assert_eq!(&inst, inst_disasm);
 }